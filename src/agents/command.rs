@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use nix::sys::signal::{kill, killpg, Signal};
+use nix::unistd::Pid;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::sync::mpsc;
+
+use super::traits::{AgentEvent, AgentProvider, AgentSession, OutputStream, SpawnContext};
+
+/// Provider backed by a local agent CLI such as `claude` or `codex`
+pub struct CommandProvider {
+    pub name: &'static str,
+    pub icon: &'static str,
+    pub description: &'static str,
+    /// Executable to launch
+    pub program: &'static str,
+    /// Arguments passed before everything else (e.g. a subcommand)
+    pub base_args: &'static [&'static str],
+    /// Flag used to select a model from the `model` option
+    pub model_flag: Option<&'static str>,
+    /// Flag placed right before the prompt, if the CLI needs one
+    pub prompt_flag: Option<&'static str>,
+    /// Whether the CLI survives SIGINT and moves on to its next input;
+    /// one-shot CLIs exit on it, so urgent messages pause them instead
+    pub interruptible: bool,
+}
+
+/// Variables agents keep even when their environment is restricted
+const BASE_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "TERM", "LANG", "LC_ALL", "TMPDIR",
+];
+
+impl CommandProvider {
+    /// Executable launched for an instance
+    fn program<'a>(&'a self, ctx: &'a SpawnContext) -> &'a str {
+        ctx.program.as_deref().unwrap_or(self.program)
+    }
+
+    /// Build the command line for an instance
+    pub fn command(&self, ctx: &SpawnContext) -> Command {
+        let mut command = Command::new(self.program(ctx));
+        command.args(self.base_args);
+        if let (Some(flag), Some(model)) = (self.model_flag, ctx.options.get("model")) {
+            command.arg(flag).arg(model);
+        }
+        if let Some(flag) = self.prompt_flag {
+            command.arg(flag);
+        }
+        command.arg(&ctx.prompt).current_dir(&ctx.worktree);
+        if let Some(allowlist) = &ctx.env_allowlist {
+            command
+                .env_clear()
+                .envs(std::env::vars_os().filter(|(name, _)| {
+                    name.to_str().is_some_and(|name| {
+                        BASE_ENV.contains(&name) || allowlist.iter().any(|allowed| allowed == name)
+                    })
+                }));
+        }
+        command.envs(ctx.env.iter().map(|(k, v)| (k, v)));
+        command
+    }
+}
+
+#[async_trait]
+impl AgentProvider for CommandProvider {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn icon(&self) -> &str {
+        self.icon
+    }
+
+    async fn spawn(&self, ctx: SpawnContext) -> Result<Box<dyn AgentSession>> {
+        let mut session = CommandSession::spawn(self.command(&ctx)).with_context(|| {
+            format!(
+                "Failed to launch {}: is `{}` installed and on PATH?",
+                ctx.agent_name,
+                self.program(&ctx)
+            )
+        })?;
+        session.interruptible = self.interruptible;
+        Ok(Box::new(session))
+    }
+}
+
+/// An agent running as a child process.
+///
+/// The child gets its own process group so pause/resume/terminate also reach
+/// any tools the agent spawned.
+pub struct CommandSession {
+    child: Child,
+    pid: Option<u32>,
+    stdin: Option<ChildStdin>,
+    events: mpsc::Receiver<AgentEvent>,
+    exit_code: Option<Option<i32>>,
+    reported_exit: bool,
+    interruptible: bool,
+}
+
+impl CommandSession {
+    /// Spawn `command` with piped stdio
+    pub fn spawn(mut command: Command) -> Result<Self> {
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true);
+
+        let mut child = command.spawn()?;
+        let (tx, events) = mpsc::channel(1024);
+
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_lines(stdout, OutputStream::Stdout, tx.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_lines(stderr, OutputStream::Stderr, tx));
+        }
+
+        Ok(Self {
+            pid: child.id(),
+            stdin: child.stdin.take(),
+            child,
+            events,
+            exit_code: None,
+            reported_exit: false,
+            interruptible: false,
+        })
+    }
+
+    fn signal(&self, signal: Signal) -> Result<()> {
+        let pid = self
+            .pid
+            .ok_or_else(|| anyhow::anyhow!("Agent process has no pid"))?;
+        killpg(Pid::from_raw(pid as i32), signal)
+            .with_context(|| format!("Failed to send {} to process group {}", signal, pid))?;
+        Ok(())
+    }
+}
+
+/// Forward every line of `reader` as an output event
+async fn forward_lines<R>(reader: R, stream: OutputStream, tx: mpsc::Sender<AgentEvent>)
+where
+    R: AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if tx.send(AgentEvent::Output { stream, line }).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[async_trait]
+impl AgentSession for CommandSession {
+    fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    fn can_interrupt(&self) -> bool {
+        self.interruptible
+    }
+
+    async fn send_message(&mut self, message: &str) -> Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Agent stdin is closed"))?;
+        stdin.write_all(message.as_bytes()).await?;
+        stdin.write_all(b"\n").await?;
+        stdin.flush().await?;
+        Ok(())
+    }
+
+    async fn poll_output(&mut self) -> Result<Option<AgentEvent>> {
+        if self.reported_exit {
+            return Ok(None);
+        }
+        if let Some(event) = self.events.recv().await {
+            return Ok(Some(event));
+        }
+
+        // Both output streams closed: the process is gone or about to be
+        let code = match self.exit_code {
+            Some(code) => code,
+            None => self.child.wait().await?.code(),
+        };
+        self.exit_code = Some(code);
+        self.reported_exit = true;
+        Ok(Some(AgentEvent::Exited { code }))
+    }
+
+    async fn interrupt(&mut self) -> Result<()> {
+        self.signal(Signal::SIGINT)
+    }
+
+    async fn pause(&mut self) -> Result<()> {
+        self.signal(Signal::SIGSTOP)
+    }
+
+    async fn resume(&mut self) -> Result<()> {
+        self.signal(Signal::SIGCONT)
+    }
+
+    async fn wake(&mut self) -> Result<()> {
+        let pid = self
+            .pid
+            .ok_or_else(|| anyhow::anyhow!("Agent process has no pid"))?;
+        kill(Pid::from_raw(pid as i32), Signal::SIGCONT)
+            .with_context(|| format!("Failed to send SIGCONT to process {}", pid))?;
+        Ok(())
+    }
+
+    async fn terminate(&mut self, grace: Duration) -> Result<Option<i32>> {
+        if let Some(code) = self.exit_code {
+            return Ok(code);
+        }
+        if let Ok(Some(status)) = self.child.try_wait() {
+            self.exit_code = Some(status.code());
+            return Ok(status.code());
+        }
+
+        self.stdin = None;
+        self.signal(Signal::SIGTERM)?;
+        // A stopped process cannot handle SIGTERM until it is continued
+        let _ = self.signal(Signal::SIGCONT);
+
+        let status = match tokio::time::timeout(grace, self.child.wait()).await {
+            Ok(status) => status?,
+            Err(_) => {
+                let _ = self.signal(Signal::SIGKILL);
+                self.child.wait().await?
+            }
+        };
+
+        self.exit_code = Some(status.code());
+        Ok(status.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shell(script: &str) -> CommandSession {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        CommandSession::spawn(command).expect("Should spawn sh")
+    }
+
+    #[tokio::test]
+    async fn test_captures_both_streams_and_exit_code() {
+        let mut session = shell("echo out; echo err >&2; exit 4");
+
+        let mut events = Vec::new();
+        while let Some(event) = session.poll_output().await.expect("Should poll") {
+            events.push(event);
+        }
+
+        assert!(events.contains(&AgentEvent::Output {
+            stream: OutputStream::Stdout,
+            line: "out".to_string()
+        }));
+        assert!(events.contains(&AgentEvent::Output {
+            stream: OutputStream::Stderr,
+            line: "err".to_string()
+        }));
+        assert_eq!(events.last(), Some(&AgentEvent::Exited { code: Some(4) }));
+    }
+
+    #[tokio::test]
+    async fn test_send_message_reaches_stdin() {
+        let mut session = shell("read line; echo got $line");
+        session
+            .send_message("hello")
+            .await
+            .expect("Should write stdin");
+
+        let event = session.poll_output().await.expect("Should poll");
+        assert_eq!(
+            event,
+            Some(AgentEvent::Output {
+                stream: OutputStream::Stdout,
+                line: "got hello".to_string()
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_restricted_environment() {
+        let provider = CommandProvider {
+            name: "env",
+            icon: "🧪",
+            description: "prints its environment",
+            program: "false",
+            base_args: &["-c"],
+            model_flag: None,
+            prompt_flag: None,
+            interruptible: false,
+        };
+        let ctx = SpawnContext {
+            agent_name: "env-1".to_string(),
+            worktree: std::env::temp_dir(),
+            prompt: "env | cut -d= -f1".to_string(),
+            options: Default::default(),
+            env: vec![("AGENTCREW_AGENT".to_string(), "env-1".to_string())],
+            program: Some("sh".to_string()),
+            env_allowlist: Some(vec!["ANTHROPIC_API_KEY".to_string()]),
+        };
+
+        let output = provider
+            .command(&ctx)
+            .output()
+            .await
+            .expect("Should run sh");
+        let names: Vec<_> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect();
+        assert!(names.contains(&"AGENTCREW_AGENT".to_string()));
+        // sh sets PWD, SHLVL and _ itself
+        let allowed = [
+            "AGENTCREW_AGENT",
+            "ANTHROPIC_API_KEY",
+            "PWD",
+            "OLDPWD",
+            "SHLVL",
+            "_",
+        ];
+        for name in &names {
+            assert!(
+                BASE_ENV.contains(&name.as_str()) || allowed.contains(&name.as_str()),
+                "{} leaked into the agent environment",
+                name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_terminate_escalates_to_kill() {
+        let mut session = shell("trap '' TERM; sleep 30");
+        session.pause().await.expect("Should pause");
+        session.resume().await.expect("Should resume");
+
+        let code = session
+            .terminate(Duration::from_millis(200))
+            .await
+            .expect("Should terminate");
+        assert_eq!(code, None);
+    }
+}
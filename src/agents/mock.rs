@@ -0,0 +1,438 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Component, Path};
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::traits::{AgentEvent, AgentProvider, AgentSession, OutputStream, SpawnContext};
+
+/// Delay between two script steps unless the script says otherwise
+const DEFAULT_STEP_DELAY_MS: u64 = 50;
+
+/// Local provider that replays a scripted transcript instead of calling a model.
+///
+/// The script comes from the `script=<path>` option (TOML, resolved against the
+/// worktree) or falls back to [`MockScript::default_transcript`]. Text fields may
+/// use `{agent}`, `{prompt}` and `{answer}` (the last answer received).
+pub struct MockProvider;
+
+#[async_trait]
+impl AgentProvider for MockProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn description(&self) -> &str {
+        "Scripted local agent for testing (no network access)"
+    }
+
+    fn icon(&self) -> &str {
+        "🧪"
+    }
+
+    async fn spawn(&self, ctx: SpawnContext) -> Result<Box<dyn AgentSession>> {
+        let mut script = match ctx.options.get("script") {
+            Some(path) => MockScript::load(&ctx.worktree.join(path))?,
+            None => MockScript::default_transcript(),
+        };
+        if let Some(delay) = ctx.options.get("delay_ms") {
+            script.delay_ms = Some(
+                delay
+                    .parse()
+                    .with_context(|| format!("Invalid delay_ms option '{}'", delay))?,
+            );
+        }
+
+        Ok(Box::new(MockSession::new(ctx, script)))
+    }
+}
+
+/// A scripted transcript for the mock provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockScript {
+    /// Delay between steps in milliseconds
+    pub delay_ms: Option<u64>,
+    pub steps: Vec<MockStep>,
+}
+
+/// One step of a [`MockScript`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MockStep {
+    /// Print a line to stdout
+    Say(String),
+    /// Print a line to stderr
+    Warn(String),
+    /// Write a file relative to the worktree
+    Write { path: String, content: String },
+    /// Ask a question and wait for the answer
+    Ask(String),
+    /// Pause for the given number of milliseconds
+    Sleep(u64),
+    /// Exit with the given code
+    Exit(i32),
+}
+
+impl MockScript {
+    /// Parse a script from TOML
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content).with_context(|| "Failed to parse mock script")
+    }
+
+    /// Load a script from a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mock script: {}", path.display()))?;
+        Self::from_toml(&content)
+    }
+
+    /// Transcript used when no script is given: edit a file, ask, apply the answer, exit
+    pub fn default_transcript() -> Self {
+        Self {
+            delay_ms: None,
+            steps: vec![
+                MockStep::Say("{agent} starting on: {prompt}".to_string()),
+                MockStep::Write {
+                    path: "MOCK_AGENT.md".to_string(),
+                    content: "# {agent}\n\nTask: {prompt}\n".to_string(),
+                },
+                MockStep::Ask("Proceed with the implementation? [y/n]".to_string()),
+                MockStep::Write {
+                    path: "MOCK_AGENT.md".to_string(),
+                    content: "# {agent}\n\nTask: {prompt}\nAnswer: {answer}\n".to_string(),
+                },
+                MockStep::Say("{agent} finished".to_string()),
+                MockStep::Exit(0),
+            ],
+        }
+    }
+}
+
+/// A running mock agent
+pub struct MockSession {
+    ctx: SpawnContext,
+    steps: VecDeque<MockStep>,
+    delay: Duration,
+    pending: VecDeque<AgentEvent>,
+    sleep_until: Option<Instant>,
+    last_answer: String,
+    awaiting_answer: bool,
+    paused: bool,
+    exited: bool,
+}
+
+impl MockSession {
+    fn new(ctx: SpawnContext, script: MockScript) -> Self {
+        Self {
+            ctx,
+            steps: script.steps.into(),
+            delay: Duration::from_millis(script.delay_ms.unwrap_or(DEFAULT_STEP_DELAY_MS)),
+            pending: VecDeque::new(),
+            sleep_until: None,
+            last_answer: String::new(),
+            awaiting_answer: false,
+            paused: false,
+            exited: false,
+        }
+    }
+
+    fn render(&self, template: &str) -> String {
+        template
+            .replace("{agent}", &self.ctx.agent_name)
+            .replace("{prompt}", &self.ctx.prompt)
+            .replace("{answer}", &self.last_answer)
+    }
+
+    fn output(&mut self, stream: OutputStream, line: String) {
+        self.pending.push_back(AgentEvent::Output { stream, line });
+    }
+
+    fn exit(&mut self, code: Option<i32>) {
+        self.steps.clear();
+        self.exited = true;
+        self.awaiting_answer = false;
+        self.pending.push_back(AgentEvent::Exited { code });
+    }
+
+    fn run_step(&mut self, step: MockStep) -> Result<()> {
+        match step {
+            MockStep::Say(text) => {
+                let line = self.render(&text);
+                self.output(OutputStream::Stdout, line);
+            }
+            MockStep::Warn(text) => {
+                let line = self.render(&text);
+                self.output(OutputStream::Stderr, line);
+            }
+            MockStep::Write { path, content } => {
+                let relative = Path::new(&path);
+                if relative.is_absolute()
+                    || relative
+                        .components()
+                        .any(|c| matches!(c, Component::ParentDir))
+                {
+                    anyhow::bail!("Mock script may only write inside the worktree: {}", path);
+                }
+                let target = self.ctx.worktree.join(relative);
+                if let Some(parent) = target.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&target, self.render(&content))
+                    .with_context(|| format!("Failed to write {}", target.display()))?;
+                self.output(OutputStream::Stdout, format!("wrote {}", path));
+            }
+            MockStep::Ask(text) => {
+                let text = self.render(&text);
+                self.awaiting_answer = true;
+                self.pending.push_back(AgentEvent::Question { text });
+            }
+            MockStep::Sleep(ms) => {
+                self.sleep_until = Some(Instant::now() + Duration::from_millis(ms));
+            }
+            MockStep::Exit(code) => self.exit(Some(code)),
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AgentSession for MockSession {
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+
+    fn can_interrupt(&self) -> bool {
+        true
+    }
+
+    async fn send_message(&mut self, message: &str) -> Result<()> {
+        if self.exited {
+            anyhow::bail!("{} has already exited", self.ctx.agent_name);
+        }
+        if self.awaiting_answer {
+            self.awaiting_answer = false;
+            self.last_answer = message.to_string();
+            self.output(
+                OutputStream::Stdout,
+                format!("answer received: {}", message),
+            );
+        } else {
+            self.output(
+                OutputStream::Stdout,
+                format!("message received: {}", message),
+            );
+        }
+        Ok(())
+    }
+
+    async fn poll_output(&mut self) -> Result<Option<AgentEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            if self.exited {
+                return Ok(None);
+            }
+            if self.paused || self.awaiting_answer {
+                // Woken up by the caller once resumed or answered
+                std::future::pending::<()>().await;
+            }
+
+            // Only mutate state after the await points so cancellation is harmless
+            if let Some(deadline) = self.sleep_until {
+                tokio::time::sleep_until(deadline).await;
+                self.sleep_until = None;
+            }
+            tokio::time::sleep(self.delay).await;
+
+            match self.steps.pop_front() {
+                Some(step) => self.run_step(step)?,
+                None => self.exit(Some(0)),
+            }
+        }
+    }
+
+    async fn interrupt(&mut self) -> Result<()> {
+        if self.exited {
+            anyhow::bail!("{} has already exited", self.ctx.agent_name);
+        }
+        // The current turn is whatever the script is sleeping on
+        if self.sleep_until.take().is_some() {
+            self.output(OutputStream::Stderr, "interrupted".to_string());
+        }
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> Result<()> {
+        self.paused = true;
+        Ok(())
+    }
+
+    async fn resume(&mut self) -> Result<()> {
+        self.paused = false;
+        Ok(())
+    }
+
+    async fn wake(&mut self) -> Result<()> {
+        self.resume().await
+    }
+
+    async fn terminate(&mut self, _grace: Duration) -> Result<Option<i32>> {
+        if !self.exited {
+            self.pending.clear();
+            self.exit(None);
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn context(dir: &Path, options: &[(&str, &str)]) -> SpawnContext {
+        SpawnContext {
+            agent_name: "mock-1".to_string(),
+            worktree: dir.to_path_buf(),
+            prompt: "add a feature".to_string(),
+            options: options
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+            env: Vec::new(),
+            program: None,
+            env_allowlist: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_transcript() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let mut session = MockProvider
+            .spawn(context(temp_dir.path(), &[("delay_ms", "0")]))
+            .await
+            .expect("Should spawn");
+
+        let mut question = None;
+        while question.is_none() {
+            match session.poll_output().await.expect("Should poll") {
+                Some(AgentEvent::Question { text }) => question = Some(text),
+                Some(_) => {}
+                None => panic!("Agent exited before asking"),
+            }
+        }
+        assert!(question.unwrap().contains("Proceed"));
+        assert!(temp_dir.path().join("MOCK_AGENT.md").exists());
+
+        session.send_message("yes").await.expect("Should answer");
+        let mut exit_code = None;
+        while let Some(event) = session.poll_output().await.expect("Should poll") {
+            if let AgentEvent::Exited { code } = event {
+                exit_code = code;
+            }
+        }
+
+        assert_eq!(exit_code, Some(0));
+        let content = std::fs::read_to_string(temp_dir.path().join("MOCK_AGENT.md")).unwrap();
+        assert!(content.contains("Task: add a feature"));
+        assert!(content.contains("Answer: yes"));
+    }
+
+    #[tokio::test]
+    async fn test_scripted_transcript() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        std::fs::write(
+            temp_dir.path().join("script.toml"),
+            r#"
+            delay_ms = 0
+
+            [[steps]]
+            warn = "careful"
+
+            [[steps]]
+            write = { path = "src/new.rs", content = "// {agent}" }
+
+            [[steps]]
+            exit = 3
+            "#,
+        )
+        .unwrap();
+
+        let mut session = MockProvider
+            .spawn(context(temp_dir.path(), &[("script", "script.toml")]))
+            .await
+            .expect("Should spawn");
+
+        let mut events = Vec::new();
+        while let Some(event) = session.poll_output().await.expect("Should poll") {
+            events.push(event);
+        }
+
+        assert_eq!(
+            events.first(),
+            Some(&AgentEvent::Output {
+                stream: OutputStream::Stderr,
+                line: "careful".to_string()
+            })
+        );
+        assert_eq!(events.last(), Some(&AgentEvent::Exited { code: Some(3) }));
+        let written = std::fs::read_to_string(temp_dir.path().join("src/new.rs")).unwrap();
+        assert_eq!(written, "// mock-1");
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_aborts_current_step() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let script = MockScript {
+            delay_ms: Some(0),
+            steps: vec![
+                MockStep::Say("working".to_string()),
+                MockStep::Sleep(60_000),
+                MockStep::Say("done".to_string()),
+            ],
+        };
+        let mut session = MockSession::new(context(temp_dir.path(), &[]), script);
+        assert!(matches!(
+            session.poll_output().await.unwrap(),
+            Some(AgentEvent::Output { line, .. }) if line == "working"
+        ));
+        // Now sleeping on the long step
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), session.poll_output())
+                .await
+                .is_err()
+        );
+
+        session.interrupt().await.expect("Should interrupt");
+        session.send_message("stop").await.expect("Should deliver");
+        let mut lines = Vec::new();
+        while let Some(event) = tokio::time::timeout(Duration::from_secs(5), session.poll_output())
+            .await
+            .expect("Interrupted agent should not keep sleeping")
+            .expect("Should poll")
+        {
+            if let AgentEvent::Output { line, .. } = event {
+                lines.push(line);
+            }
+        }
+        assert_eq!(lines, ["interrupted", "message received: stop", "done"]);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_writes_outside_worktree() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let script = MockScript {
+            delay_ms: Some(0),
+            steps: vec![MockStep::Write {
+                path: "../escape.txt".to_string(),
+                content: String::new(),
+            }],
+        };
+        let mut session = MockSession::new(context(temp_dir.path(), &[]), script);
+        assert!(session.poll_output().await.is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Agent provider abstractions
+
+mod command;
+mod mock;
+mod spec;
+mod traits;
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub use command::{CommandProvider, CommandSession};
+pub use mock::{MockProvider, MockScript, MockStep};
+pub use spec::{AgentInstance, AgentSpec, AgentSpecEntry, SpecError};
+pub use traits::{AgentEvent, AgentProvider, AgentSession, OutputStream, SpawnContext};
+
+/// Providers agentcrew knows how to launch
+pub const KNOWN_PROVIDERS: &[&str] = &["claude", "gpt", "jules", "mock"];
+
+/// Lookup table of available agent providers
+#[derive(Clone)]
+pub struct ProviderRegistry {
+    providers: BTreeMap<String, Arc<dyn AgentProvider>>,
+}
+
+impl ProviderRegistry {
+    /// Registry with every built-in provider
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            providers: BTreeMap::new(),
+        };
+        registry.register(Arc::new(CommandProvider {
+            name: "claude",
+            icon: "🧠",
+            description: "Anthropic Claude Code (local execution)",
+            program: "claude",
+            base_args: &[],
+            model_flag: Some("--model"),
+            prompt_flag: Some("-p"),
+            interruptible: false,
+        }));
+        registry.register(Arc::new(CommandProvider {
+            name: "gpt",
+            icon: "🤖",
+            description: "OpenAI GPT/Codex (API-based)",
+            program: "codex",
+            base_args: &["exec"],
+            model_flag: Some("--model"),
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        registry.register(Arc::new(CommandProvider {
+            name: "jules",
+            icon: "🌟",
+            description: "Google Jules/Gemini (GitHub integration)",
+            program: "gemini",
+            base_args: &[],
+            model_flag: Some("--model"),
+            prompt_flag: Some("-p"),
+            interruptible: false,
+        }));
+        registry.register(Arc::new(MockProvider));
+        registry
+    }
+
+    /// Add or replace a provider
+    pub fn register(&mut self, provider: Arc<dyn AgentProvider>) {
+        self.providers.insert(provider.name().to_string(), provider);
+    }
+
+    /// Find a provider by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn AgentProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    /// All providers ordered by name
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn AgentProvider>> {
+        self.providers.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_registry_matches_known_providers() {
+        let registry = ProviderRegistry::builtin();
+        let mut names: Vec<_> = registry.iter().map(|p| p.name().to_string()).collect();
+        let mut known: Vec<_> = KNOWN_PROVIDERS.iter().map(|n| n.to_string()).collect();
+        names.sort();
+        known.sort();
+        assert_eq!(names, known);
+        assert!(registry.get("mock").is_some());
+    }
+}
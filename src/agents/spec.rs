@@ -0,0 +1,458 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use super::KNOWN_PROVIDERS;
+use crate::config::AgentCrewConfig;
+
+/// Parsed `--agents` specification such as `claude:2@model=opus,gpt:1`
+///
+/// Grammar: `entry ("," entry)*` where
+/// `entry = provider [":" count] ("@" key "=" value)*`.
+/// A missing count means one instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentSpec {
+    input: String,
+    entries: Vec<AgentSpecEntry>,
+}
+
+/// One comma separated entry of an [`AgentSpec`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentSpecEntry {
+    /// Provider name, e.g. `claude`
+    pub provider: String,
+    /// Number of instances to launch
+    pub count: u32,
+    /// Options applied to every instance of this entry
+    pub options: BTreeMap<String, String>,
+    provider_span: Range<usize>,
+    span: Range<usize>,
+}
+
+/// A single agent instance to deploy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentInstance {
+    pub provider: String,
+    /// 1-based instance number per provider (`claude-1`, `claude-2`, ...)
+    pub instance_number: u32,
+    pub options: BTreeMap<String, String>,
+}
+
+impl AgentInstance {
+    /// Human friendly name such as `claude-1`
+    pub fn name(&self) -> String {
+        format!("{}-{}", self.provider, self.instance_number)
+    }
+}
+
+/// Error in an agent specification, pointing at the offending token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecError {
+    input: String,
+    span: Range<usize>,
+    message: String,
+}
+
+impl SpecError {
+    fn new(input: &str, span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            input: input.to_string(),
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Error message without the source excerpt
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Byte range of the offending token in the input
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let offset = self.input[..self.span.start].chars().count();
+        let width = self.input[self.span.clone()].chars().count().max(1);
+        writeln!(f, "Invalid agent spec: {}", self.message)?;
+        writeln!(f, "  {}", self.input)?;
+        write!(f, "  {}{}", " ".repeat(offset), "^".repeat(width))
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+impl AgentSpec {
+    /// Parse a specification without checking it against the configuration
+    pub fn parse(input: &str) -> Result<Self, SpecError> {
+        let mut entries = Vec::new();
+        let mut start = 0;
+
+        for token in input.split(',') {
+            let end = start + token.len();
+            entries.push(parse_entry(input, start..end)?);
+            start = end + 1;
+        }
+
+        Ok(Self {
+            input: input.to_string(),
+            entries,
+        })
+    }
+
+    /// Resolve the spec to deploy: the `--agents` value if given, otherwise
+    /// `default_agents` from the configuration. The result is validated,
+    /// counting the `live` agents per provider that are already running.
+    pub fn resolve(
+        cli_value: Option<&str>,
+        config: &AgentCrewConfig,
+        live: &BTreeMap<String, u32>,
+    ) -> Result<Self, SpecError> {
+        let spec = match cli_value {
+            Some(value) => Self::parse(value)?,
+            None => Self::parse(&config.default_agents.join(","))?,
+        };
+        spec.validate(config, live)?;
+        Ok(spec)
+    }
+
+    /// Check providers and instance counts against the configuration. The
+    /// `live` agents per provider count towards `max_instances` and
+    /// `max_agents` as well.
+    pub fn validate(
+        &self,
+        config: &AgentCrewConfig,
+        live: &BTreeMap<String, u32>,
+    ) -> Result<(), SpecError> {
+        let mut per_provider: BTreeMap<&str, u32> = live
+            .iter()
+            .map(|(provider, count)| (provider.as_str(), *count))
+            .collect();
+        let mut total = live
+            .values()
+            .fold(0u32, |total, count| total.saturating_add(*count));
+        let already = |count: u32| match count {
+            0 => String::new(),
+            count => format!(" ({} already running)", count),
+        };
+
+        for entry in &self.entries {
+            if !KNOWN_PROVIDERS.contains(&entry.provider.as_str()) {
+                return Err(SpecError::new(
+                    &self.input,
+                    entry.provider_span.clone(),
+                    format!(
+                        "unknown provider '{}' (expected one of: {})",
+                        entry.provider,
+                        KNOWN_PROVIDERS.join(", ")
+                    ),
+                ));
+            }
+
+            if !config.is_enabled(&entry.provider) {
+                return Err(SpecError::new(
+                    &self.input,
+                    entry.provider_span.clone(),
+                    format!(
+                        "provider '{}' is disabled ([agents.{}] enabled = false)",
+                        entry.provider, entry.provider
+                    ),
+                ));
+            }
+
+            let provider_total = per_provider.entry(&entry.provider).or_default();
+            *provider_total = provider_total.saturating_add(entry.count);
+            if let Some(cap) = config.max_instances(&entry.provider) {
+                if *provider_total > cap {
+                    return Err(SpecError::new(
+                        &self.input,
+                        entry.span.clone(),
+                        format!(
+                            "{} '{}' instances requested but max_instances is {}{}",
+                            provider_total,
+                            entry.provider,
+                            cap,
+                            already(live.get(&entry.provider).copied().unwrap_or(0))
+                        ),
+                    ));
+                }
+            }
+
+            total = total.saturating_add(entry.count);
+            if total > config.max_agents {
+                return Err(SpecError::new(
+                    &self.input,
+                    entry.span.clone(),
+                    format!(
+                        "{} agents requested but max_agents is {}{}",
+                        total,
+                        config.max_agents,
+                        already(live.values().sum())
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Entries in the order they were written
+    pub fn entries(&self) -> &[AgentSpecEntry] {
+        &self.entries
+    }
+
+    /// Total number of instances requested
+    pub fn total(&self) -> u32 {
+        self.entries.iter().map(|e| e.count).sum()
+    }
+
+    /// Requested instance count per provider
+    pub fn counts(&self) -> BTreeMap<String, u32> {
+        let mut counts = BTreeMap::new();
+        for entry in &self.entries {
+            *counts.entry(entry.provider.clone()).or_default() += entry.count;
+        }
+        counts
+    }
+
+    /// Expand the spec into numbered instances, e.g. `claude-1`, `claude-2`
+    pub fn instances(&self) -> Vec<AgentInstance> {
+        let mut next_number: BTreeMap<&str, u32> = BTreeMap::new();
+        let mut instances = Vec::new();
+
+        for entry in &self.entries {
+            for _ in 0..entry.count {
+                let number = next_number.entry(&entry.provider).or_insert(0);
+                *number += 1;
+                instances.push(AgentInstance {
+                    provider: entry.provider.clone(),
+                    instance_number: *number,
+                    options: entry.options.clone(),
+                });
+            }
+        }
+
+        instances
+    }
+}
+
+impl FromStr for AgentSpec {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for AgentSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.input)
+    }
+}
+
+/// Parse the entry at `range` of `input`
+fn parse_entry(input: &str, range: Range<usize>) -> Result<AgentSpecEntry, SpecError> {
+    let token = &input[range.clone()];
+
+    // Trim surrounding whitespace while keeping offsets into `input`
+    let start = range.start + (token.len() - token.trim_start().len());
+    let end = range.end - (token.len() - token.trim_end().len());
+    let trimmed = &input[start..end];
+
+    if trimmed.is_empty() {
+        return Err(SpecError::new(input, start..end, "empty agent entry"));
+    }
+
+    // Split off `@key=value` options
+    let mut parts = trimmed.split('@');
+    let head = parts.next().unwrap_or_default();
+    let mut options = BTreeMap::new();
+    let mut offset = start + head.len() + 1;
+    for option in parts {
+        let span = offset..offset + option.len();
+        offset += option.len() + 1;
+
+        let (key, value) = option.split_once('=').ok_or_else(|| {
+            SpecError::new(input, span.clone(), "option must look like key=value")
+        })?;
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(SpecError::new(
+                input,
+                span,
+                format!("invalid option name '{}'", key),
+            ));
+        }
+        if value.is_empty() {
+            return Err(SpecError::new(
+                input,
+                span,
+                format!("option '{}' needs a value", key),
+            ));
+        }
+        if options.insert(key.to_string(), value.to_string()).is_some() {
+            return Err(SpecError::new(
+                input,
+                span,
+                format!("option '{}' given twice", key),
+            ));
+        }
+    }
+
+    // Provider and optional count
+    let (provider, count) = match head.split_once(':') {
+        Some((provider, count_str)) => {
+            let count_start = start + provider.len() + 1;
+            let count_span = count_start..count_start + count_str.len();
+            let count = count_str.parse::<u32>().map_err(|_| {
+                SpecError::new(
+                    input,
+                    count_span.clone(),
+                    format!("invalid instance count '{}'", count_str),
+                )
+            })?;
+            if count == 0 {
+                return Err(SpecError::new(
+                    input,
+                    count_span,
+                    "instance count must be at least 1",
+                ));
+            }
+            (provider, count)
+        }
+        None => (head, 1),
+    };
+
+    let provider_span = start..start + provider.len();
+    if provider.is_empty() {
+        return Err(SpecError::new(
+            input,
+            provider_span,
+            "missing provider name",
+        ));
+    }
+
+    Ok(AgentSpecEntry {
+        provider: provider.to_string(),
+        count,
+        options,
+        provider_span,
+        span: start..end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_counts_and_options() {
+        let spec = AgentSpec::parse("claude:2@model=opus, gpt:1,jules").expect("Should parse");
+        assert_eq!(spec.total(), 4);
+        assert_eq!(
+            spec.entries()[0].options.get("model").map(String::as_str),
+            Some("opus")
+        );
+        assert_eq!(spec.entries()[2].count, 1);
+
+        let names: Vec<_> = spec.instances().iter().map(|i| i.name()).collect();
+        assert_eq!(names, ["claude-1", "claude-2", "gpt-1", "jules-1"]);
+    }
+
+    #[test]
+    fn test_repeated_provider_numbers_instances_sequentially() {
+        let spec = AgentSpec::parse("claude@model=opus,claude@model=sonnet").expect("Should parse");
+        let instances = spec.instances();
+        assert_eq!(instances[1].name(), "claude-2");
+        assert_eq!(instances[1].options["model"], "sonnet");
+        assert_eq!(spec.counts()["claude"], 2);
+    }
+
+    #[test]
+    fn test_errors_point_at_token() {
+        let err = AgentSpec::parse("claude:2,gpt:0").unwrap_err();
+        assert_eq!(err.span(), 13..14);
+        assert!(err.message().contains("at least 1"));
+
+        let err = AgentSpec::parse("claude:x").unwrap_err();
+        assert_eq!(err.span(), 7..8);
+
+        let err = AgentSpec::parse("claude,,gpt").unwrap_err();
+        assert!(err.message().contains("empty"));
+
+        let err = AgentSpec::parse("claude@model").unwrap_err();
+        assert_eq!(err.span(), 7..12);
+        assert!(err.to_string().ends_with("         ^^^^^"));
+    }
+
+    #[test]
+    fn test_validate_against_config() {
+        let mut config = AgentCrewConfig {
+            max_agents: 3,
+            ..Default::default()
+        };
+
+        let err =
+            AgentSpec::resolve(Some("claude:1,foo:1"), &config, &BTreeMap::new()).unwrap_err();
+        assert_eq!(err.span(), 9..12);
+        assert!(err.message().contains("unknown provider 'foo'"));
+
+        let err =
+            AgentSpec::resolve(Some("claude:2,gpt:2"), &config, &BTreeMap::new()).unwrap_err();
+        assert_eq!(err.span(), 9..14);
+        assert!(err.message().contains("max_agents is 3"));
+
+        config.agents.insert(
+            "gpt".to_string(),
+            crate::config::AgentProviderConfig {
+                max_instances: Some(1),
+                ..Default::default()
+            },
+        );
+        let err = AgentSpec::resolve(Some("gpt:1,gpt:1"), &config, &BTreeMap::new()).unwrap_err();
+        assert_eq!(err.span(), 6..11);
+
+        config.agents.insert(
+            "jules".to_string(),
+            crate::config::AgentProviderConfig {
+                enabled: false,
+                ..Default::default()
+            },
+        );
+        let err =
+            AgentSpec::resolve(Some("claude:1,jules:1"), &config, &BTreeMap::new()).unwrap_err();
+        assert_eq!(err.span(), 9..14);
+        assert!(err.message().contains("disabled"));
+
+        // Agents still running from an earlier deploy count towards the caps
+        let live = BTreeMap::from([("gpt".to_string(), 1), ("claude".to_string(), 1)]);
+        let err = AgentSpec::resolve(Some("gpt:1"), &config, &live).unwrap_err();
+        assert!(err
+            .message()
+            .ends_with("max_instances is 1 (1 already running)"));
+        let err = AgentSpec::resolve(Some("claude:2"), &config, &live).unwrap_err();
+        assert!(err
+            .message()
+            .contains("4 agents requested but max_agents is 3"));
+        AgentSpec::resolve(Some("claude:1"), &config, &live).expect("Should fit");
+    }
+
+    #[test]
+    fn test_falls_back_to_default_agents() {
+        let config = AgentCrewConfig {
+            default_agents: vec!["claude:1".to_string(), "gpt".to_string()],
+            ..Default::default()
+        };
+        let spec =
+            AgentSpec::resolve(None, &config, &BTreeMap::new()).expect("Should resolve defaults");
+        assert_eq!(spec.to_string(), "claude:1,gpt");
+        assert_eq!(spec.total(), 2);
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Everything a provider needs to start one agent instance
+#[derive(Debug, Clone)]
+pub struct SpawnContext {
+    /// Instance name such as `claude-1`
+    pub agent_name: String,
+    /// Worktree the agent works in
+    pub worktree: PathBuf,
+    /// Initial task for the agent
+    pub prompt: String,
+    /// Per-instance options from the agent spec (e.g. `model=opus`)
+    pub options: BTreeMap<String, String>,
+    /// Extra environment variables for the agent process
+    pub env: Vec<(String, String)>,
+    /// Executable replacing the provider's own (`[agents.<name>] command`)
+    pub program: Option<String>,
+    /// When set, the agent inherits only these variables and the basics
+    /// such as `PATH`; `env` is always added
+    pub env_allowlist: Option<Vec<String>>,
+}
+
+/// Output stream an agent line was written to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputStream {
+    /// Short tag used in logs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Stdout => "stdout",
+            Self::Stderr => "stderr",
+        }
+    }
+}
+
+/// Something an agent did that the orchestrator should know about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AgentEvent {
+    /// A line of output
+    Output { stream: OutputStream, line: String },
+    /// The agent is blocked on a question for the user
+    Question { text: String },
+    /// The agent exited; `code` is `None` when killed by a signal
+    Exited { code: Option<i32> },
+}
+
+/// A kind of agent that can be launched (Claude Code, Codex, ...)
+#[async_trait]
+pub trait AgentProvider: Send + Sync {
+    /// Provider name used in agent specs, e.g. `claude`
+    fn name(&self) -> &str;
+
+    /// One line description shown by `agentcrew list`
+    fn description(&self) -> &str;
+
+    /// Icon shown next to the provider name
+    fn icon(&self) -> &str {
+        "🤖"
+    }
+
+    /// Start an agent instance
+    async fn spawn(&self, ctx: SpawnContext) -> Result<Box<dyn AgentSession>>;
+}
+
+/// A running agent instance returned by [`AgentProvider::spawn`]
+#[async_trait]
+pub trait AgentSession: Send {
+    /// OS process id, if the agent runs as a separate process
+    fn pid(&self) -> Option<u32>;
+
+    /// Deliver a message (answer, brief, ...) to the agent
+    async fn send_message(&mut self, message: &str) -> Result<()>;
+
+    /// Wait for the next event. Returns `None` after the final
+    /// [`AgentEvent::Exited`] has been reported.
+    ///
+    /// Must be cancel safe so it can be used inside `tokio::select!`.
+    async fn poll_output(&mut self) -> Result<Option<AgentEvent>>;
+
+    /// Whether [`AgentSession::interrupt`] keeps the agent running. Urgent
+    /// messages pause agents that cannot be interrupted instead.
+    fn can_interrupt(&self) -> bool;
+
+    /// Abort the agent's current turn so an urgent message is handled next
+    async fn interrupt(&mut self) -> Result<()>;
+
+    /// Suspend the agent
+    async fn pause(&mut self) -> Result<()>;
+
+    /// Continue a paused agent
+    async fn resume(&mut self) -> Result<()>;
+
+    /// Continue only the agent process of a paused agent so it can read a
+    /// message, leaving the tools it started suspended
+    async fn wake(&mut self) -> Result<()>;
+
+    /// Stop the agent, forcefully once `grace` has elapsed. Returns the exit code.
+    async fn terminate(&mut self, grace: Duration) -> Result<Option<i32>>;
+}
@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+use super::CommandHandler;
+use crate::database::SessionStatus;
+use crate::git::adopt::{adopt, AdoptStrategy};
+use crate::git::diff::{branch_tip, short_id};
+use crate::git::GitUtils;
+
+impl CommandHandler {
+    /// Bring an agent's committed work into the current branch and complete
+    /// its session. Without a name, the winner marked in the TUI is adopted.
+    pub async fn adopt(name: Option<&str>, strategy: AdoptStrategy) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (session, agent) = match name {
+            Some(name) => Self::resolve_agent(&db, name).await?,
+            None => {
+                let session = db.latest_session().await?.ok_or_else(|| {
+                    anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first.")
+                })?;
+                let Some(winner) = session.winner_agent_id.as_deref() else {
+                    anyhow::bail!(
+                        "No winner marked in session {}. Name the agent to adopt or mark one in the TUI compare view.",
+                        session.slug()
+                    );
+                };
+                let agent = db.get_agent(winner).await?;
+                (session, agent)
+            }
+        };
+        let name = &agent.name();
+        println!("📥 Adopting {} ({})...", name, strategy);
+
+        if !agent.status.is_terminal() {
+            anyhow::bail!(
+                "{} is still {}. Wait for it to finish or dismiss it first.",
+                name,
+                agent.status
+            );
+        }
+        if GitUtils::has_uncommitted_changes()? {
+            anyhow::bail!("You have uncommitted changes. Commit or stash them before adopting.");
+        }
+        let current_branch =
+            GitUtils::get_current_branch().with_context(|| "Failed to determine current branch")?;
+        if let Some(base_branch) = session.base_branch.as_deref() {
+            if base_branch != current_branch {
+                println!(
+                    "  ⚠️  Adopting into {} although session {} forked from {}",
+                    current_branch,
+                    session.slug(),
+                    base_branch
+                );
+            }
+        }
+        let worktree = agent.worktree_path.as_deref().map(Path::new);
+        if let Some(worktree) = worktree.filter(|path| path.exists()) {
+            if GitUtils::is_dirty(worktree)? {
+                println!(
+                    "  ⚠️  {} has uncommitted changes; only committed work is adopted",
+                    name
+                );
+            }
+        }
+
+        let branch = agent
+            .branch_name
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("{} has no branch", name))?;
+        let repo = GitUtils::find_git_repository(&GitUtils::get_repository_root()?)?;
+        let theirs = branch_tip(&repo, branch)?;
+        let message = format!(
+            "Adopt {} from agentcrew session {}\n\n{}\n",
+            name,
+            session.slug(),
+            session.prompt
+        );
+
+        let head = adopt(&repo, &theirs, strategy, &message).with_context(|| {
+            format!(
+                "Adopting {} into {} aborted; the repository was left unchanged",
+                name, current_branch
+            )
+        })?;
+        db.set_session_winner(&session.id, Some(&agent.id)).await?;
+        db.update_session_status(&session.id, SessionStatus::Completed)
+            .await?;
+        db.close().await;
+
+        println!(
+            "  🎉 Adopted {} into {} ({}) at {}",
+            name,
+            current_branch,
+            strategy,
+            &short_id(head)
+        );
+        println!(
+            "  ✅ Session {} completed. Run 'agentcrew clean' to remove its worktrees.",
+            session.slug()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use chrono::Local;
+use git2::Oid;
+use std::path::Path;
+
+use super::CommandHandler;
+use crate::database::{DeliveryStatus, InteractionRecord, InteractionType};
+use crate::git::diff::short_id;
+use crate::process::communication::Request;
+use crate::worktree::{checkpoint, rollback};
+
+impl CommandHandler {
+    /// Commit everything in an agent's worktree on its branch
+    pub async fn checkpoint(name: &str, message: &str) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        let result = checkpoint(&db, &agent, message).await;
+        db.close().await;
+
+        let Some(checkpoint) = result? else {
+            println!("  ℹ️  Nothing to checkpoint in {}", name);
+            return Ok(());
+        };
+        let additions: usize = checkpoint
+            .changes
+            .iter()
+            .map(|change| change.additions)
+            .sum();
+        let deletions: usize = checkpoint
+            .changes
+            .iter()
+            .map(|change| change.deletions)
+            .sum();
+        println!(
+            "  📌 {} at {}: {} file{} changed, +{} -{}",
+            name,
+            &short_id(checkpoint.commit),
+            checkpoint.changes.len(),
+            if checkpoint.changes.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            additions,
+            deletions
+        );
+        for change in &checkpoint.changes {
+            println!(
+                "    {} {} +{} -{}",
+                change.kind.letter(),
+                change.path,
+                change.additions,
+                change.deletions
+            );
+        }
+        Ok(())
+    }
+
+    /// List the checkpoints recorded for an agent
+    pub async fn checkpoints(name: &str) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        let checkpoints = db.list_checkpoints(&agent.id).await?;
+        db.close().await;
+
+        if checkpoints.is_empty() {
+            println!("📭 No checkpoints of {} yet", name);
+            return Ok(());
+        }
+        println!("📌 {} checkpoint(s) of {}:", checkpoints.len(), name);
+        for record in &checkpoints {
+            let metadata = CheckpointMetadata::parse(record);
+            println!(
+                "  #{:<4} {} {} {:>3} file{} +{} -{}  {}",
+                record.id,
+                metadata.commit.get(..8).unwrap_or("-"),
+                record
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                metadata.files,
+                if metadata.files == 1 { " " } else { "s" },
+                metadata.lines_added,
+                metadata.lines_removed,
+                record.content
+            );
+        }
+        println!();
+        println!(
+            "💡 Roll back with: agentcrew rollback --agent {} --to <#id|commit>",
+            name
+        );
+        Ok(())
+    }
+
+    /// Hard-reset an agent's worktree to one of its checkpoints. A running
+    /// agent is paused during the reset and briefed afterwards, or relaunched
+    /// from scratch with `restart`.
+    pub async fn rollback(name: &str, to: &str, restart: bool) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        let checkpoints = db.list_checkpoints(&agent.id).await?;
+        let target = find_checkpoint(&checkpoints, to)?;
+        let metadata = CheckpointMetadata::parse(target);
+        let commit = Oid::from_str(&metadata.commit)
+            .with_context(|| format!("Checkpoint #{} has no commit", target.id))?;
+        let worktree = agent
+            .worktree_path
+            .as_deref()
+            .map(Path::new)
+            .filter(|path| path.exists())
+            .ok_or_else(|| anyhow::anyhow!("{} has no worktree", name))?;
+        let short = short_id(commit);
+
+        let running = !agent.status.is_terminal();
+        let mut client = if running || restart {
+            Some(Self::daemon_client().await?)
+        } else {
+            None
+        };
+        if let Some(client) = client.as_mut().filter(|_| running) {
+            let request = if restart {
+                Request::Dismiss {
+                    agent_id: agent.id.clone(),
+                }
+            } else {
+                Request::Pause {
+                    agent_id: agent.id.clone(),
+                }
+            };
+            client
+                .request(&request)
+                .await
+                .with_context(|| format!("Failed to stop {} before rolling back", name))?;
+        }
+
+        let reset = rollback(worktree, commit);
+        if reset.is_ok() {
+            let metadata =
+                serde_json::json!({ "commit": metadata.commit, "checkpoint": target.id });
+            db.record_interaction(
+                &agent.id,
+                &agent.session_id,
+                InteractionType::Status,
+                &format!("Rolled back to checkpoint #{} ({})", target.id, short),
+                Some(&metadata.to_string()),
+            )
+            .await?;
+            println!(
+                "  ⏪ {} rolled back to #{} {}: {}",
+                name, target.id, short, target.content
+            );
+        }
+
+        if let Some(client) = client.as_mut() {
+            if restart {
+                client
+                    .request(&Request::Restart {
+                        agent_id: agent.id.clone(),
+                    })
+                    .await?;
+                println!("  🔄 {} restarted", name);
+            } else if running {
+                client
+                    .request(&Request::Resume {
+                        agent_id: agent.id.clone(),
+                    })
+                    .await?;
+                if reset.is_ok() {
+                    let brief = format!(
+                        "Your worktree was rolled back to checkpoint {} (\"{}\"). \
+                         Later changes were discarded; check the files before you continue.",
+                        short, target.content
+                    );
+                    let delivered = client
+                        .request(&Request::Send {
+                            agent_id: agent.id.clone(),
+                            message: brief.clone(),
+                        })
+                        .await;
+                    let status = match delivered {
+                        Ok(_) => DeliveryStatus::Delivered,
+                        Err(_) => DeliveryStatus::Failed,
+                    };
+                    db.record_delivery(
+                        &agent.id,
+                        &agent.session_id,
+                        InteractionType::Brief,
+                        &brief,
+                        status,
+                        None,
+                    )
+                    .await?;
+                    match status {
+                        DeliveryStatus::Delivered => println!("  📨 {} briefed", name),
+                        DeliveryStatus::Failed => {
+                            println!("  ⚠️  Could not brief {} about the rollback", name)
+                        }
+                    }
+                }
+            }
+        }
+        db.close().await;
+        reset
+    }
+}
+
+/// Summary stored in a checkpoint interaction's metadata
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct CheckpointMetadata {
+    commit: String,
+    files: usize,
+    lines_added: usize,
+    lines_removed: usize,
+}
+
+impl CheckpointMetadata {
+    fn parse(record: &InteractionRecord) -> Self {
+        record
+            .metadata
+            .as_deref()
+            .and_then(|metadata| serde_json::from_str(metadata).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// Find a checkpoint by `#id`, id or commit prefix (at least 4 hex digits)
+fn find_checkpoint<'a>(
+    checkpoints: &'a [InteractionRecord],
+    reference: &str,
+) -> Result<&'a InteractionRecord> {
+    let by_id = reference
+        .strip_prefix('#')
+        .unwrap_or(reference)
+        .parse::<i64>()
+        .ok()
+        .and_then(|id| checkpoints.iter().find(|record| record.id == id));
+    if let Some(record) = by_id {
+        return Ok(record);
+    }
+
+    let prefix = reference.to_lowercase();
+    if prefix.len() >= 4 && prefix.chars().all(|c| c.is_ascii_hexdigit()) {
+        let matches: Vec<&InteractionRecord> = checkpoints
+            .iter()
+            .filter(|record| {
+                CheckpointMetadata::parse(record)
+                    .commit
+                    .starts_with(&prefix)
+            })
+            .collect();
+        match matches.as_slice() {
+            [record] => return Ok(record),
+            [] => {}
+            _ => anyhow::bail!("Commit prefix {} matches several checkpoints", reference),
+        }
+    }
+    anyhow::bail!(
+        "No checkpoint {} found. Run 'agentcrew checkpoints --agent <name>' to list them.",
+        reference
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn record(id: i64, commit: &str) -> InteractionRecord {
+        InteractionRecord {
+            id,
+            agent_id: "a".to_string(),
+            session_id: "s".to_string(),
+            interaction_type: InteractionType::Checkpoint,
+            content: format!("checkpoint {}", id),
+            metadata: Some(serde_json::json!({ "commit": commit, "files": 1 }).to_string()),
+            requires_response: false,
+            responded_at: None,
+            timestamp: Utc::now(),
+            delivery_status: None,
+        }
+    }
+
+    #[test]
+    fn test_find_checkpoint() {
+        let checkpoints = vec![record(3, "abcdef0123456789"), record(7, "abc9990000000000")];
+        assert_eq!(find_checkpoint(&checkpoints, "#7").unwrap().id, 7);
+        assert_eq!(find_checkpoint(&checkpoints, "3").unwrap().id, 3);
+        assert_eq!(find_checkpoint(&checkpoints, "ABCDEF").unwrap().id, 3);
+        assert!(find_checkpoint(&checkpoints, "abc").is_err());
+        assert!(find_checkpoint(&checkpoints, "abcd")
+            .unwrap()
+            .content
+            .ends_with('3'));
+        assert!(find_checkpoint(&checkpoints, "abc9").is_ok());
+        assert!(find_checkpoint(&checkpoints, "#9").is_err());
+    }
+}
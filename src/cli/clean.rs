@@ -0,0 +1,239 @@
+use anyhow::Result;
+use chrono::Utc;
+use git2::{Oid, Repository};
+
+use super::{agent_worktree, CommandHandler};
+use crate::config::AgentCrewConfig;
+use crate::database::{SessionRecord, SessionStatus};
+use crate::git::diff::branch_tip;
+use crate::git::GitUtils;
+use crate::process::exec::{is_running, stop_agent_commands};
+use crate::process::logs::{agent_log_path, parse_since, remove_log};
+use crate::process::DEFAULT_TERMINATE_GRACE;
+use crate::worktree::{AgentWorktree, WorktreeManager};
+
+impl CommandHandler {
+    /// Remove worktrees (and branches unless `keep_branches`) of finished
+    /// agents, then prune sessions older than the configured retention
+    pub async fn clean(
+        force: bool,
+        dry_run: bool,
+        older_than: Option<&str>,
+        keep_branches: bool,
+    ) -> Result<()> {
+        let config = AgentCrewConfig::load()?;
+        let keep_branches = keep_branches || config.worktree.keep_branches;
+        let logs_dir = AgentCrewConfig::logs_dir()?;
+        let cutoff = older_than
+            .map(|value| {
+                parse_since(value, Utc::now()).map_err(|_| {
+                    anyhow::anyhow!("Invalid --older-than value: {} (use e.g. 12h or 7d)", value)
+                })
+            })
+            .transpose()?;
+        let db = Self::open_database().await?;
+        let manager = WorktreeManager::new(
+            GitUtils::get_repository_root()?,
+            AgentCrewConfig::worktrees_dir()?,
+        );
+        let repo = manager.open()?;
+
+        let (mut removed, mut skipped) = (0, 0);
+        for session in db.list_sessions().await? {
+            let harvested: Vec<String> = db
+                .list_harvest_results(&session.id)
+                .await?
+                .into_iter()
+                .map(|result| result.agent_id)
+                .collect();
+            for agent in db.list_agents(&session.id).await? {
+                let has_branch = agent.branch_name.is_some() && !keep_branches;
+                if !agent.status.is_terminal() || (agent.worktree_path.is_none() && !has_branch) {
+                    continue;
+                }
+                if cutoff.is_some_and(|cutoff| agent.last_activity > cutoff) {
+                    continue;
+                }
+                let label = format!("{}/{}", session.slug(), agent.name());
+                let worktree = agent_worktree(&manager, &session, &agent);
+
+                if !force {
+                    if let Some(reason) = keep_reason(
+                        &repo,
+                        &session,
+                        &agent.id,
+                        &worktree,
+                        harvested.contains(&agent.id),
+                        keep_branches,
+                    )? {
+                        println!("  ⏭️  Skipping {}: {}", label, reason);
+                        skipped += 1;
+                        continue;
+                    }
+                }
+
+                let mut what = if keep_branches {
+                    format!("worktree {}", worktree.path.display())
+                } else {
+                    format!(
+                        "worktree {} and branch {}",
+                        worktree.path.display(),
+                        worktree.branch
+                    )
+                };
+                let commands = db
+                    .running_agent_exec_processes(&agent.id)
+                    .await?
+                    .iter()
+                    .filter(|process| is_running(process))
+                    .count();
+                if commands > 0 {
+                    what.push_str(&format!(
+                        " (stopping {} detached command{} first)",
+                        commands,
+                        if commands == 1 { "" } else { "s" }
+                    ));
+                }
+                let log = agent_log_path(&logs_dir, session.slug(), &agent.name());
+                let remove_logs = !config.worktree.preserve_logs && log.exists();
+                if remove_logs {
+                    what.push_str(" (and logs)");
+                }
+                if dry_run {
+                    println!("  🗑️  Would remove {}: {}", label, what);
+                } else {
+                    stop_agent_commands(&db, &agent.id, DEFAULT_TERMINATE_GRACE).await?;
+                    manager.remove(&repo, &worktree, !keep_branches)?;
+                    db.clear_agent_worktree(&agent.id, !keep_branches).await?;
+                    db.release_ports(&agent.id).await?;
+                    if remove_logs {
+                        remove_log(&log)?;
+                    }
+                    println!("  🗑️  Removed {}: {}", label, what);
+                }
+                removed += 1;
+            }
+        }
+
+        if dry_run {
+            println!(
+                "  🔍 Dry run: {} agent(s) would be cleaned, {} skipped",
+                removed, skipped
+            );
+            println!(
+                "  ℹ️  Finished sessions older than {} days would be pruned from the database",
+                config.retention_days
+            );
+        } else {
+            println!("  ✅ Cleaned {} agent(s), skipped {}", removed, skipped);
+            db.cleanup_old_sessions(config.retention_days.into())
+                .await?;
+        }
+        if skipped > 0 {
+            println!("💡 Use --force to remove skipped worktrees anyway");
+        }
+        db.close().await;
+        Ok(())
+    }
+}
+
+/// Why an agent's worktree must stay: uncommitted changes, or commits that
+/// were neither harvested nor adopted when the branch would be deleted
+fn keep_reason(
+    repo: &Repository,
+    session: &SessionRecord,
+    agent_id: &str,
+    worktree: &AgentWorktree,
+    harvested: bool,
+    keep_branches: bool,
+) -> Result<Option<String>> {
+    if worktree.path.exists() && GitUtils::is_dirty(&worktree.path)? {
+        return Ok(Some("uncommitted changes".to_string()));
+    }
+    // Adopting completes the session and only merges the winner's branch
+    let adopted = session.status == SessionStatus::Completed
+        && session.winner_agent_id.as_deref() == Some(agent_id);
+    if keep_branches || harvested || adopted {
+        return Ok(None);
+    }
+    let Ok(tip) = branch_tip(repo, &worktree.branch) else {
+        return Ok(None);
+    };
+    let base = session
+        .base_commit
+        .as_deref()
+        .and_then(|base| Oid::from_str(base).ok());
+    let ahead = match base {
+        Some(base) => repo.graph_ahead_behind(tip.id(), base)?.0,
+        None => 0,
+    };
+    if ahead > 0 {
+        return Ok(Some(format!(
+            "{} unharvested commit{} on {} (harvest or adopt it, or use --keep-branches)",
+            ahead,
+            if ahead == 1 { "" } else { "s" },
+            worktree.branch
+        )));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use crate::git::diff::tests::commit_files;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_keep_reason() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = Repository::init(temp_dir.path().join("repo")).expect("Should init repo");
+        let base = commit_files(&repo, "main", None, &[("README.md", Some("hi\n"))]);
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let mut session = db
+            .create_session("build it", r#"{"mock":1}"#, "main", &base.to_string())
+            .await
+            .unwrap();
+        let worktree = AgentWorktree {
+            agent_name: "mock-1".to_string(),
+            branch: "agentcrew/test/mock-1".to_string(),
+            path: temp_dir.path().join("missing"),
+            worktree_name: "agentcrew-test-mock-1".to_string(),
+        };
+
+        // No branch at all: nothing to lose
+        assert_eq!(
+            keep_reason(&repo, &session, "agent-1", &worktree, false, false).unwrap(),
+            None
+        );
+
+        commit_files(
+            &repo,
+            &worktree.branch,
+            Some(base),
+            &[("a.rs", Some("1\n"))],
+        );
+        let reason = keep_reason(&repo, &session, "agent-1", &worktree, false, false).unwrap();
+        assert!(reason.unwrap().starts_with("1 unharvested commit on"));
+        assert_eq!(
+            keep_reason(&repo, &session, "agent-1", &worktree, true, false).unwrap(),
+            None
+        );
+        assert_eq!(
+            keep_reason(&repo, &session, "agent-1", &worktree, false, true).unwrap(),
+            None
+        );
+        // Only the adopted agent's commits reached the base branch
+        session.status = SessionStatus::Completed;
+        session.winner_agent_id = Some("agent-2".to_string());
+        let reason = keep_reason(&repo, &session, "agent-1", &worktree, false, false).unwrap();
+        assert!(reason.unwrap().starts_with("1 unharvested commit on"));
+        assert_eq!(
+            keep_reason(&repo, &session, "agent-2", &worktree, false, false).unwrap(),
+            None
+        );
+    }
+}
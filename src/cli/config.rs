@@ -0,0 +1,51 @@
+use anyhow::{Context, Result};
+
+use super::CommandHandler;
+use crate::config::AgentCrewConfig;
+
+/// Lines longer than this do not push the origin column further right
+const MAX_ALIGNED_WIDTH: usize = 60;
+
+impl CommandHandler {
+    /// Print the effective configuration as TOML; with `origin`, one dotted
+    /// key per line followed by where its value was set
+    pub fn config_show(origin: bool) -> Result<()> {
+        let layered = AgentCrewConfig::load_layered()?;
+        for name in layered.ignored_env() {
+            eprintln!("⚠️  Ignoring {}: it does not name a config key", name);
+        }
+        if !origin {
+            let content = toml::to_string_pretty(&layered.config)
+                .with_context(|| "Failed to serialize config")?;
+            print!("{}", content);
+            return Ok(());
+        }
+
+        match AgentCrewConfig::global_config_path() {
+            Some(path) if path.exists() => println!("# global:  {}", path.display()),
+            Some(path) => println!("# global:  {} (not found)", path.display()),
+            None => println!("# global:  none ($HOME is not set)"),
+        }
+        println!(
+            "# project: {}",
+            AgentCrewConfig::config_file_path()?.display()
+        );
+
+        let lines: Vec<_> = layered
+            .entries()?
+            .into_iter()
+            .map(|(key, value, origin)| (format!("{} = {}", key, value), origin))
+            .collect();
+        // Long values such as harvest steps are not padded to
+        let width = lines
+            .iter()
+            .map(|(line, _)| line.len())
+            .filter(|&len| len <= MAX_ALIGNED_WIDTH)
+            .max()
+            .unwrap_or(0);
+        for (line, origin) in lines {
+            println!("{:<width$}  # {}", line, origin, width = width);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+
+use super::CommandHandler;
+use crate::process::communication::{Request, Response};
+use crate::process::exec::stop_agent_commands;
+use crate::process::DEFAULT_TERMINATE_GRACE;
+
+impl CommandHandler {
+    /// Suspend an agent's process group
+    pub async fn pause(name: &str) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        db.close().await;
+
+        let mut client = Self::daemon_client().await?;
+        client
+            .request(&Request::Pause { agent_id: agent.id })
+            .await?;
+
+        println!("  ⏸️  {} paused", name);
+        Ok(())
+    }
+
+    /// Continue a paused agent
+    pub async fn resume(name: &str) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        db.close().await;
+
+        let mut client = Self::daemon_client().await?;
+        client
+            .request(&Request::Resume { agent_id: agent.id })
+            .await?;
+
+        println!("  ▶️  {} resumed", name);
+        Ok(())
+    }
+
+    /// Stop an agent gracefully, killing it if it does not exit in time.
+    /// A finished agent still has its detached commands stopped and its
+    /// ports released.
+    pub async fn dismiss(name: &str) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+
+        if agent.status.is_terminal() {
+            println!("  ℹ️  {} already {}", name, agent.status);
+            let stopped = stop_agent_commands(&db, &agent.id, DEFAULT_TERMINATE_GRACE).await?;
+            if stopped > 0 {
+                println!("  🛑 Stopped {} detached command(s)", stopped);
+            }
+            if let Some(ports) = db.agent_ports(&agent.id).await? {
+                db.release_ports(&agent.id).await?;
+                println!(
+                    "  🔓 Released ports {}-{}",
+                    ports.port_start,
+                    ports.port_end()
+                );
+            }
+            db.close().await;
+            return Ok(());
+        }
+        db.close().await;
+
+        let mut client = Self::daemon_client().await?;
+        match client
+            .request(&Request::Dismiss { agent_id: agent.id })
+            .await?
+        {
+            Response::Dismissed {
+                exit_code: Some(code),
+            } => println!("  👋 {} dismissed (exit code {})", name, code),
+            _ => println!("  👋 {} dismissed", name),
+        }
+        Ok(())
+    }
+
+    /// Stop an agent if needed and launch it again in its worktree
+    pub async fn restart(name: &str) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        db.close().await;
+
+        let mut client = Self::daemon_client().await?;
+        if let Response::Launched { started, .. } = client
+            .request(&Request::Restart { agent_id: agent.id })
+            .await?
+        {
+            for agent in started {
+                match agent.pid {
+                    Some(pid) => println!("  🔄 {} restarted (pid {})", agent.name, pid),
+                    None => println!("  🔄 {} restarted", agent.name),
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use chrono::Local;
+
+use super::CommandHandler;
+use crate::agents::ProviderRegistry;
+use crate::config::AgentCrewConfig;
+use crate::process::communication::{Request, Response};
+use crate::process::{Daemon, DaemonClient, Supervisor};
+
+impl CommandHandler {
+    /// Run the daemon in the foreground, or stop the running one
+    pub async fn daemon(stop: bool) -> Result<()> {
+        let socket_path = AgentCrewConfig::socket_path()?;
+
+        if stop {
+            match DaemonClient::connect(&socket_path).await {
+                Ok(mut client) => {
+                    client.request(&Request::Shutdown).await?;
+                    println!("👋 Daemon stopping, agents are being dismissed");
+                }
+                Err(_) => println!("ℹ️  No daemon is running"),
+            }
+            return Ok(());
+        }
+
+        let db = Self::open_database().await?;
+        let supervisor = Supervisor::with_logs(
+            db.clone(),
+            ProviderRegistry::builtin(),
+            AgentCrewConfig::logs_dir()?,
+        );
+        let config = AgentCrewConfig::load()?;
+        supervisor.set_checkpoint_policies(config.checkpoint_policies());
+        supervisor.set_port_config(config.ports);
+        supervisor.set_provider_configs(config.agents);
+        Daemon::new(db.clone(), supervisor)
+            .serve(&socket_path)
+            .await?;
+        db.close().await;
+        Ok(())
+    }
+
+    /// Send instructions to every running agent
+    pub async fn brief(message: &str) -> Result<()> {
+        Self::deliver(Request::Brief {
+            message: message.to_string(),
+        })
+        .await
+    }
+
+    /// Send a priority message to every running agent. Urgent messages
+    /// interrupt (or pause) each agent's current turn while they are delivered.
+    pub async fn broadcast(message: &str, urgent: bool) -> Result<()> {
+        Self::deliver(Request::Broadcast {
+            message: message.to_string(),
+            urgent,
+        })
+        .await
+    }
+
+    async fn deliver(request: Request) -> Result<()> {
+        let mut client = Self::daemon_client().await?;
+        let Response::Delivered { deliveries } = client.request(&request).await? else {
+            anyhow::bail!("Unexpected response from daemon");
+        };
+
+        if deliveries.is_empty() {
+            println!("📭 No running agents to message");
+            return Ok(());
+        }
+        let mut failed = 0;
+        for delivery in &deliveries {
+            match &delivery.error {
+                None => println!("  📨 {} delivered", delivery.agent),
+                Some(error) => {
+                    failed += 1;
+                    println!("  ❌ {} failed: {}", delivery.agent, error)
+                }
+            }
+        }
+        println!(
+            "  📬 {} delivered, {} failed",
+            deliveries.len() - failed,
+            failed
+        );
+        Ok(())
+    }
+
+    /// Answer an agent waiting for input
+    pub async fn respond(name: &str, response: &str) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        db.close().await;
+
+        let mut client = Self::daemon_client().await?;
+        let answered = client
+            .request(&Request::Respond {
+                agent_id: agent.id,
+                message: response.to_string(),
+            })
+            .await?;
+
+        match answered {
+            Response::Answered { questions } if !questions.is_empty() => {
+                let ids: Vec<String> = questions.iter().map(|id| format!("#{}", id)).collect();
+                println!("  💬 Answered {} of {}", ids.join(", "), name);
+            }
+            _ => println!("  💬 Response sent to {} (no pending question)", name),
+        }
+        Ok(())
+    }
+
+    /// List unanswered agent questions
+    pub async fn questions() -> Result<()> {
+        let db = Self::open_database().await?;
+        let questions = db.pending_questions().await?;
+        if questions.is_empty() {
+            println!("✅ No pending questions");
+            db.close().await;
+            return Ok(());
+        }
+
+        println!("❓ {} pending question(s):", questions.len());
+        for question in &questions {
+            let agent = db.get_agent(&question.agent_id).await?;
+            println!(
+                "  #{} {} ({}, asked {}): {}",
+                question.id,
+                agent.name(),
+                agent.session_slug(),
+                question
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                question.content
+            );
+        }
+        println!();
+        println!("💡 Answer with: agentcrew respond --agent <name> \"<answer>\"");
+        db.close().await;
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+use super::CommandHandler;
+use crate::agents::AgentSpec;
+use crate::config::AgentCrewConfig;
+use crate::database::Database;
+use crate::git::GitUtils;
+use crate::process::communication::{Request, Response};
+use crate::worktree::WorktreeManager;
+
+impl CommandHandler {
+    /// Deploy agents into freshly provisioned worktrees
+    pub async fn deploy(agents: Option<&str>, prompt: &str) -> Result<()> {
+        let config = AgentCrewConfig::load()?;
+        let db = Database::new(&AgentCrewConfig::database_path()?).await?;
+        let mut live = BTreeMap::new();
+        for agent in db.list_unfinished_agents().await? {
+            *live.entry(agent.agent_type).or_insert(0) += 1;
+        }
+        let spec = AgentSpec::resolve(agents, &config, &live)?;
+        if agents.is_none() {
+            println!("  📋 Using default agents: {}", spec);
+        }
+
+        let repo_root = GitUtils::get_repository_root()?;
+        let base_branch =
+            GitUtils::get_current_branch().with_context(|| "Failed to determine current branch")?;
+        if GitUtils::has_uncommitted_changes()? {
+            println!(
+                "  ⚠️  Uncommitted changes are not visible to agents (worktrees fork from HEAD)"
+            );
+        }
+
+        let manager = WorktreeManager::new(repo_root, AgentCrewConfig::worktrees_dir()?);
+
+        let base_commit = manager.head_commit()?;
+
+        let agents_requested = serde_json::to_string(&spec.counts())?;
+        let session = db
+            .create_session(
+                prompt,
+                &agents_requested,
+                &base_branch,
+                &base_commit.to_string(),
+            )
+            .await?;
+
+        println!("  🌿 Creating git worktrees...");
+        let mut batch = manager.begin(session.slug(), base_commit)?;
+        let provisioned: Result<()> = async {
+            for instance in spec.instances() {
+                let agent = db
+                    .create_agent(
+                        &session.id,
+                        &instance.provider,
+                        instance.instance_number,
+                        &instance.options,
+                    )
+                    .await?;
+                let worktree = batch.create(&agent.name())?;
+                db.set_agent_worktree(&agent.id, &worktree.path, &worktree.branch)
+                    .await?;
+                println!("    ✅ {} → {}", agent.name(), worktree.branch);
+            }
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = provisioned {
+            if let Err(rollback_error) = batch.rollback() {
+                eprintln!("  ⚠️  Rollback incomplete: {:#}", rollback_error);
+            }
+            db.delete_session(&session.id).await?;
+            db.close().await;
+            return Err(e.context("Failed to provision agent worktrees, rolled back"));
+        }
+        batch.commit();
+
+        db.close().await;
+
+        println!("  🤖 Spawning agent processes...");
+        let mut client = Self::daemon_client().await?;
+        let Response::Launched { started, failed } = client
+            .request(&Request::Launch {
+                session_id: session.id.clone(),
+            })
+            .await?
+        else {
+            anyhow::bail!("Unexpected response from daemon");
+        };
+        for agent in &started {
+            match agent.pid {
+                Some(pid) => println!("    🚀 {} (pid {})", agent.name, pid),
+                None => println!("    🚀 {}", agent.name),
+            }
+        }
+        for agent in &failed {
+            println!(
+                "    ❌ {}: {}",
+                agent.agent,
+                agent.error.as_deref().unwrap_or("failed to start")
+            );
+        }
+
+        println!(
+            "  🎉 {} of {} agents deployed (session {})",
+            started.len(),
+            started.len() + failed.len(),
+            session.slug()
+        );
+        println!(
+            "💡 Agents keep running in the background. Use 'agentcrew status' to check on them."
+        );
+        Ok(())
+    }
+}
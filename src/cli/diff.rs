@@ -0,0 +1,174 @@
+use anyhow::{Context, Result};
+use git2::{Commit, Oid, Repository};
+use std::path::Path;
+
+use super::CommandHandler;
+use crate::database::{AgentRecord, SessionRecord};
+use crate::git::diff::{self, short_id, FileChange};
+use crate::git::GitUtils;
+
+/// Output format of `agentcrew diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffMode {
+    /// Per-file overlap summary against the common base
+    Summary,
+    /// `git diff --stat` of tip against tip
+    Stat,
+    /// Changed paths of tip against tip
+    NameOnly,
+    /// Full patch of tip against tip
+    Patch,
+}
+
+impl CommandHandler {
+    /// Compare the branches of two agents with each other and with the commit
+    /// they forked from
+    pub async fn diff(name1: &str, name2: &str, mode: DiffMode) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (session, left) = Self::resolve_agent(&db, name1).await?;
+        let (_, right) = Self::resolve_agent(&db, name2).await?;
+        db.close().await;
+
+        if left.id == right.id {
+            anyhow::bail!("Cannot compare {} with itself", name1);
+        }
+        for agent in [&left, &right] {
+            let dirty = agent
+                .worktree_path
+                .as_deref()
+                .map(Path::new)
+                .filter(|path| path.exists())
+                .map(GitUtils::is_dirty)
+                .transpose()?
+                .unwrap_or(false);
+            if dirty {
+                println!(
+                    "⚠️  {} has uncommitted changes; only committed work is compared",
+                    agent.name()
+                );
+            }
+        }
+
+        let repo_root = GitUtils::get_repository_root()?;
+        let repo = GitUtils::find_git_repository(&repo_root)?;
+        let left_tip = agent_tip(&repo, &left)?;
+        let right_tip = agent_tip(&repo, &right)?;
+        let tips = diff::diff_commits(&repo, &left_tip, &right_tip)?;
+
+        match mode {
+            DiffMode::Stat => print!("{}", diff::stat_text(&tips)?),
+            DiffMode::NameOnly => {
+                for change in diff::file_changes(&tips)? {
+                    println!("{}\t{}", change.kind.letter(), change.path);
+                }
+            }
+            DiffMode::Patch => print!("{}", diff::patch_text(&tips)?),
+            DiffMode::Summary => {
+                let base = base_commit(&repo, &session, &left_tip, &right_tip)?;
+                let left_changes =
+                    diff::file_changes(&diff::diff_commits(&repo, &base, &left_tip)?)?;
+                let right_changes =
+                    diff::file_changes(&diff::diff_commits(&repo, &base, &right_tip)?)?;
+
+                println!("📍 Base {}", short_id(base.id()));
+                for (agent, changes) in [(&left, &left_changes), (&right, &right_changes)] {
+                    println!("  {} {}", agent.name(), totals(changes));
+                }
+
+                let overlap = diff::overlap(&left_changes, &right_changes);
+                if !overlap.both.is_empty() {
+                    println!("\n🤝 Changed by both:");
+                    for (ours, theirs) in &overlap.both {
+                        println!(
+                            "  {:<40} {} +{} -{} | {} +{} -{}",
+                            ours.path,
+                            left.name(),
+                            ours.additions,
+                            ours.deletions,
+                            right.name(),
+                            theirs.additions,
+                            theirs.deletions
+                        );
+                    }
+                }
+                for (agent, only) in [(&left, &overlap.only_left), (&right, &overlap.only_right)] {
+                    if only.is_empty() {
+                        continue;
+                    }
+                    println!("\n📝 Only {}:", agent.name());
+                    for change in only.iter() {
+                        print_change(change);
+                    }
+                }
+                if overlap.both.is_empty()
+                    && overlap.only_left.is_empty()
+                    && overlap.only_right.is_empty()
+                {
+                    println!("\n📭 Neither agent has committed changes yet");
+                }
+
+                let tip_changes = diff::file_changes(&tips)?;
+                println!(
+                    "\n🔍 {} vs {}: {}",
+                    left.name(),
+                    right.name(),
+                    totals(&tip_changes)
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Commit at the tip of an agent's branch
+pub(super) fn agent_tip<'r>(repo: &'r Repository, agent: &AgentRecord) -> Result<Commit<'r>> {
+    let branch = agent
+        .branch_name
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("{} has no branch yet", agent.name()))?;
+    diff::branch_tip(repo, branch)
+}
+
+/// Commit the session forked from, or the merge base of both tips for
+/// sessions that did not record one
+fn base_commit<'r>(
+    repo: &'r Repository,
+    session: &SessionRecord,
+    left: &Commit<'_>,
+    right: &Commit<'_>,
+) -> Result<Commit<'r>> {
+    let oid = match session.base_commit.as_deref() {
+        Some(base) => Oid::from_str(base)
+            .with_context(|| format!("Invalid base commit of session {}", session.slug()))?,
+        None => repo
+            .merge_base(left.id(), right.id())
+            .with_context(|| "Agent branches share no history")?,
+    };
+    repo.find_commit(oid)
+        .with_context(|| format!("Base commit {} not found", oid))
+}
+
+/// One line of the per-file summary
+fn print_change(change: &FileChange) {
+    println!(
+        "  {} {:<40} +{} -{}",
+        change.kind.letter(),
+        change.path,
+        change.additions,
+        change.deletions
+    );
+}
+
+/// `N files changed, +A -D` for a set of changes
+fn totals(changes: &[FileChange]) -> String {
+    let files = changes.len();
+    let additions: usize = changes.iter().map(|change| change.additions).sum();
+    let deletions: usize = changes.iter().map(|change| change.deletions).sum();
+    format!(
+        "{} file{} changed, +{} -{}",
+        files,
+        if files == 1 { "" } else { "s" },
+        additions,
+        deletions
+    )
+}
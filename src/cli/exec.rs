@@ -0,0 +1,321 @@
+use anyhow::Result;
+use chrono::{Local, Utc};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use super::CommandHandler;
+use crate::config::AgentCrewConfig;
+use crate::database::{
+    AgentRecord, AgentStatus, Database, ExecProcessRecord, PortAllocation, SessionRecord,
+};
+use crate::process::exec::{is_running, run_prefixed, shell_command, spawn_detached, ExecOutcome};
+use crate::process::{signals, DEFAULT_TERMINATE_GRACE};
+
+/// An agent whose worktree a command runs in
+struct Target {
+    agent: AgentRecord,
+    worktree: PathBuf,
+    ports: Option<PortAllocation>,
+    env: Vec<(String, String)>,
+}
+
+impl CommandHandler {
+    /// Run a command in the worktree of every agent of the latest session, or
+    /// only `agent`'s, at most `jobs` at a time. With `detach`, start it in
+    /// the background and track it until 'agentcrew exec --stop'.
+    pub async fn exec(
+        agent: Option<&str>,
+        command: &[String],
+        jobs: Option<usize>,
+        detach: bool,
+    ) -> Result<()> {
+        if command.is_empty() {
+            anyhow::bail!("No command given. Usage: agentcrew exec --all -- <command>");
+        }
+        let command = shell_command(command);
+        let db = Self::open_database().await?;
+        let (session, targets) = Self::exec_targets(&db, agent).await?;
+
+        if !detach {
+            db.close().await;
+            return Self::exec_attached(targets, &command, jobs).await;
+        }
+        let result = Self::exec_detached(&db, &session, targets, &command).await;
+        db.close().await;
+        result
+    }
+
+    async fn exec_targets(
+        db: &Database,
+        agent: Option<&str>,
+    ) -> Result<(SessionRecord, Vec<Target>)> {
+        let ports = AgentCrewConfig::load()?.ports;
+        let (session, agents) = match agent {
+            Some(name) => {
+                let (session, agent) = Self::resolve_agent(db, name).await?;
+                (session, vec![agent])
+            }
+            None => {
+                let session = db.latest_session().await?.ok_or_else(|| {
+                    anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first.")
+                })?;
+                let agents = db.list_agents(&session.id).await?;
+                (session, agents)
+            }
+        };
+
+        let mut targets = Vec::new();
+        for agent in agents {
+            if agent.status == AgentStatus::Dismissed {
+                println!("  ⏭️  Skipping {}: dismissed", agent.name());
+                continue;
+            }
+            let worktree = agent.worktree_path.as_deref().map(PathBuf::from);
+            let Some(worktree) = worktree.filter(|path| path.exists()) else {
+                println!("  ⏭️  Skipping {}: no worktree", agent.name());
+                continue;
+            };
+            let mut env = vec![
+                ("AGENTCREW_AGENT".to_string(), agent.name()),
+                ("AGENTCREW_SESSION".to_string(), session.id.clone()),
+            ];
+            // A finished agent keeps the ports it had but gets no new ones
+            let ports = if agent.status.is_terminal() {
+                db.agent_ports(&agent.id).await?
+            } else {
+                Some(
+                    db.allocate_ports(&session.id, &agent.id, ports.base, ports.block_size)
+                        .await?,
+                )
+            };
+            if let Some(ports) = &ports {
+                env.extend(ports.env());
+            }
+            targets.push(Target {
+                agent,
+                worktree,
+                ports,
+                env,
+            });
+        }
+        if targets.is_empty() {
+            anyhow::bail!("No agent worktrees in session {}", session.slug());
+        }
+        Ok((session, targets))
+    }
+
+    async fn exec_attached(targets: Vec<Target>, command: &str, jobs: Option<usize>) -> Result<()> {
+        let jobs = jobs
+            .or_else(|| std::thread::available_parallelism().ok().map(usize::from))
+            .unwrap_or(1)
+            .max(1);
+        let width = targets
+            .iter()
+            .map(|target| target.agent.name().len())
+            .max()
+            .unwrap_or(0);
+        let names: Vec<String> = targets.iter().map(|target| target.agent.name()).collect();
+
+        let limit = Arc::new(Semaphore::new(jobs));
+        let mut runs = JoinSet::new();
+        for (index, target) in targets.into_iter().enumerate() {
+            let limit = limit.clone();
+            let command = command.to_string();
+            runs.spawn(async move {
+                let _permit = limit.acquire_owned().await;
+                let prefix = format!("[{:<width$}]", target.agent.name());
+                let outcome = run_prefixed(&command, &target.worktree, &target.env, &prefix).await;
+                (index, outcome)
+            });
+        }
+
+        let mut outcomes: Vec<Option<Result<ExecOutcome>>> = names.iter().map(|_| None).collect();
+        while let Some(joined) = runs.join_next().await {
+            let (index, outcome) = joined?;
+            outcomes[index] = Some(outcome);
+        }
+
+        println!("\n📊 Summary: {}", command);
+        println!(
+            "     {:<width$}  {:<6}  Time",
+            "Agent",
+            "Exit",
+            width = width.max(5)
+        );
+        let mut failed = 0;
+        for (name, outcome) in names.iter().zip(outcomes) {
+            let (passed, row) = match outcome {
+                Some(Ok(outcome)) => {
+                    let exit = outcome
+                        .exit_code
+                        .map_or("signal".to_string(), |code| code.to_string());
+                    (
+                        outcome.passed(),
+                        format!("{:<6}  {:.1}s", exit, outcome.duration.as_secs_f64()),
+                    )
+                }
+                Some(Err(e)) => (false, format!("{:#}", e)),
+                None => (false, "not run".to_string()),
+            };
+            if !passed {
+                failed += 1;
+            }
+            println!(
+                "  {} {:<width$}  {}",
+                if passed { "✅" } else { "❌" },
+                name,
+                row,
+                width = width.max(5)
+            );
+        }
+
+        if failed > 0 {
+            anyhow::bail!("{} of {} runs failed", failed, names.len());
+        }
+        Ok(())
+    }
+
+    async fn exec_detached(
+        db: &Database,
+        session: &SessionRecord,
+        targets: Vec<Target>,
+        command: &str,
+    ) -> Result<()> {
+        let exec_logs = AgentCrewConfig::logs_dir()?
+            .join(session.slug())
+            .join("exec");
+        let stamp = Local::now().format("%Y%m%d-%H%M%S");
+        for target in &targets {
+            let name = target.agent.name();
+            let log_path = exec_logs.join(format!("{}-{}.log", name, stamp));
+            let pid = spawn_detached(command, &target.worktree, &target.env, &log_path)?;
+            let process = db
+                .record_exec_process(
+                    &session.id,
+                    &target.agent.id,
+                    command,
+                    pid,
+                    signals::start_time(pid),
+                    &log_path,
+                )
+                .await?;
+            match &target.ports {
+                Some(ports) => println!(
+                    "  🚀 #{} {}: started pid {} on port {} → {}",
+                    process.id,
+                    name,
+                    pid,
+                    ports.port_start,
+                    log_path.display()
+                ),
+                None => println!(
+                    "  🚀 #{} {}: started pid {} → {}",
+                    process.id,
+                    name,
+                    pid,
+                    log_path.display()
+                ),
+            }
+        }
+        println!("💡 Stop with 'agentcrew exec --all --stop'; list with 'agentcrew exec --list'");
+        Ok(())
+    }
+
+    /// List the detached commands of the latest session
+    pub async fn exec_list() -> Result<()> {
+        let db = Self::open_database().await?;
+        let session = db
+            .latest_session()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first."))?;
+        let agents = db.list_agents(&session.id).await?;
+        let processes = db.running_exec_processes(&session.id).await?;
+        if processes.is_empty() {
+            println!("📭 No detached commands in session {}", session.slug());
+        }
+        for process in &processes {
+            let name = agent_name(&agents, process);
+            let alive = is_running(process);
+            if !alive {
+                db.mark_exec_stopped(process.id).await?;
+            }
+            let minutes = (Utc::now() - process.started_at).num_minutes();
+            println!(
+                "  {} #{} {:<12} pid {:<7} {:<8} {}",
+                if alive { "🟢" } else { "⚪" },
+                process.id,
+                name,
+                process.pid,
+                if alive {
+                    format!("{}m", minutes)
+                } else {
+                    "exited".to_string()
+                },
+                process.command
+            );
+            println!("       {}", process.log_path);
+        }
+        db.close().await;
+        Ok(())
+    }
+
+    /// Stop the detached commands of the latest session, or only `agent`'s
+    pub async fn exec_stop(agent: Option<&str>) -> Result<()> {
+        let db = Self::open_database().await?;
+        let (session, agent_id) = match agent {
+            Some(name) => {
+                let (session, agent) = Self::resolve_agent(&db, name).await?;
+                (session, Some(agent.id))
+            }
+            None => {
+                let session = db.latest_session().await?.ok_or_else(|| {
+                    anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first.")
+                })?;
+                (session, None)
+            }
+        };
+
+        let agents = db.list_agents(&session.id).await?;
+        let processes: Vec<_> = db
+            .running_exec_processes(&session.id)
+            .await?
+            .into_iter()
+            .filter(|process| agent_id.as_ref().is_none_or(|id| *id == process.agent_id))
+            .collect();
+        if processes.is_empty() {
+            println!("📭 No detached commands to stop");
+        }
+        for process in &processes {
+            let pid = process.pid as u32;
+            let outcome = if is_running(process) {
+                match signals::terminate(pid, DEFAULT_TERMINATE_GRACE).await? {
+                    true => "killed",
+                    false => "stopped",
+                }
+            } else {
+                "had already exited"
+            };
+            db.mark_exec_stopped(process.id).await?;
+            println!(
+                "  🛑 #{} {}: {} (pid {}) {}",
+                process.id,
+                agent_name(&agents, process),
+                process.command,
+                pid,
+                outcome
+            );
+        }
+        db.close().await;
+        Ok(())
+    }
+}
+
+/// Name of the agent a detached command runs for
+fn agent_name(agents: &[AgentRecord], process: &ExecProcessRecord) -> String {
+    agents
+        .iter()
+        .find(|agent| agent.id == process.agent_id)
+        .map_or_else(|| process.agent_id.clone(), |agent| agent.name())
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use chrono::Local;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+use super::CommandHandler;
+use crate::config::AgentCrewConfig;
+use crate::database::AgentRecord;
+use crate::process::logs::{agent_log_path, read_entries, LogEntry, LogTail};
+
+/// How often logs and agent statuses are polled
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Consecutive polls an agent must stay in a terminal status before following
+/// stops, so the brief `dismissed` state during a restart is ignored
+const TERMINAL_POLLS: u32 = 5;
+
+/// Lines of history printed per agent before following
+const HISTORY_LINES: usize = 10;
+
+/// ANSI colors cycled through for agent prefixes
+const PREFIX_COLORS: [&str; 6] = ["36", "33", "35", "32", "34", "31"];
+
+/// One followed agent
+struct Followed {
+    agent: AgentRecord,
+    prefix: String,
+    tail: LogTail,
+    terminal_polls: u32,
+}
+
+impl CommandHandler {
+    /// Stream an agent's output live until it stops; `--all` interleaves the
+    /// output of every agent in the current session
+    pub async fn follow(name: Option<&str>, all: bool) -> Result<()> {
+        let db = Self::open_database().await?;
+        let agents = match (name, all) {
+            (_, true) => {
+                let session = db.latest_session().await?.ok_or_else(|| {
+                    anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first.")
+                })?;
+                db.list_agents(&session.id).await?
+            }
+            (Some(name), false) => vec![Self::resolve_agent(&db, name).await?.1],
+            (None, false) => anyhow::bail!("Specify an agent to follow or use --all"),
+        };
+        if agents.is_empty() {
+            println!("📭 No agents to follow");
+            db.close().await;
+            return Ok(());
+        }
+
+        let logs_dir = AgentCrewConfig::logs_dir()?;
+        let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        let mut history = Vec::new();
+        let mut followed = Vec::new();
+        for (index, agent) in agents.into_iter().enumerate() {
+            let path = agent_log_path(&logs_dir, agent.session_slug(), &agent.name());
+            let prefix = match (all, color) {
+                (false, _) => String::new(),
+                (true, false) => format!("[{}] ", agent.name()),
+                (true, true) => format!(
+                    "\x1b[{}m[{}]\x1b[0m ",
+                    PREFIX_COLORS[index % PREFIX_COLORS.len()],
+                    agent.name()
+                ),
+            };
+
+            let entries = read_entries(&path).unwrap_or_default();
+            let skip = entries.len().saturating_sub(HISTORY_LINES);
+            history.extend(
+                entries
+                    .into_iter()
+                    .skip(skip)
+                    .map(|entry| (entry, followed.len())),
+            );
+            followed.push(Followed {
+                tail: LogTail::from_end(&path),
+                agent,
+                prefix,
+                terminal_polls: 0,
+            });
+        }
+
+        history.sort_by_key(|(entry, _)| entry.timestamp);
+        for (entry, index) in &history {
+            print_entry(&followed[*index].prefix, entry);
+        }
+
+        let mut interval = tokio::time::interval(FOLLOW_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+
+            let mut batch = Vec::new();
+            for (index, followed) in followed.iter_mut().enumerate() {
+                batch.extend(
+                    followed
+                        .tail
+                        .poll()?
+                        .into_iter()
+                        .map(|entry| (entry, index)),
+                );
+            }
+            batch.sort_by_key(|(entry, _)| entry.timestamp);
+            for (entry, index) in &batch {
+                print_entry(&followed[*index].prefix, entry);
+            }
+
+            let mut running = 0;
+            for followed in followed.iter_mut() {
+                if followed.terminal_polls >= TERMINAL_POLLS {
+                    continue;
+                }
+                let status = db.get_agent(&followed.agent.id).await?.status;
+                if !status.is_terminal() {
+                    followed.terminal_polls = 0;
+                    running += 1;
+                    continue;
+                }
+
+                followed.terminal_polls += 1;
+                if followed.terminal_polls < TERMINAL_POLLS {
+                    running += 1;
+                } else {
+                    for entry in followed.tail.poll()? {
+                        print_entry(&followed.prefix, &entry);
+                    }
+                    println!("🏁 {} {}", followed.agent.name(), status);
+                }
+            }
+            if running == 0 {
+                break;
+            }
+        }
+
+        db.close().await;
+        Ok(())
+    }
+}
+
+/// Print one followed log line
+fn print_entry(prefix: &str, entry: &LogEntry) {
+    println!(
+        "{}{} [{}] {}",
+        prefix,
+        entry.timestamp.with_timezone(&Local).format("%H:%M:%S"),
+        entry.stream,
+        entry.text
+    );
+}
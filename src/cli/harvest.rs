@@ -0,0 +1,135 @@
+use anyhow::Result;
+use git2::Oid;
+
+use super::CommandHandler;
+use crate::config::AgentCrewConfig;
+use crate::database::{AgentStatus, HarvestResultRecord};
+use crate::git::diff::short_id;
+use crate::harvest::{rank, Harvester};
+
+impl CommandHandler {
+    /// Run the verification pipeline in every completed agent's worktree and
+    /// rank the agents by the results
+    pub async fn harvest() -> Result<()> {
+        let config = AgentCrewConfig::load()?;
+        let harvester = Harvester::new(&config.harvest)?;
+        let db = Self::open_database().await?;
+        let session = db
+            .latest_session()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first."))?;
+
+        let agents = db.list_agents(&session.id).await?;
+        for agent in &agents {
+            if agent.status != AgentStatus::Completed {
+                println!("  ⏭️  Skipping {} ({})", agent.name(), agent.status);
+            }
+        }
+        let completed: Vec<_> = agents
+            .iter()
+            .filter(|agent| agent.status == AgentStatus::Completed)
+            .collect();
+        if completed.is_empty() {
+            println!(
+                "📭 No completed agents to harvest in session {}",
+                session.slug()
+            );
+            db.close().await;
+            return Ok(());
+        }
+
+        let steps: Vec<&str> = config
+            .harvest
+            .steps
+            .iter()
+            .map(|step| step.name.as_str())
+            .collect();
+        println!(
+            "🌾 Harvesting {} agent{} of session {} ({})",
+            completed.len(),
+            if completed.len() == 1 { "" } else { "s" },
+            session.slug(),
+            steps.join(", ")
+        );
+        for agent in completed {
+            match harvester.harvest_agent(&db, agent).await {
+                Ok(results) => {
+                    let cells: Vec<String> = results
+                        .iter()
+                        .map(|result| format!("{} {}", result.step, cell(result)))
+                        .collect();
+                    let head = results
+                        .first()
+                        .and_then(|result| result.head_commit.as_deref())
+                        .and_then(|commit| Oid::from_str(commit).ok())
+                        .map_or("-".to_string(), short_id);
+                    println!("  🔬 {:<12} {}  {}", agent.name(), head, cells.join("  "));
+                }
+                Err(e) => println!("  ❌ {:<12} {:#}", agent.name(), e),
+            }
+        }
+
+        let results = db.latest_harvest(&session.id).await?;
+        db.close().await;
+        let ranking = rank(&agents, &results);
+        if ranking.is_empty() {
+            return Ok(());
+        }
+
+        let name_width = ranking
+            .iter()
+            .map(|score| score.agent.name().len())
+            .max()
+            .unwrap_or(0)
+            .max(5);
+        let widths: Vec<usize> = steps.iter().map(|step| step.len().max(12)).collect();
+
+        println!("\n🏆 Ranking");
+        let mut header = format!("  {:<3} {:<name_width$}", "#", "Agent");
+        for (step, width) in steps.iter().zip(&widths) {
+            header.push_str(&format!("  {:<width$}", step));
+        }
+        println!("{}  {:<8}  Time", header, "Failures");
+
+        for (position, score) in ranking.iter().enumerate() {
+            let mut row = format!("  {:<3} {:<name_width$}", position + 1, score.agent.name());
+            for (step, width) in steps.iter().zip(&widths) {
+                match score.results.iter().find(|result| result.step == *step) {
+                    // The status emoji occupies two columns but counts as one char
+                    Some(result) => row.push_str(&format!("  {:<w$}", cell(result), w = width - 1)),
+                    None => row.push_str(&format!("  {:<width$}", "-")),
+                }
+            }
+            println!(
+                "{}  {:<8}  {}",
+                row,
+                score.failures(),
+                seconds(score.duration_ms())
+            );
+        }
+        if let [first, second, ..] = ranking.as_slice() {
+            println!(
+                "\n💡 Compare the leaders with 'agentcrew diff {} {}'",
+                first.agent.name(),
+                second.agent.name()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Table cell for one step result
+fn cell(result: &HarvestResultRecord) -> String {
+    if result.timed_out {
+        format!("⌛ {}", seconds(result.duration_ms))
+    } else if result.passed() {
+        format!("✅ {}", seconds(result.duration_ms))
+    } else {
+        format!("❌ {} ({})", seconds(result.duration_ms), result.failures)
+    }
+}
+
+/// Milliseconds as `1.2s`
+fn seconds(duration_ms: i64) -> String {
+    format!("{:.1}s", duration_ms as f64 / 1000.0)
+}
@@ -0,0 +1,72 @@
+use anyhow::{Context, Result};
+use chrono::{Local, Utc};
+use regex::Regex;
+
+use super::CommandHandler;
+use crate::config::AgentCrewConfig;
+use crate::process::logs::{agent_log_path, parse_since, read_entries, LogFilter, LOG_STREAMS};
+
+impl CommandHandler {
+    /// Print an agent's log, optionally filtered and limited to the last lines
+    pub async fn logs(
+        name: &str,
+        tail: Option<usize>,
+        since: Option<&str>,
+        grep: Option<&str>,
+        stream: Option<&str>,
+    ) -> Result<()> {
+        if let Some(stream) = stream {
+            if !LOG_STREAMS.contains(&stream) {
+                anyhow::bail!(
+                    "Unknown stream '{}' (expected one of: {})",
+                    stream,
+                    LOG_STREAMS.join(", ")
+                );
+            }
+        }
+        let filter = LogFilter {
+            since: since
+                .map(|since| parse_since(since, Utc::now()))
+                .transpose()?,
+            pattern: grep
+                .map(|grep| {
+                    Regex::new(grep).with_context(|| format!("Invalid --grep pattern: {}", grep))
+                })
+                .transpose()?,
+            stream: stream.map(str::to_string),
+        };
+
+        let db = Self::open_database().await?;
+        let (_, agent) = Self::resolve_agent(&db, name).await?;
+        db.close().await;
+
+        let path = agent_log_path(
+            &AgentCrewConfig::logs_dir()?,
+            agent.session_slug(),
+            &agent.name(),
+        );
+        if !path.exists() {
+            println!("  📭 No logs for {} yet", name);
+            return Ok(());
+        }
+
+        let entries: Vec<_> = read_entries(&path)?
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .collect();
+        let skip = tail.map_or(0, |tail| entries.len().saturating_sub(tail));
+
+        for entry in &entries[skip..] {
+            println!(
+                "{} [{}] {}",
+                entry
+                    .timestamp
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S"),
+                entry.stream,
+                entry.text
+            );
+        }
+        Ok(())
+    }
+}
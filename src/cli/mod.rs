@@ -2,13 +2,85 @@ use anyhow::{Context, Result};
 use std::path::PathBuf;
 
 use crate::config::AgentCrewConfig;
-use crate::database::Database;
+use crate::database::{AgentRecord, Database, SessionRecord};
 use crate::git::GitUtils;
+use crate::process::DaemonClient;
+use crate::worktree::{AgentWorktree, WorktreeManager};
+
+mod adopt;
+mod checkpoint;
+mod clean;
+mod config;
+mod control;
+mod daemon;
+mod deploy;
+mod diff;
+mod exec;
+mod follow;
+mod harvest;
+mod logs;
+mod session;
+mod status;
+mod tui;
+mod worktrees;
+
+pub use diff::DiffMode;
+
+/// Worktree and branch of an agent, falling back to the standard layout
+fn agent_worktree(
+    manager: &WorktreeManager,
+    session: &SessionRecord,
+    agent: &AgentRecord,
+) -> AgentWorktree {
+    let name = agent.name();
+    AgentWorktree {
+        branch: agent
+            .branch_name
+            .clone()
+            .unwrap_or_else(|| WorktreeManager::branch_name(session.slug(), &name)),
+        path: agent
+            .worktree_path
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| manager.worktree_path(session.slug(), &name)),
+        worktree_name: WorktreeManager::worktree_name(session.slug(), &name),
+        agent_name: name,
+    }
+}
 
 /// CLI command implementations
 pub struct CommandHandler;
 
 impl CommandHandler {
+    /// Open the project database
+    pub(crate) async fn open_database() -> Result<Database> {
+        if !AgentCrewConfig::is_initialized() {
+            anyhow::bail!("agentcrew not initialized. Run 'agentcrew init' first.");
+        }
+        Database::new(&AgentCrewConfig::database_path()?).await
+    }
+
+    /// Connect to the project daemon, starting it if needed
+    pub(crate) async fn daemon_client() -> Result<DaemonClient> {
+        DaemonClient::connect_or_spawn(
+            &AgentCrewConfig::socket_path()?,
+            &AgentCrewConfig::daemon_log_path()?,
+        )
+        .await
+    }
+
+    /// Find an agent such as `claude-1` in the most recent session
+    pub(crate) async fn resolve_agent(db: &Database, name: &str) -> Result<(SessionRecord, AgentRecord)> {
+        let session = db
+            .latest_session()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first."))?;
+        let agent = db.find_agent(&session.id, name).await?.ok_or_else(|| {
+            anyhow::anyhow!("No agent named '{}' in session {}", name, session.slug())
+        })?;
+        Ok((session, agent))
+    }
+
     /// Initialize agentcrew in the current project
     pub async fn init() -> Result<()> {
         println!("🚀 Initializing agentcrew in current project...");
@@ -37,7 +109,11 @@ impl CommandHandler {
         println!("  📍 Location: {}", repo_root.display());
 
         // Step 4: Create configuration
-        let config = AgentCrewConfig::new(repo_name, repo_root);
+        let config = AgentCrewConfig {
+            project_name: repo_name,
+            project_root: repo_root,
+            ..AgentCrewConfig::inherited()?
+        };
 
         // Step 5: Save configuration and create directories
         config
@@ -63,7 +139,7 @@ impl CommandHandler {
             .with_context(|| "Failed to initialize database")?;
         
         // Run initial cleanup (won't delete anything on first run)
-        db.cleanup_old_sessions(30).await
+        db.cleanup_old_sessions(config.retention_days.into()).await
             .with_context(|| "Failed to run database cleanup")?;
         
         let stats = db.get_stats().await?;
@@ -92,8 +168,11 @@ impl CommandHandler {
             "",
             "# agentcrew",
             ".agentcrew/logs/",
+            ".agentcrew/sessions/",
             ".agentcrew/temp/",
+            ".agentcrew/worktrees/",
             ".agentcrew/agentcrew.db*",
+            ".agentcrew/agentcrew.sock",
         ];
 
         if gitignore_path.exists() {
@@ -164,6 +243,7 @@ mod tests {
         let content = fs::read_to_string(".gitignore").expect("Should read gitignore");
         assert!(content.contains("# agentcrew"));
         assert!(content.contains(".agentcrew/logs/"));
+        assert!(content.contains(".agentcrew/sessions/"));
 
         std::env::set_current_dir(original_dir).expect("Should restore dir");
     }
@@ -0,0 +1,371 @@
+use anyhow::{Context, Result};
+use chrono::{Local, Utc};
+use git2::Oid;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::CommandHandler;
+use crate::config::AgentCrewConfig;
+use crate::database::{AgentStatus, DeliveryStatus, InteractionType, SessionStatus};
+use crate::git::diff::short_id;
+use crate::git::GitUtils;
+use crate::process::communication::{Request, Response};
+use crate::session::{export, import, validate_name, ProjectLayout, SessionSnapshot};
+use crate::worktree::WorktreeManager;
+
+impl CommandHandler {
+    /// Snapshot the current session into `.agentcrew/sessions/<name>.json`
+    pub async fn save(name: &str) -> Result<()> {
+        validate_name(name)?;
+        let db = Self::open_database().await?;
+        let session = db
+            .latest_session()
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No sessions yet. Run 'agentcrew deploy' first."))?;
+        let repo = GitUtils::find_git_repository(&GitUtils::get_repository_root()?)?;
+
+        let snapshot = SessionSnapshot::capture(&db, &repo, &session, name).await?;
+        let path = SessionSnapshot::path(&AgentCrewConfig::sessions_dir()?, name);
+        snapshot.write(&path)?;
+        db.set_session_name(&session.id, name).await?;
+        db.close().await;
+
+        println!(
+            "  💾 Session {} saved as '{}' → {}",
+            session.slug(),
+            name,
+            path.display()
+        );
+        for saved in &snapshot.agents {
+            let agent = &saved.agent;
+            println!(
+                "    {} {:<12} {:<13} {}",
+                if saved.pending_questions.is_empty() {
+                    "✅"
+                } else {
+                    "❓"
+                },
+                agent.name(),
+                agent.status,
+                saved
+                    .branch_tip
+                    .as_deref()
+                    .map_or("-", |tip| tip.get(..8).unwrap_or(tip))
+            );
+            let worktree = agent.worktree_path.as_deref().map(Path::new);
+            if let Some(worktree) = worktree.filter(|path| path.exists()) {
+                if GitUtils::is_dirty(worktree)? {
+                    println!(
+                        "      ⚠️  uncommitted changes are not saved; run 'agentcrew checkpoint --agent {}' first",
+                        agent.name()
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Recreate a saved session's worktrees at their saved commits and
+    /// relaunch the agents that were still at work, briefing them on where
+    /// they left off. Commits made after the save are kept unless `force`.
+    pub async fn load(name: &str, force: bool) -> Result<()> {
+        validate_name(name)?;
+        let sessions_dir = AgentCrewConfig::sessions_dir()?;
+        let path = SessionSnapshot::path(&sessions_dir, name);
+        if !path.exists() {
+            let saved = SessionSnapshot::list(&sessions_dir)?;
+            if saved.is_empty() {
+                anyhow::bail!(
+                    "No saved session '{}'. Save one with 'agentcrew save <name>'.",
+                    name
+                );
+            }
+            anyhow::bail!(
+                "No saved session '{}'. Saved sessions: {}",
+                name,
+                saved.join(", ")
+            );
+        }
+        let snapshot = SessionSnapshot::read(&path)?;
+        let slug = snapshot.session.slug();
+
+        let mut client = Self::daemon_client().await?;
+        if let Response::Status { agents } = client.request(&Request::Status).await? {
+            let running: Vec<&str> = agents
+                .iter()
+                .filter(|running| {
+                    snapshot
+                        .agents
+                        .iter()
+                        .any(|saved| saved.agent.id == running.agent_id)
+                })
+                .map(|running| running.name.as_str())
+                .collect();
+            if !running.is_empty() {
+                anyhow::bail!(
+                    "Session {} is still running ({}). Dismiss its agents first.",
+                    slug,
+                    running.join(", ")
+                );
+            }
+        }
+
+        let db = Self::open_database().await?;
+        let manager = WorktreeManager::new(
+            GitUtils::get_repository_root()?,
+            AgentCrewConfig::worktrees_dir()?,
+        );
+        let mut session = snapshot.session.clone();
+        session.status = SessionStatus::Active;
+        session.completed_at = None;
+        db.restore_session(&session).await?;
+        println!("  🌿 Restoring worktrees of session {}...", slug);
+        let mut relaunch = Vec::new();
+        for saved in &snapshot.agents {
+            let mut agent = saved.agent.clone();
+            let name = agent.name();
+            let Some(tip) = saved.branch_tip.as_deref() else {
+                println!("    ⚠️  {} had no branch; not restored", name);
+                continue;
+            };
+            let commit = Oid::from_str(tip)
+                .with_context(|| format!("Invalid branch tip {} of {}", tip, name))?;
+            let worktree = manager
+                .restore(slug, &name, commit, force)
+                .with_context(|| format!("Failed to restore {}", name))?;
+            agent.worktree_path = Some(worktree.path.to_string_lossy().into_owned());
+            agent.branch_name = Some(worktree.branch.clone());
+
+            let status = if agent.status.is_terminal() {
+                agent.status
+            } else {
+                relaunch.push(saved);
+                AgentStatus::Initializing
+            };
+            db.restore_agent(&agent, status).await?;
+            println!(
+                "    ✅ {} → {} at {}",
+                name,
+                worktree.branch,
+                short_id(commit)
+            );
+        }
+
+        if relaunch.is_empty() {
+            db.close().await;
+            println!(
+                "  🎉 Session {} restored; its agents had all finished",
+                slug
+            );
+            return Ok(());
+        }
+
+        println!("  🤖 Relaunching agents...");
+        let Response::Launched { started, failed } = client
+            .request(&Request::Launch {
+                session_id: snapshot.session.id.clone(),
+            })
+            .await?
+        else {
+            anyhow::bail!("Unexpected response from daemon");
+        };
+        for agent in &failed {
+            println!(
+                "    ❌ {}: {}",
+                agent.agent,
+                agent.error.as_deref().unwrap_or("failed to start")
+            );
+        }
+        for started in &started {
+            let Some(saved) = relaunch
+                .iter()
+                .find(|saved| saved.agent.id == started.agent_id)
+            else {
+                continue;
+            };
+            let brief = saved.resume_brief(&snapshot);
+            let delivered = client
+                .request(&Request::Send {
+                    agent_id: started.agent_id.clone(),
+                    message: brief.clone(),
+                })
+                .await;
+            let status = match delivered {
+                Ok(_) => DeliveryStatus::Delivered,
+                Err(_) => DeliveryStatus::Failed,
+            };
+            db.record_delivery(
+                &saved.agent.id,
+                &saved.agent.session_id,
+                InteractionType::Brief,
+                &brief,
+                status,
+                None,
+            )
+            .await?;
+            match status {
+                DeliveryStatus::Delivered => {
+                    println!("    🚀 {} relaunched and briefed", started.name)
+                }
+                DeliveryStatus::Failed => {
+                    println!("    ⚠️  {} relaunched, but the brief failed", started.name)
+                }
+            }
+        }
+        db.close().await;
+
+        println!(
+            "  🎉 Session {} loaded: {} of {} agents relaunched",
+            slug,
+            started.len(),
+            relaunch.len()
+        );
+        Ok(())
+    }
+
+    /// Pack a session into a tar file a teammate can import
+    pub async fn export(reference: &str, output: &Path) -> Result<()> {
+        let db = Self::open_database().await?;
+        let session = db.find_session(reference).await?;
+        for agent in db.list_agents(&session.id).await? {
+            let worktree = agent.worktree_path.as_deref().map(Path::new);
+            if let Some(worktree) = worktree.filter(|path| path.exists()) {
+                if GitUtils::is_dirty(worktree)? {
+                    println!(
+                        "  ⚠️  {} has uncommitted changes; only committed work is exported",
+                        agent.name()
+                    );
+                }
+            }
+        }
+
+        let exported = export(&db, &Self::project_layout()?, &session, output).await;
+        db.close().await;
+        let exported = exported?;
+        println!(
+            "  📦 Session {} exported to {}: {} agents, {} branches, {} interactions, {} log files",
+            session.slug(),
+            output.display(),
+            exported.agents,
+            exported.branches,
+            exported.interactions,
+            exported.log_files
+        );
+        Ok(())
+    }
+
+    /// Unpack a session exported from another clone
+    pub async fn import(input: &Path) -> Result<()> {
+        let db = Self::open_database().await?;
+        let imported = import(&db, &Self::project_layout()?, input).await;
+        db.close().await;
+        let imported = imported?;
+
+        let slug = imported.session.slug();
+        if imported.remapped {
+            println!(
+                "  🔀 Session or branches already existed here; imported with new ids as {}",
+                slug
+            );
+        }
+        for worktree in &imported.worktrees {
+            println!("    ✅ {} → {}", worktree.agent_name, worktree.branch);
+        }
+        println!(
+            "  📥 Session {} imported with {} interactions: {}",
+            slug, imported.interactions, imported.session.prompt
+        );
+        println!(
+            "💡 Run 'agentcrew load {}' to relaunch its unfinished agents.",
+            imported.snapshot_name
+        );
+        Ok(())
+    }
+
+    fn project_layout() -> Result<ProjectLayout> {
+        Ok(ProjectLayout {
+            repo_root: GitUtils::get_repository_root()?,
+            worktrees_dir: AgentCrewConfig::worktrees_dir()?,
+            logs_dir: AgentCrewConfig::logs_dir()?,
+            sessions_dir: AgentCrewConfig::sessions_dir()?,
+        })
+    }
+
+    /// List previous sessions with their outcome, duration and agents
+    pub async fn history() -> Result<()> {
+        let db = Self::open_database().await?;
+        let sessions = db.list_sessions().await?;
+        if sessions.is_empty() {
+            println!("📭 No sessions yet. Run 'agentcrew deploy' to start one.");
+            db.close().await;
+            return Ok(());
+        }
+
+        for session in &sessions {
+            let agents = db.list_agents(&session.id).await?;
+            let mut statuses: BTreeMap<String, usize> = BTreeMap::new();
+            for agent in &agents {
+                *statuses.entry(agent.status.to_string()).or_default() += 1;
+            }
+            let statuses: Vec<String> = statuses
+                .iter()
+                .map(|(status, count)| format!("{} {}", count, status))
+                .collect();
+            // An active session whose agents all stopped ended with its last agent
+            let stopped = session.status == SessionStatus::Active
+                && !agents.is_empty()
+                && agents.iter().all(|agent| agent.status.is_terminal());
+            let (outcome, ended) = if stopped {
+                let last = agents.iter().map(|agent| agent.last_activity).max();
+                ("ended".to_string(), last)
+            } else {
+                (session.status.to_string(), session.completed_at)
+            };
+            let duration = ended.unwrap_or_else(Utc::now) - session.started_at;
+
+            println!(
+                "  {} {:<9} {} {:>8}  {} agent{} ({})",
+                session.slug(),
+                outcome,
+                session
+                    .started_at
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M"),
+                format_duration(duration.num_seconds()),
+                agents.len(),
+                if agents.len() == 1 { "" } else { "s" },
+                statuses.join(", ")
+            );
+            let prompt: String = session.prompt.chars().take(60).collect();
+            match session.name.as_deref() {
+                Some(name) => println!("           💾 {}: {}", name, prompt),
+                None => println!("           {}", prompt),
+            }
+        }
+        db.close().await;
+        Ok(())
+    }
+}
+
+/// Compact duration such as `45s`, `12m` or `3h 05m`
+pub(super) fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(-3), "0s");
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(12 * 60 + 5), "12m");
+        assert_eq!(format_duration(3 * 3600 + 5 * 60), "3h 05m");
+    }
+}
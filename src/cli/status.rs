@@ -0,0 +1,347 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use git2::{Oid, Repository};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use super::session::format_duration;
+use super::CommandHandler;
+use crate::agents::ProviderRegistry;
+use crate::config::AgentCrewConfig;
+use crate::database::{AgentRecord, AgentStatus, Database, SessionStatus};
+use crate::git::diff::{branch_tip, diff_commits, file_changes};
+use crate::git::GitUtils;
+use crate::process::communication::{Request, Response};
+use crate::process::{signals, DaemonClient};
+
+/// State of the current session as printed by `agentcrew status`
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    session: Option<SessionSummary>,
+    active_agents: i64,
+    pending_questions: i64,
+    agents: Vec<AgentSummary>,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionSummary {
+    id: String,
+    slug: String,
+    prompt: String,
+    status: SessionStatus,
+    started_at: DateTime<Utc>,
+    /// Agent marked for adoption in the TUI
+    winner: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AgentSummary {
+    name: String,
+    agent_type: String,
+    status: AgentStatus,
+    progress: i64,
+    last_activity: DateTime<Utc>,
+    idle_seconds: i64,
+    pid: Option<i64>,
+    /// Whether `pid` is still running; `None` without a pid
+    alive: Option<bool>,
+    /// Supervised by the running daemon without a process of its own
+    in_process: bool,
+    branch: Option<String>,
+    diff: Option<DiffSize>,
+    ports: Option<String>,
+}
+
+/// Size of an agent's branch compared to the session base
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+struct DiffSize {
+    files: usize,
+    additions: usize,
+    deletions: usize,
+    /// Files changed in the worktree but not yet committed
+    uncommitted: usize,
+}
+
+impl CommandHandler {
+    /// Show the agents of the current session from the database, as JSON
+    /// with `json`, refreshed every `watch` seconds until Ctrl-C
+    pub async fn status(json: bool, watch: Option<u64>) -> Result<()> {
+        let db = Self::open_database().await?;
+        let repo = GitUtils::get_repository_root()
+            .ok()
+            .and_then(|root| Repository::open(root).ok());
+
+        let Some(seconds) = watch else {
+            let result = print_status(&db, repo.as_ref(), json).await;
+            db.close().await;
+            return result;
+        };
+        let mut interval = tokio::time::interval(Duration::from_secs(seconds.max(1)));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = tokio::signal::ctrl_c() => break,
+            }
+            if !json {
+                // Clear the screen and move the cursor home before redrawing
+                print!("\x1b[2J\x1b[H");
+            }
+            print_status(&db, repo.as_ref(), json).await?;
+            if !json {
+                println!("\n🔄 Refreshing every {}s, Ctrl-C to stop", seconds.max(1));
+            }
+            std::io::stdout().flush()?;
+        }
+        db.close().await;
+        Ok(())
+    }
+}
+
+async fn print_status(db: &Database, repo: Option<&Repository>, json: bool) -> Result<()> {
+    let in_process = daemon_in_process().await;
+    let report = status_report(db, repo, &in_process).await?;
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    let Some(session) = &report.session else {
+        println!("📭 No sessions yet. Run 'agentcrew deploy' to start one.");
+        return Ok(());
+    };
+    println!(
+        "📊 Session {} ({}): {}",
+        session.slug, session.status, session.prompt
+    );
+    println!(
+        "   🤖 {} active agent(s), ❓ {} pending question(s)",
+        report.active_agents, report.pending_questions
+    );
+    if let Some(winner) = &session.winner {
+        println!("   🏆 Winner: {} ('agentcrew adopt' adopts it)", winner);
+    }
+    if report.agents.is_empty() {
+        println!("  📭 No agents in this session");
+    }
+    let registry = ProviderRegistry::builtin();
+    for agent in &report.agents {
+        let provider = registry.get(&agent.agent_type);
+        let icon = provider.as_ref().map_or("🤖", |provider| provider.icon());
+        let process = match (agent.pid, agent.alive) {
+            (Some(pid), Some(true)) => format!("pid {}", pid),
+            (Some(pid), _) => format!("pid {} ✝", pid),
+            (None, _) if agent.in_process => "in-process".to_string(),
+            (None, _) => "-".to_string(),
+        };
+        let diff = match &agent.diff {
+            Some(diff) if diff.uncommitted > 0 => format!(
+                "{} files +{} -{} ({} uncommitted)",
+                diff.files, diff.additions, diff.deletions, diff.uncommitted
+            ),
+            Some(diff) => format!(
+                "{} files +{} -{}",
+                diff.files, diff.additions, diff.deletions
+            ),
+            None => "-".to_string(),
+        };
+        println!(
+            "  {} {:<12} {:<13} {:>3}%  {:>7} ago  {:<13} {}",
+            icon,
+            agent.name,
+            agent.status.as_str(),
+            agent.progress,
+            format_duration(agent.idle_seconds),
+            process,
+            diff
+        );
+        match &agent.ports {
+            Some(ports) => println!(
+                "       {} (ports {})",
+                agent.branch.as_deref().unwrap_or("-"),
+                ports
+            ),
+            None => println!("       {}", agent.branch.as_deref().unwrap_or("-")),
+        }
+    }
+    Ok(())
+}
+
+/// Ids of agents the running daemon supervises in-process. The daemon is
+/// not started just to ask.
+async fn daemon_in_process() -> HashMap<String, bool> {
+    let Ok(socket_path) = AgentCrewConfig::socket_path() else {
+        return HashMap::new();
+    };
+    let Ok(mut client) = DaemonClient::connect(&socket_path).await else {
+        return HashMap::new();
+    };
+    match client.request(&Request::Status).await {
+        Ok(Response::Status { agents }) => agents
+            .into_iter()
+            .map(|agent| (agent.agent_id, agent.pid.is_none()))
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+async fn status_report(
+    db: &Database,
+    repo: Option<&Repository>,
+    in_process: &HashMap<String, bool>,
+) -> Result<StatusReport> {
+    let stats = db.get_stats().await?;
+    let mut report = StatusReport {
+        session: None,
+        active_agents: stats.active_agents_count,
+        pending_questions: stats.pending_questions_count,
+        agents: Vec::new(),
+    };
+    let Some(session) = db.latest_session().await? else {
+        return Ok(report);
+    };
+
+    let base = session
+        .base_commit
+        .as_deref()
+        .and_then(|base| Oid::from_str(base).ok());
+    let mut winner = None;
+    for agent in db.list_agents(&session.id).await? {
+        if session.winner_agent_id.as_deref() == Some(agent.id.as_str()) {
+            winner = Some(agent.name());
+        }
+        let diff = match repo {
+            Some(repo) => diff_size(repo, &agent, base).ok().flatten(),
+            None => None,
+        };
+        let ports = db
+            .agent_ports(&agent.id)
+            .await?
+            .map(|ports| format!("{}-{}", ports.port_start, ports.port_end()));
+        report.agents.push(AgentSummary {
+            name: agent.name(),
+            idle_seconds: (Utc::now() - agent.last_activity).num_seconds(),
+            alive: agent.process_id.map(|pid| signals::is_alive(pid as u32)),
+            in_process: in_process.get(&agent.id).copied().unwrap_or(false),
+            agent_type: agent.agent_type,
+            status: agent.status,
+            progress: agent.progress,
+            last_activity: agent.last_activity,
+            pid: agent.process_id,
+            branch: agent.branch_name,
+            diff,
+            ports,
+        });
+    }
+    report.session = Some(SessionSummary {
+        winner,
+        slug: session.slug().to_string(),
+        id: session.id,
+        prompt: session.prompt,
+        status: session.status,
+        started_at: session.started_at,
+    });
+    Ok(report)
+}
+
+/// Files and lines an agent's branch changed since `base`, plus the files
+/// still uncommitted in its worktree; `None` before it has a branch
+fn diff_size(
+    repo: &Repository,
+    agent: &AgentRecord,
+    base: Option<Oid>,
+) -> Result<Option<DiffSize>> {
+    let (Some(branch), Some(base)) = (agent.branch_name.as_deref(), base) else {
+        return Ok(None);
+    };
+    let tip = branch_tip(repo, branch)?;
+    let changes = file_changes(&diff_commits(repo, &repo.find_commit(base)?, &tip)?)?;
+    let uncommitted = match agent.worktree_path.as_deref().map(Path::new) {
+        Some(path) if path.exists() => GitUtils::changed_files(path)?,
+        _ => 0,
+    };
+    Ok(Some(DiffSize {
+        files: changes.len(),
+        additions: changes.iter().map(|change| change.additions).sum(),
+        deletions: changes.iter().map(|change| change.deletions).sum(),
+        uncommitted,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::tests::commit_files;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_status_report() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = Repository::init(temp_dir.path().join("repo")).expect("Should init repo");
+        let base = commit_files(&repo, "main", None, &[("README.md", Some("hi\n"))]);
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+
+        let report = status_report(&db, Some(&repo), &HashMap::new())
+            .await
+            .expect("Should report status");
+        assert!(report.session.is_none());
+
+        let session = db
+            .create_session("build it", r#"{"mock":2}"#, "main", &base.to_string())
+            .await
+            .unwrap();
+        let first = db
+            .create_agent(&session.id, "mock", 1, &BTreeMap::new())
+            .await
+            .unwrap();
+        let second = db
+            .create_agent(&session.id, "mock", 2, &BTreeMap::new())
+            .await
+            .unwrap();
+        let branch = "agentcrew/test/mock-1";
+        commit_files(
+            &repo,
+            branch,
+            Some(base),
+            &[("README.md", Some("hello\n")), ("a.rs", Some("1\n2\n"))],
+        );
+        db.set_agent_worktree(&first.id, &temp_dir.path().join("missing"), branch)
+            .await
+            .unwrap();
+        db.update_agent_status(&first.id, AgentStatus::Running)
+            .await
+            .unwrap();
+        db.set_agent_process(&first.id, Some(i32::MAX as u32))
+            .await
+            .unwrap();
+
+        let in_process = HashMap::from([(second.id.clone(), true)]);
+        let report = status_report(&db, Some(&repo), &in_process)
+            .await
+            .expect("Should report status");
+        assert_eq!(report.session.unwrap().id, session.id);
+        assert_eq!(report.active_agents, 2);
+        assert_eq!(report.agents.len(), 2);
+
+        let mock1 = &report.agents[0];
+        assert_eq!(mock1.branch.as_deref(), Some(branch));
+        assert_eq!(mock1.alive, Some(false));
+        assert_eq!(
+            mock1.diff,
+            Some(DiffSize {
+                files: 2,
+                additions: 3,
+                deletions: 1,
+                uncommitted: 0,
+            })
+        );
+        let mock2 = &report.agents[1];
+        assert!(mock2.in_process);
+        assert_eq!((mock2.pid, mock2.diff.as_ref()), (None, None));
+    }
+}
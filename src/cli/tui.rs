@@ -0,0 +1,212 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::Path;
+
+use super::diff::agent_tip;
+use super::CommandHandler;
+use crate::config::AgentCrewConfig;
+use crate::database::Database;
+use crate::git::diff::{diff_commits, side_by_side};
+use crate::git::GitUtils;
+use crate::process::communication::{Request, Response};
+use crate::process::logs::{agent_log_path, read_entries, LogTail};
+use crate::process::DaemonClient;
+use crate::ui::{Action, App, Event, Events, LogLine, Tui};
+
+impl CommandHandler {
+    /// Watch the latest session and answer, brief, pause or dismiss its
+    /// agents from a terminal UI
+    pub async fn tui() -> Result<()> {
+        if !std::io::stdout().is_terminal() {
+            anyhow::bail!("The TUI needs an interactive terminal; try 'agentcrew status --watch'");
+        }
+        let ui = AgentCrewConfig::load()?.ui;
+        let db = Self::open_database().await?;
+        let logs_dir = AgentCrewConfig::logs_dir()?;
+        // Start the daemon before the screen is taken over so its startup
+        // message stays readable
+        Self::daemon_client().await?;
+
+        let mut tui = Tui::enter()?;
+        let mut events = Events::new(ui.refresh_interval());
+        let mut app = App::new();
+        let mut tails = HashMap::new();
+        let result = async {
+            refresh(&db, &logs_dir, ui.history_lines, &mut app, &mut tails).await?;
+            while !app.should_quit() {
+                tui.draw(&app)?;
+                match events.next().await {
+                    Some(Event::Key(key)) => {
+                        if let Some(action) = app.handle_key(key) {
+                            if let Err(e) = perform(&db, &logs_dir, &mut app, action).await {
+                                app.set_message(format!("❌ {:#}", e));
+                            }
+                            refresh(&db, &logs_dir, ui.history_lines, &mut app, &mut tails).await?;
+                        }
+                    }
+                    Some(Event::Tick) => {
+                        refresh(&db, &logs_dir, ui.history_lines, &mut app, &mut tails).await?
+                    }
+                    Some(Event::Resize) => {}
+                    None => break,
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        drop(tui);
+        db.close().await;
+        result
+    }
+}
+
+/// Re-read the latest session, its agents and questions, and new log lines;
+/// `history` lines per agent are loaded the first time an agent is seen
+async fn refresh(
+    db: &Database,
+    logs_dir: &Path,
+    history: usize,
+    app: &mut App,
+    tails: &mut HashMap<String, LogTail>,
+) -> Result<()> {
+    let session = db.latest_session().await?;
+    if app.session_id() != session.as_ref().map(|session| session.id.as_str()) {
+        tails.clear();
+    }
+    let (agents, questions) = match &session {
+        Some(session) => {
+            let questions = db
+                .pending_questions()
+                .await?
+                .into_iter()
+                .filter(|question| question.session_id == session.id)
+                .collect();
+            (db.list_agents(&session.id).await?, questions)
+        }
+        None => (Vec::new(), Vec::new()),
+    };
+
+    let mut lines = Vec::new();
+    for agent in &agents {
+        let name = agent.name();
+        let tail = match tails.get_mut(&agent.id) {
+            Some(tail) => tail,
+            None => {
+                let path = agent_log_path(logs_dir, agent.session_slug(), &name);
+                let entries = read_entries(&path).unwrap_or_default();
+                let skip = entries.len().saturating_sub(history);
+                lines.extend(entries.into_iter().skip(skip).map(|entry| LogLine {
+                    agent: name.clone(),
+                    entry,
+                }));
+                tails
+                    .entry(agent.id.clone())
+                    .or_insert(LogTail::from_end(&path))
+            }
+        };
+        lines.extend(
+            tail.poll()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| LogLine {
+                    agent: name.clone(),
+                    entry,
+                }),
+        );
+    }
+
+    app.update(session, agents, questions);
+    app.push_logs(lines);
+    Ok(())
+}
+
+/// Carry out an action through the daemon, like the matching CLI command
+async fn perform(db: &Database, logs_dir: &Path, app: &mut App, action: Action) -> Result<()> {
+    let message = match action {
+        Action::Compare { left, right } => {
+            let repo = GitUtils::find_git_repository(&GitUtils::get_repository_root()?)?;
+            let left_tip = agent_tip(&repo, &db.get_agent(&left.agent_id).await?)?;
+            let right_tip = agent_tip(&repo, &db.get_agent(&right.agent_id).await?)?;
+            let files = side_by_side(&diff_commits(&repo, &left_tip, &right_tip)?)?;
+            app.show_compare(left, right, files);
+            return Ok(());
+        }
+        Action::MarkWinner {
+            session_id,
+            agent_id,
+            name,
+        } => {
+            db.set_session_winner(&session_id, Some(&agent_id)).await?;
+            format!(
+                "🏆 {} marked as the winner; 'agentcrew adopt' adopts it",
+                name
+            )
+        }
+        Action::ShowLogs { session_slug, name } => {
+            let path = agent_log_path(logs_dir, &session_slug, &name);
+            app.show_logs(name, read_entries(&path)?);
+            return Ok(());
+        }
+        Action::Respond {
+            agent_id,
+            name,
+            message,
+        } => match daemon()
+            .await?
+            .request(&Request::Respond { agent_id, message })
+            .await?
+        {
+            Response::Answered { questions } if !questions.is_empty() => {
+                format!("💬 Answered {}", name)
+            }
+            _ => format!("💬 Response sent to {} (no pending question)", name),
+        },
+        Action::Brief { message } => {
+            match daemon().await?.request(&Request::Brief { message }).await? {
+                Response::Delivered { deliveries } => {
+                    let failed = deliveries
+                        .iter()
+                        .filter(|delivery| delivery.error.is_some())
+                        .count();
+                    format!(
+                        "📬 Brief delivered to {}, {} failed",
+                        deliveries.len() - failed,
+                        failed
+                    )
+                }
+                _ => "📬 Brief sent".to_string(),
+            }
+        }
+        Action::Pause { agent_id, name } => {
+            daemon()
+                .await?
+                .request(&Request::Pause { agent_id })
+                .await?;
+            format!("⏸️  {} paused", name)
+        }
+        Action::Resume { agent_id, name } => {
+            daemon()
+                .await?
+                .request(&Request::Resume { agent_id })
+                .await?;
+            format!("▶️  {} resumed", name)
+        }
+        Action::Dismiss { agent_id, name } => {
+            daemon()
+                .await?
+                .request(&Request::Dismiss { agent_id })
+                .await?;
+            format!("👋 {} dismissed", name)
+        }
+    };
+    app.set_message(message);
+    Ok(())
+}
+
+/// Connect to the daemon started with the TUI. It is not spawned again
+/// behind the screen if it has stopped since.
+async fn daemon() -> Result<DaemonClient> {
+    DaemonClient::connect(&AgentCrewConfig::socket_path()?).await
+}
@@ -0,0 +1,218 @@
+use anyhow::{Context, Result};
+use git2::{Oid, Repository};
+use std::path::Path;
+
+use super::{agent_worktree, CommandHandler};
+use crate::config::AgentCrewConfig;
+use crate::database::{AgentRecord, Database, SessionRecord};
+use crate::git::diff::{branch_tip, short_id};
+use crate::git::GitUtils;
+use crate::worktree::{AgentWorktree, WorktreeManager};
+
+impl CommandHandler {
+    /// List agent worktrees reconciled with the worktrees git has
+    /// registered; with `repair`, re-create, re-register or prune the ones
+    /// that drifted apart
+    pub async fn worktrees(repair: bool) -> Result<()> {
+        let db = Self::open_database().await?;
+        let repo_root = GitUtils::get_repository_root()?;
+        let manager = WorktreeManager::new(&repo_root, AgentCrewConfig::worktrees_dir()?);
+        let repo = manager.open()?;
+        let mut registered = manager.registered(&repo)?;
+
+        let (mut listed, mut drifted, mut repaired) = (0, 0, 0);
+        for session in db.list_sessions().await? {
+            let agents: Vec<AgentRecord> = db
+                .list_agents(&session.id)
+                .await?
+                .into_iter()
+                .filter(|agent| agent.worktree_path.is_some())
+                .collect();
+            if agents.is_empty() {
+                continue;
+            }
+            let base = session
+                .base_commit
+                .as_deref()
+                .and_then(|base| Oid::from_str(base).ok());
+            println!(
+                "  📂 Session {} ({}), base {}",
+                session.slug(),
+                session.status,
+                base.map_or("-".to_string(), short_id)
+            );
+
+            for agent in &agents {
+                let worktree = agent_worktree(&manager, &session, agent);
+                let known = registered
+                    .iter()
+                    .position(|known| {
+                        known.name == worktree.worktree_name || known.path == worktree.path
+                    })
+                    .map(|index| registered.remove(index));
+                let shown = display_path(&repo_root, &worktree.path);
+                listed += 1;
+
+                let problem = if !worktree.path.exists() {
+                    "worktree directory is missing".to_string()
+                } else if known.is_none() {
+                    "not registered with git".to_string()
+                } else {
+                    match describe(&repo, &worktree, base) {
+                        Ok(description) => {
+                            println!("    ✅ {:<12} {}", agent.name(), description);
+                            match db.agent_ports(&agent.id).await? {
+                                Some(ports) => println!(
+                                    "       {} (ports {}-{})",
+                                    shown,
+                                    ports.port_start,
+                                    ports.port_end()
+                                ),
+                                None => println!("       {}", shown),
+                            }
+                            continue;
+                        }
+                        Err(e) => format!("cannot open worktree: {}", e.root_cause()),
+                    }
+                };
+                drifted += 1;
+                println!("    ❗ {:<12} {}: {}", agent.name(), problem, shown);
+                if repair {
+                    match repair_agent(&db, &manager, &repo, &session, agent, &worktree).await {
+                        Ok(action) => {
+                            repaired += 1;
+                            println!("       🔧 {}", action);
+                        }
+                        Err(e) => println!("       ⚠️  Could not repair: {:#}", e),
+                    }
+                }
+            }
+        }
+
+        if !registered.is_empty() {
+            println!("  👻 Worktrees with no agent in the database:");
+        }
+        for orphan in &registered {
+            drifted += 1;
+            let shown = display_path(&repo_root, &orphan.path);
+            if !orphan.exists {
+                println!("    ❗ {} (directory is missing): {}", orphan.name, shown);
+            } else {
+                println!("    ❗ {}: {}", orphan.name, shown);
+            }
+            if !repair {
+                continue;
+            }
+            if orphan.exists {
+                match GitUtils::is_dirty(&orphan.path) {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        println!("       ⚠️  Has uncommitted changes; not pruned");
+                        continue;
+                    }
+                    Err(e) => {
+                        println!("       ⚠️  Could not check for changes: {:#}", e);
+                        continue;
+                    }
+                }
+            }
+            match manager.prune(&repo, &orphan.name, &orphan.path) {
+                Ok(()) => {
+                    repaired += 1;
+                    println!("       🔧 Pruned (its branch is kept)");
+                }
+                Err(e) => println!("       ⚠️  Could not prune: {:#}", e),
+            }
+        }
+        db.close().await;
+
+        if listed == 0 && drifted == 0 {
+            println!("  📭 No agent worktrees");
+        } else if drifted == 0 {
+            println!("  ✅ {} worktree(s), all in sync with git", listed);
+        } else if repair {
+            println!(
+                "  🔧 Repaired {} of {} drifted worktree(s)",
+                repaired, drifted
+            );
+        } else {
+            println!(
+                "  ⚠️  {} drifted worktree(s); run 'agentcrew worktrees --repair' to fix them",
+                drifted
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Branch, HEAD, working tree state and ahead/behind counts of a worktree
+fn describe(repo: &Repository, worktree: &AgentWorktree, base: Option<Oid>) -> Result<String> {
+    let checkout = Repository::open(&worktree.path)
+        .with_context(|| format!("Failed to open worktree {}", worktree.path.display()))?;
+    let head = checkout.head().ok();
+    let branch = head
+        .as_ref()
+        .and_then(|head| head.shorthand().map(str::to_string))
+        .unwrap_or_else(|| worktree.branch.clone());
+    let commit = head.and_then(|head| head.target());
+
+    let changed = GitUtils::changed_files(&worktree.path)?;
+    let state = match changed {
+        0 => "clean".to_string(),
+        n => format!("dirty ({} file{})", n, if n == 1 { "" } else { "s" }),
+    };
+    let divergence = match (commit, base) {
+        (Some(commit), Some(base)) => {
+            let (ahead, behind) = repo.graph_ahead_behind(commit, base)?;
+            format!("↑{} ↓{}", ahead, behind)
+        }
+        _ => "-".to_string(),
+    };
+    Ok(format!(
+        "{} {} {} {}",
+        branch,
+        commit.map_or("-".to_string(), short_id),
+        state,
+        divergence
+    ))
+}
+
+/// Bring a database row and git back in line: re-register a worktree git
+/// forgot, re-create a missing one from its branch, or forget the row when
+/// the branch is gone too
+async fn repair_agent(
+    db: &Database,
+    manager: &WorktreeManager,
+    repo: &Repository,
+    session: &SessionRecord,
+    agent: &AgentRecord,
+    worktree: &AgentWorktree,
+) -> Result<String> {
+    if worktree.path.exists() {
+        manager.reattach(repo, worktree)?;
+        return Ok("Registered with git again".to_string());
+    }
+    let Ok(tip) = branch_tip(repo, &worktree.branch) else {
+        db.clear_agent_worktree(&agent.id, true).await?;
+        return Ok(format!(
+            "Branch {} is gone too; removed the worktree from the database",
+            worktree.branch
+        ));
+    };
+    let restored = manager.restore(session.slug(), &agent.name(), tip.id(), false)?;
+    db.set_agent_worktree(&agent.id, &restored.path, &restored.branch)
+        .await?;
+    Ok(format!(
+        "Re-created from {} at {}",
+        restored.branch,
+        short_id(tip.id())
+    ))
+}
+
+/// `path` relative to the repository root when it lies below it
+fn display_path(repo_root: &Path, path: &Path) -> String {
+    path.strip_prefix(repo_root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
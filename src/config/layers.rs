@@ -0,0 +1,535 @@
+//! Merging of the built-in defaults, the user-global config, the project
+//! config and `AGENTCREW_*` environment variables, in that order of
+//! precedence, while remembering where every value came from
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+use super::{
+    AgentCrewConfig, AgentProviderConfig, CheckpointPolicy, HarvestConfig, PortConfig, UiConfig,
+    WorktreeConfig,
+};
+use crate::agents::KNOWN_PROVIDERS;
+
+/// Prefix of environment variables overriding config values
+const ENV_PREFIX: &str = "AGENTCREW_";
+
+/// `AGENTCREW_*` variables that are not config keys: the global config
+/// location and the variables agentcrew exports to agents and commands
+const RESERVED_ENV: &[&str] = &[
+    "CONFIG_DIR",
+    "LOG_LEVEL",
+    "AGENT",
+    "SESSION",
+    "PORT",
+    "PORT_RANGE",
+    "QUESTION_MARKER",
+];
+
+/// Where an effective config value was set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    /// Built into agentcrew
+    Default,
+    /// The user-global config file
+    Global(PathBuf),
+    /// The project's `.agentcrew/config.toml`
+    Project(PathBuf),
+    /// An `AGENTCREW_*` environment variable
+    Env(String),
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::Global(path) => write!(f, "global ({})", path.display()),
+            ConfigOrigin::Project(path) => write!(f, "project ({})", path.display()),
+            ConfigOrigin::Env(name) => write!(f, "env ({})", name),
+        }
+    }
+}
+
+/// The effective configuration and the origin of each of its values
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    pub config: AgentCrewConfig,
+    /// Origin of every value set by a file or variable, by dotted key
+    origins: BTreeMap<String, ConfigOrigin>,
+    /// `AGENTCREW_*` variables that name no config key
+    ignored_env: Vec<String>,
+}
+
+impl LayeredConfig {
+    /// Merge the layers over the built-in defaults. `global` and `project`
+    /// are config files with their contents; `env` holds the process
+    /// environment, of which only `AGENTCREW_*` config keys are used.
+    pub fn resolve(
+        global: Option<(PathBuf, String)>,
+        project: Option<(PathBuf, String)>,
+        env: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let mut merged = defaults()?;
+        let mut origins = BTreeMap::new();
+
+        let files = [
+            global.map(|(path, content)| (path.clone(), content, ConfigOrigin::Global(path))),
+            project.map(|(path, content)| (path.clone(), content, ConfigOrigin::Project(path))),
+        ];
+        for (path, content, origin) in files.into_iter().flatten() {
+            let table: Table = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            merge(&mut merged, table, "", &origin, &mut origins);
+        }
+
+        let keys = env_keys()?;
+        let mut ignored_env = Vec::new();
+        let mut env: Vec<_> = env.into_iter().collect();
+        env.sort();
+        for (name, raw) in env {
+            let Some(path) = env_key(&name) else { continue };
+            let Some(sample) = env_sample(&keys, &path) else {
+                ignored_env.push(name);
+                continue;
+            };
+            let value = env_value(&raw, sample);
+            let origin = ConfigOrigin::Env(name);
+            merge(&mut merged, nest(path, value), "", &origin, &mut origins);
+        }
+
+        let config: AgentCrewConfig = Value::Table(merged).try_into().with_context(|| {
+            let overrides: Vec<_> = origins
+                .values()
+                .filter_map(|origin| match origin {
+                    ConfigOrigin::Env(name) => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect();
+            match overrides.is_empty() {
+                true => "Invalid configuration".to_string(),
+                false => format!(
+                    "Invalid configuration (with overrides from {})",
+                    overrides.join(", ")
+                ),
+            }
+        })?;
+        config.validate()?;
+        Ok(Self {
+            config,
+            origins,
+            ignored_env,
+        })
+    }
+
+    /// Origin of a value by dotted key such as `ports.base`
+    pub fn origin(&self, key: &str) -> &ConfigOrigin {
+        self.origins.get(key).unwrap_or(&ConfigOrigin::Default)
+    }
+
+    /// `AGENTCREW_*` variables left out because they name no config key
+    pub fn ignored_env(&self) -> &[String] {
+        &self.ignored_env
+    }
+
+    /// Every effective value by dotted key, with its origin
+    pub fn entries(&self) -> Result<Vec<(String, Value, &ConfigOrigin)>> {
+        let mut leaves = Vec::new();
+        flatten(
+            &Table::try_from(&self.config).with_context(|| "Failed to serialize config")?,
+            "",
+            &mut leaves,
+        );
+        Ok(leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = self.origin(&key);
+                (key, value, origin)
+            })
+            .collect())
+    }
+}
+
+/// Directory holding the user-global `config.toml`: `$AGENTCREW_CONFIG_DIR`,
+/// else `$XDG_CONFIG_HOME/agentcrew`, else `~/.config/agentcrew`
+pub(super) fn global_config_dir() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+    if let Some(dir) = var("AGENTCREW_CONFIG_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = var("XDG_CONFIG_HOME") {
+        return Some(PathBuf::from(dir).join("agentcrew"));
+    }
+    var("HOME").map(|home| PathBuf::from(home).join(".config").join("agentcrew"))
+}
+
+/// Read a config file, or `None` if it does not exist
+pub(super) fn read_optional(path: &Path) -> Result<Option<(PathBuf, String)>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    Ok(Some((path.to_path_buf(), content)))
+}
+
+/// Settings of `base` that `config` leaves unchanged removed, so a file
+/// written from `config` keeps inheriting them
+pub(super) fn without_inherited(config: &Table, base: &Table) -> Table {
+    config
+        .iter()
+        .filter_map(|(key, value)| match (value, base.get(key)) {
+            (Value::Table(table), Some(Value::Table(inherited))) => {
+                let table = without_inherited(table, inherited);
+                (!table.is_empty()).then(|| (key.clone(), Value::Table(table)))
+            }
+            (value, Some(inherited)) if value == inherited => None,
+            (value, _) => Some((key.clone(), value.clone())),
+        })
+        .collect()
+}
+
+/// The built-in defaults as a table
+fn defaults() -> Result<Table> {
+    Table::try_from(AgentCrewConfig::default()).with_context(|| "Failed to serialize defaults")
+}
+
+/// Dotted key path of a config variable: `AGENTCREW_MAX_AGENTS` is
+/// `max_agents`, and `__` separates tables as in `AGENTCREW_PORTS__BASE`
+fn env_key(name: &str) -> Option<Vec<String>> {
+    let key = name.strip_prefix(ENV_PREFIX)?;
+    if key.is_empty() || RESERVED_ENV.contains(&key) {
+        return None;
+    }
+    Some(key.split("__").map(|part| part.to_lowercase()).collect())
+}
+
+/// Every key a variable may set: the defaults with each optional value and
+/// section filled in, and an unnamed provider standing for all of `agents`
+fn env_keys() -> Result<Table> {
+    let policy = CheckpointPolicy {
+        every_minutes: Some(1),
+        every_files: Some(1),
+        on_question: true,
+    };
+    let provider = AgentProviderConfig {
+        command: Some(String::new()),
+        model: Some(String::new()),
+        api_key_env: Some(String::new()),
+        env: Some(Vec::new()),
+        max_instances: Some(1),
+        timeout_minutes: Some(1),
+        checkpoint: Some(policy),
+        ..AgentProviderConfig::default()
+    };
+    let sample = AgentCrewConfig {
+        default_prompt: Some(String::new()),
+        agents: BTreeMap::from([(String::new(), provider)]),
+        checkpoint: policy,
+        harvest: HarvestConfig {
+            timeout_secs: Some(1),
+            steps: Vec::new(),
+        },
+        ports: PortConfig {
+            base: 1,
+            block_size: 1,
+        },
+        worktree: WorktreeConfig {
+            keep_branches: true,
+            preserve_logs: false,
+        },
+        ui: UiConfig {
+            refresh_rate: 1,
+            history_lines: 1,
+        },
+        ..AgentCrewConfig::default()
+    };
+    Table::try_from(sample).with_context(|| "Failed to serialize config keys")
+}
+
+/// Sample value of the single config value a variable's key path names,
+/// with `agents.<provider>` keys allowed for every known provider; `None`
+/// when the path names no such value
+fn env_sample<'a>(keys: &'a Table, path: &[String]) -> Option<&'a Value> {
+    let mut path = path.to_vec();
+    if path.len() > 2 && path[0] == "agents" {
+        if !KNOWN_PROVIDERS.contains(&path[1].as_str()) {
+            return None;
+        }
+        path[1] = String::new();
+    }
+    lookup(keys, &path).filter(|value| !value.is_table())
+}
+
+/// Value of a config variable, typed like `sample`. Strings and lists stay
+/// text (lists split on commas); anything else is parsed as a TOML value
+/// and falls back to a string.
+fn env_value(raw: &str, sample: &Value) -> Value {
+    match sample {
+        Value::String(_) => Value::String(raw.to_string()),
+        Value::Array(_) if !raw.trim_start().starts_with('[') => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        _ => toml::from_str::<Table>(&format!("value = {}", raw))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| Value::String(raw.to_string())),
+    }
+}
+
+/// A table holding `value` under the parts of a key path
+fn nest(path: Vec<String>, value: Value) -> Table {
+    let mut table = Table::new();
+    if let Some((last, parents)) = path.split_last() {
+        table.insert(last.clone(), value);
+        for part in parents.iter().rev() {
+            let mut parent = Table::new();
+            parent.insert(part.clone(), Value::Table(table));
+            table = parent;
+        }
+    }
+    table
+}
+
+/// Value at a key path, if set
+fn lookup<'a>(table: &'a Table, path: &[String]) -> Option<&'a Value> {
+    let (last, parents) = path.split_last()?;
+    let mut table = table;
+    for part in parents {
+        table = table.get(part)?.as_table()?;
+    }
+    table.get(last)
+}
+
+/// Merge `layer` into `base`, tables key by key and anything else by
+/// replacement, recording `origin` for every value it sets
+fn merge(
+    base: &mut Table,
+    layer: Table,
+    prefix: &str,
+    origin: &ConfigOrigin,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+) {
+    for (key, value) in layer {
+        let dotted = join(prefix, &key);
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(existing)), Value::Table(table)) => {
+                merge(existing, table, &dotted, origin, origins)
+            }
+            (_, value) => {
+                // Whatever was below a replaced key is gone with it
+                origins.retain(|key, _| !key.starts_with(&format!("{}.", dotted)));
+                record(&dotted, &value, origin, origins);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Record `origin` for a value and everything below it
+fn record(
+    dotted: &str,
+    value: &Value,
+    origin: &ConfigOrigin,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+) {
+    match value {
+        Value::Table(table) => {
+            for (key, value) in table {
+                record(&join(dotted, key), value, origin, origins);
+            }
+        }
+        _ => {
+            origins.insert(dotted.to_string(), origin.clone());
+        }
+    }
+}
+
+/// Non-table values of a table by dotted key
+fn flatten(table: &Table, prefix: &str, leaves: &mut Vec<(String, Value)>) {
+    for (key, value) in table {
+        let dotted = join(prefix, key);
+        match value {
+            Value::Table(table) => flatten(table, &dotted, leaves),
+            value => leaves.push((dotted, value.clone())),
+        }
+    }
+}
+
+fn join(prefix: &str, key: &str) -> String {
+    match prefix {
+        "" => key.to_string(),
+        prefix => format!("{}.{}", prefix, key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_layer_precedence() {
+        let global = PathBuf::from("/home/dev/.config/agentcrew/config.toml");
+        let project = PathBuf::from(".agentcrew/config.toml");
+        let layered = LayeredConfig::resolve(
+            Some((
+                global.clone(),
+                "max_agents = 8\nretention_days = 7\ndefault_agents = [\"gpt:1\"]\n\n[ports]\nbase = 6000\nblock_size = 5\n"
+                    .to_string(),
+            )),
+            Some((
+                project.clone(),
+                "project_name = \"demo\"\nmax_agents = 3\n\n[ports]\nbase = 7000\n".to_string(),
+            )),
+            env(&[
+                ("AGENTCREW_MAX_AGENTS", "2"),
+                ("AGENTCREW_DEFAULT_AGENTS", "claude:1, gpt:2"),
+                ("AGENTCREW_AGENTS__CLAUDE__MAX_INSTANCES", "4"),
+                ("AGENTCREW_PORT", "4000"),
+                ("HOME", "/home/dev"),
+            ]),
+        )
+        .expect("Should resolve");
+
+        let config = &layered.config;
+        assert_eq!(config.project_name, "demo");
+        assert_eq!(config.max_agents, 2);
+        assert_eq!(config.retention_days, 7);
+        assert_eq!(config.default_agents, vec!["claude:1", "gpt:2"]);
+        assert_eq!(config.max_instances("claude"), Some(4));
+        assert_eq!((config.ports.base, config.ports.block_size), (7000, 5));
+
+        assert_eq!(layered.origin("version"), &ConfigOrigin::Default);
+        assert_eq!(
+            layered.origin("retention_days"),
+            &ConfigOrigin::Global(global.clone())
+        );
+        assert_eq!(
+            layered.origin("ports.block_size"),
+            &ConfigOrigin::Global(global)
+        );
+        assert_eq!(
+            layered.origin("ports.base"),
+            &ConfigOrigin::Project(project.clone())
+        );
+        assert_eq!(
+            layered.origin("project_name"),
+            &ConfigOrigin::Project(project)
+        );
+        assert_eq!(
+            layered.origin("max_agents"),
+            &ConfigOrigin::Env("AGENTCREW_MAX_AGENTS".to_string())
+        );
+
+        let entries = layered.entries().expect("Should flatten");
+        let (_, value, origin) = entries
+            .iter()
+            .find(|(key, _, _)| key == "agents.claude.max_instances")
+            .expect("Should list nested values");
+        assert_eq!(value, &Value::Integer(4));
+        assert_eq!(
+            origin.to_string(),
+            "env (AGENTCREW_AGENTS__CLAUDE__MAX_INSTANCES)"
+        );
+    }
+
+    #[test]
+    fn test_layer_errors() {
+        let error = LayeredConfig::resolve(
+            None,
+            Some((PathBuf::from("config.toml"), "max_agents = ".to_string())),
+            Vec::new(),
+        )
+        .unwrap_err();
+        assert!(format!("{:#}", error).contains("Failed to parse config.toml"));
+
+        let error = LayeredConfig::resolve(None, None, env(&[("AGENTCREW_MAX_AGENTS", "many")]))
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("Invalid configuration"));
+    }
+
+    #[test]
+    fn test_env_values_typed_by_key() {
+        // Neither key is set by the defaults or a file
+        let layered = LayeredConfig::resolve(
+            None,
+            None,
+            env(&[
+                ("AGENTCREW_AGENTS__CLAUDE__ENV", "PATH, HOME"),
+                ("AGENTCREW_DEFAULT_PROMPT", "42"),
+            ]),
+        )
+        .expect("Should resolve");
+
+        let config = &layered.config;
+        assert_eq!(
+            config.agents["claude"].env,
+            Some(vec!["PATH".to_string(), "HOME".to_string()])
+        );
+        assert_eq!(config.default_prompt.as_deref(), Some("42"));
+    }
+
+    #[test]
+    fn test_unknown_env_keys_are_ignored() {
+        let layered = LayeredConfig::resolve(
+            None,
+            None,
+            env(&[
+                ("AGENTCREW_TOKEN", "secret"),
+                ("AGENTCREW_PORTS", "{ base = 5000 }"),
+                ("AGENTCREW_PORTS__HOST", "localhost"),
+                ("AGENTCREW_AGENTS__LLAMA__MODEL", "7b"),
+                ("AGENTCREW_AGENT", "mock-1"),
+                ("AGENTCREW_AGENTS__GPT__MODEL", "o3"),
+                ("AGENTCREW_AGENTS__JULES__CHECKPOINT__EVERY_FILES", "5"),
+                ("AGENTCREW_HARVEST__TIMEOUT_SECS", "60"),
+            ]),
+        )
+        .expect("Should resolve");
+
+        assert_eq!(
+            layered.ignored_env(),
+            [
+                "AGENTCREW_AGENTS__LLAMA__MODEL",
+                "AGENTCREW_PORTS",
+                "AGENTCREW_PORTS__HOST",
+                "AGENTCREW_TOKEN",
+            ]
+        );
+        let config = &layered.config;
+        assert_eq!(config.agents["gpt"].model.as_deref(), Some("o3"));
+        assert_eq!(
+            config.agents["jules"]
+                .checkpoint
+                .map(|policy| policy.every_files),
+            Some(Some(5))
+        );
+        assert_eq!(config.harvest.timeout_secs, Some(60));
+        assert_eq!(config.ports.base, 4000);
+    }
+
+    #[test]
+    fn test_without_inherited() {
+        let base: Table =
+            toml::from_str("max_agents = 5\n[ports]\nbase = 4000\nblock_size = 10\n").unwrap();
+        let config: Table = toml::from_str(
+            "max_agents = 5\nproject_name = \"demo\"\n[ports]\nbase = 4000\nblock_size = 20\n",
+        )
+        .unwrap();
+        let expected: Table =
+            toml::from_str("project_name = \"demo\"\n[ports]\nblock_size = 20\n").unwrap();
+        assert_eq!(without_inherited(&config, &base), expected);
+    }
+}
@@ -1,10 +1,19 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::Duration;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 
+use crate::agents::KNOWN_PROVIDERS;
+
+mod layers;
+
+pub use layers::{ConfigOrigin, LayeredConfig};
+
 /// Project configuration for agentcrew
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct AgentCrewConfig {
     /// Project name
     pub project_name: String,
@@ -18,6 +27,285 @@ pub struct AgentCrewConfig {
     pub default_prompt: Option<String>,
     /// Configuration version for future compatibility
     pub version: String,
+    /// Days finished sessions stay in the database before `clean` prunes them
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+    /// Per-provider settings (`[agents.<name>]` tables)
+    #[serde(default)]
+    pub agents: BTreeMap<String, AgentProviderConfig>,
+    /// Automatic checkpoints of agent worktrees (`[checkpoint]` table)
+    #[serde(default, skip_serializing_if = "CheckpointPolicy::is_disabled")]
+    pub checkpoint: CheckpointPolicy,
+    /// Verification pipeline run by `agentcrew harvest` (`[harvest]` table)
+    #[serde(default, skip_serializing_if = "HarvestConfig::is_empty")]
+    pub harvest: HarvestConfig,
+    /// Ports reserved for each agent's dev servers (`[ports]` table)
+    #[serde(default, skip_serializing_if = "PortConfig::is_default")]
+    pub ports: PortConfig,
+    /// What `agentcrew clean` keeps of finished agents (`[worktree]` table)
+    #[serde(default, skip_serializing_if = "WorktreeConfig::is_default")]
+    pub worktree: WorktreeConfig,
+    /// Terminal UI settings (`[ui]` table)
+    #[serde(default, skip_serializing_if = "UiConfig::is_default")]
+    pub ui: UiConfig,
+}
+
+/// Retention window used when `retention_days` is not configured
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+
+fn default_retention_days() -> u32 {
+    DEFAULT_RETENTION_DAYS
+}
+
+/// Settings for a single agent provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentProviderConfig {
+    /// Whether the provider may be deployed
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Executable launched instead of the provider's own CLI
+    pub command: Option<String>,
+    /// Model for instances that do not pick one with `@model=`
+    pub model: Option<String>,
+    /// Variable holding the provider's API key; agents fail to launch
+    /// while it is unset
+    pub api_key_env: Option<String>,
+    /// When set, agents only see these environment variables, besides
+    /// `api_key_env`, basics such as `PATH` and `HOME`, and agentcrew's own
+    pub env: Option<Vec<String>>,
+    /// Maximum number of concurrent instances of this provider
+    pub max_instances: Option<u32>,
+    /// Minutes an instance may run before it is stopped and marked failed
+    pub timeout_minutes: Option<u64>,
+    /// Replaces the project-wide `[checkpoint]` policy for this provider
+    pub checkpoint: Option<CheckpointPolicy>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for AgentProviderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            command: None,
+            model: None,
+            api_key_env: None,
+            env: None,
+            max_instances: None,
+            timeout_minutes: None,
+            checkpoint: None,
+        }
+    }
+}
+
+impl AgentProviderConfig {
+    /// Variables agents keep when their environment is restricted with `env`
+    pub fn env_allowlist(&self) -> Option<Vec<String>> {
+        let mut names = self.env.clone()?;
+        names.extend(self.api_key_env.clone());
+        Some(names)
+    }
+
+    /// How long an instance may run
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_minutes
+            .map(|minutes| Duration::from_secs(minutes * 60))
+    }
+
+    /// Check the settings of provider `name`
+    fn validate(&self, name: &str) -> Result<()> {
+        if self.max_instances == Some(0) {
+            anyhow::bail!(
+                "[agents.{}] max_instances must be at least 1; set enabled = false to turn the provider off",
+                name
+            );
+        }
+        if self.timeout_minutes == Some(0) {
+            anyhow::bail!("[agents.{}] timeout_minutes must be at least 1", name);
+        }
+        for (key, value) in [("command", &self.command), ("model", &self.model)] {
+            if value.as_ref().is_some_and(|value| value.trim().is_empty()) {
+                anyhow::bail!("[agents.{}] {} is empty", name, key);
+            }
+        }
+        let variables = self.api_key_env.iter().chain(self.env.iter().flatten());
+        for variable in variables {
+            let valid = variable
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && variable.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                anyhow::bail!(
+                    "[agents.{}] '{}' is not an environment variable name",
+                    name,
+                    variable
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// When the supervisor commits an agent's worktree on its own
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CheckpointPolicy {
+    /// Checkpoint once this many minutes passed since the last one
+    pub every_minutes: Option<u64>,
+    /// Checkpoint once this many files have uncommitted changes
+    pub every_files: Option<usize>,
+    /// Checkpoint whenever the agent asks a question
+    #[serde(default)]
+    pub on_question: bool,
+}
+
+impl CheckpointPolicy {
+    /// Whether no automatic checkpoints are taken
+    pub fn is_disabled(&self) -> bool {
+        self.every_minutes.is_none() && self.every_files.is_none() && !self.on_question
+    }
+}
+
+/// Checkpoint policies resolved for every provider
+#[derive(Debug, Clone, Default)]
+pub struct CheckpointPolicies {
+    pub default: CheckpointPolicy,
+    pub providers: BTreeMap<String, CheckpointPolicy>,
+}
+
+impl CheckpointPolicies {
+    /// Policy applying to instances of `provider`
+    pub fn get(&self, provider: &str) -> CheckpointPolicy {
+        self.providers.get(provider).copied().unwrap_or(self.default)
+    }
+}
+
+/// Verification pipeline run in every completed agent's worktree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HarvestConfig {
+    /// Seconds a single step may run before it is killed
+    pub timeout_secs: Option<u64>,
+    /// Steps in the order they run (`[[harvest.steps]]`)
+    #[serde(default)]
+    pub steps: Vec<HarvestStep>,
+}
+
+impl HarvestConfig {
+    /// Default per-step timeout
+    pub const DEFAULT_TIMEOUT_SECS: u64 = 600;
+
+    /// Whether no pipeline is configured
+    pub fn is_empty(&self) -> bool {
+        self.timeout_secs.is_none() && self.steps.is_empty()
+    }
+
+    /// Per-step timeout
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.timeout_secs.unwrap_or(Self::DEFAULT_TIMEOUT_SECS))
+    }
+}
+
+/// A single harvest step such as `cargo test`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarvestStep {
+    /// Short name shown as a column of the ranking
+    pub name: String,
+    /// Shell command run in the agent's worktree
+    pub command: String,
+    /// Regex counting failures in the step output, one per matching line;
+    /// without it a failing step counts as a single failure
+    pub failure_pattern: Option<String>,
+}
+
+/// How ports are handed out to agents: consecutive blocks of `block_size`
+/// ports starting at `base`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PortConfig {
+    /// First port of the first block
+    pub base: u16,
+    /// Number of ports reserved for each agent
+    pub block_size: u16,
+}
+
+impl PortConfig {
+    /// Whether the built-in port layout is used
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for PortConfig {
+    fn default() -> Self {
+        Self {
+            base: 4000,
+            block_size: 10,
+        }
+    }
+}
+
+/// What `agentcrew clean` keeps when it removes a finished agent's worktree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorktreeConfig {
+    /// Keep the agent's branch, as with `--keep-branches`
+    pub keep_branches: bool,
+    /// Keep the agent's log files
+    pub preserve_logs: bool,
+}
+
+impl WorktreeConfig {
+    /// Whether the built-in behavior is used
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for WorktreeConfig {
+    fn default() -> Self {
+        Self {
+            keep_branches: false,
+            preserve_logs: true,
+        }
+    }
+}
+
+/// Terminal UI settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UiConfig {
+    /// Milliseconds between refreshes from the database and logs
+    pub refresh_rate: u64,
+    /// Log lines loaded per agent when the TUI starts
+    pub history_lines: usize,
+}
+
+impl UiConfig {
+    /// Accepted range of `refresh_rate`
+    pub const REFRESH_RATE_RANGE: std::ops::RangeInclusive<u64> = 50..=10_000;
+
+    /// Whether the built-in settings are used
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Time between refreshes
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_millis(self.refresh_rate)
+    }
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            refresh_rate: 500,
+            history_lines: 50,
+        }
+    }
 }
 
 impl Default for AgentCrewConfig {
@@ -29,6 +317,13 @@ impl Default for AgentCrewConfig {
             max_agents: 5,
             default_prompt: None,
             version: "0.1.0".to_string(),
+            retention_days: DEFAULT_RETENTION_DAYS,
+            agents: BTreeMap::new(),
+            checkpoint: CheckpointPolicy::default(),
+            harvest: HarvestConfig::default(),
+            ports: PortConfig::default(),
+            worktree: WorktreeConfig::default(),
+            ui: UiConfig::default(),
         }
     }
 }
@@ -43,24 +338,29 @@ impl AgentCrewConfig {
         }
     }
 
-    /// Load configuration from .agentcrew/config.toml
+    /// Load the effective configuration: the built-in defaults, overridden
+    /// by the user-global config, then .agentcrew/config.toml, then
+    /// `AGENTCREW_*` environment variables
     pub fn load() -> Result<Self> {
+        Ok(Self::load_layered()?.config)
+    }
+
+    /// Load the effective configuration with the origin of every value
+    pub fn load_layered() -> Result<LayeredConfig> {
         let config_path = Self::config_file_path()?;
         
         if !config_path.exists() {
             anyhow::bail!("agentcrew not initialized. Run 'agentcrew init' first.");
         }
 
-        let content = fs::read_to_string(&config_path)
-            .with_context(|| format!("Failed to read config file: {}", config_path.display()))?;
-        
-        let config: AgentCrewConfig = toml::from_str(&content)
-            .with_context(|| "Failed to parse config.toml")?;
-        
-        Ok(config)
+        let env = std::env::vars_os()
+            .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)));
+        LayeredConfig::resolve(Self::read_global()?, layers::read_optional(&config_path)?, env)
     }
 
-    /// Save configuration to .agentcrew/config.toml
+    /// Save configuration to .agentcrew/config.toml, leaving out settings
+    /// that match the defaults and the user-global config so later changes
+    /// to those still apply
     pub fn save(&self) -> Result<()> {
         let agentcrew_dir = Self::agentcrew_dir()?;
         
@@ -70,8 +370,13 @@ impl AgentCrewConfig {
                 .with_context(|| format!("Failed to create directory: {}", agentcrew_dir.display()))?;
         }
 
+        let project = layers::without_inherited(
+            &toml::Table::try_from(self).with_context(|| "Failed to serialize config")?,
+            &toml::Table::try_from(Self::inherited()?).with_context(|| "Failed to serialize config")?,
+        );
+
         let config_path = Self::config_file_path()?;
-        let content = toml::to_string_pretty(self)
+        let content = toml::to_string_pretty(&project)
             .with_context(|| "Failed to serialize config")?;
         
         fs::write(&config_path, content)
@@ -80,6 +385,70 @@ impl AgentCrewConfig {
         Ok(())
     }
 
+    /// Configuration a new project starts from: the defaults overridden by
+    /// the user-global config
+    pub fn inherited() -> Result<Self> {
+        Ok(LayeredConfig::resolve(Self::read_global()?, None, Vec::new())?.config)
+    }
+
+    /// The user-global config file and its contents, if there is one
+    fn read_global() -> Result<Option<(PathBuf, String)>> {
+        match Self::global_config_path() {
+            Some(path) => layers::read_optional(&path),
+            None => Ok(None),
+        }
+    }
+
+    /// Instance cap configured for a provider, if any
+    pub fn max_instances(&self, provider: &str) -> Option<u32> {
+        self.agents.get(provider).and_then(|agent| agent.max_instances)
+    }
+
+    /// Whether a provider may be deployed
+    pub fn is_enabled(&self, provider: &str) -> bool {
+        self.agents.get(provider).is_none_or(|agent| agent.enabled)
+    }
+
+    /// Check values that parse but cannot work, naming the offending key
+    pub fn validate(&self) -> Result<()> {
+        if self.max_agents == 0 {
+            anyhow::bail!("max_agents must be at least 1");
+        }
+        if self.ports.block_size == 0 {
+            anyhow::bail!("[ports] block_size must be at least 1");
+        }
+        for (name, agent) in &self.agents {
+            if !KNOWN_PROVIDERS.contains(&name.as_str()) {
+                anyhow::bail!(
+                    "Unknown provider in [agents.{}] (expected one of: {})",
+                    name,
+                    KNOWN_PROVIDERS.join(", ")
+                );
+            }
+            agent.validate(name)?;
+        }
+        if !UiConfig::REFRESH_RATE_RANGE.contains(&self.ui.refresh_rate) {
+            anyhow::bail!(
+                "[ui] refresh_rate must be between {} and {} milliseconds",
+                UiConfig::REFRESH_RATE_RANGE.start(),
+                UiConfig::REFRESH_RATE_RANGE.end()
+            );
+        }
+        Ok(())
+    }
+
+    /// Automatic checkpoint policy of every provider
+    pub fn checkpoint_policies(&self) -> CheckpointPolicies {
+        CheckpointPolicies {
+            default: self.checkpoint,
+            providers: self
+                .agents
+                .iter()
+                .filter_map(|(name, agent)| Some((name.clone(), agent.checkpoint?)))
+                .collect(),
+        }
+    }
+
     /// Check if agentcrew is already initialized in current directory
     pub fn is_initialized() -> bool {
         Self::config_file_path()
@@ -87,6 +456,13 @@ impl AgentCrewConfig {
             .unwrap_or(false)
     }
 
+    /// Get the user-global config file path: `config.toml` in
+    /// `$AGENTCREW_CONFIG_DIR`, else in `$XDG_CONFIG_HOME/agentcrew` or
+    /// `~/.config/agentcrew`
+    pub fn global_config_path() -> Option<PathBuf> {
+        layers::global_config_dir().map(|dir| dir.join("config.toml"))
+    }
+
     /// Get the .agentcrew directory path
     pub fn agentcrew_dir() -> Result<PathBuf> {
         let current_dir = std::env::current_dir()
@@ -109,16 +485,30 @@ impl AgentCrewConfig {
         Ok(Self::agentcrew_dir()?.join("logs"))
     }
 
+    /// Get the directory agent worktrees are checked out into
+    pub fn worktrees_dir() -> Result<PathBuf> {
+        Ok(Self::agentcrew_dir()?.join("worktrees"))
+    }
+
     /// Get the database file path
     pub fn database_path() -> Result<PathBuf> {
         Ok(Self::agentcrew_dir()?.join("agentcrew.db"))
     }
+
+    /// Get the Unix socket the daemon listens on
+    pub fn socket_path() -> Result<PathBuf> {
+        Ok(Self::agentcrew_dir()?.join("agentcrew.sock"))
+    }
+
+    /// Get the daemon log file path
+    pub fn daemon_log_path() -> Result<PathBuf> {
+        Ok(Self::logs_dir()?.join("daemon.log"))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use tempfile::TempDir;
 
     #[test]
     fn test_config_default() {
@@ -147,4 +537,181 @@ mod tests {
         assert_eq!(config.project_name, deserialized.project_name);
         assert_eq!(config.max_agents, deserialized.max_agents);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_provider_caps() {
+        let config: AgentCrewConfig = toml::from_str(
+            r#"
+            project_name = "demo"
+            project_root = "."
+            default_agents = ["claude:1"]
+            max_agents = 4
+            version = "0.1.0"
+
+            [agents.claude]
+            max_instances = 2
+            "#,
+        )
+        .expect("Should parse");
+
+        assert_eq!(config.max_instances("claude"), Some(2));
+        assert_eq!(config.max_instances("gpt"), None);
+        assert!(config.harvest.is_empty());
+    }
+
+    #[test]
+    fn test_checkpoint_policies() {
+        let config: AgentCrewConfig = toml::from_str(
+            r#"
+            project_name = "demo"
+            project_root = "."
+            default_agents = ["claude:1"]
+            max_agents = 4
+            version = "0.1.0"
+
+            [checkpoint]
+            every_minutes = 10
+
+            [agents.claude]
+            checkpoint = { every_files = 5, on_question = true }
+            "#,
+        )
+        .expect("Should parse");
+
+        let policies = config.checkpoint_policies();
+        assert_eq!(policies.get("gpt").every_minutes, Some(10));
+        let claude = policies.get("claude");
+        assert_eq!(claude.every_minutes, None);
+        assert_eq!(claude.every_files, Some(5));
+        assert!(claude.on_question);
+        assert!(AgentCrewConfig::default().checkpoint.is_disabled());
+        assert_eq!(config.retention_days, DEFAULT_RETENTION_DAYS);
+    }
+
+    #[test]
+    fn test_port_config() {
+        let config: AgentCrewConfig = toml::from_str(
+            r#"
+            project_name = "demo"
+            project_root = "."
+            default_agents = ["claude:1"]
+            max_agents = 4
+            version = "0.1.0"
+
+            [ports]
+            base = 5000
+            "#,
+        )
+        .expect("Should parse");
+
+        assert_eq!(config.ports.base, 5000);
+        assert_eq!(config.ports.block_size, PortConfig::default().block_size);
+        assert!(AgentCrewConfig::default().ports.is_default());
+    }
+
+    #[test]
+    fn test_harvest_steps() {
+        let config: AgentCrewConfig = toml::from_str(
+            r#"
+            project_name = "demo"
+            project_root = "."
+            default_agents = ["claude:1"]
+            max_agents = 4
+            version = "0.1.0"
+
+            [harvest]
+            timeout_secs = 30
+
+            [[harvest.steps]]
+            name = "build"
+            command = "cargo build"
+
+            [[harvest.steps]]
+            name = "test"
+            command = "cargo test"
+            failure_pattern = "^test .* FAILED$"
+            "#,
+        )
+        .expect("Should parse");
+
+        assert_eq!(config.harvest.steps.len(), 2);
+        assert_eq!(config.harvest.steps[1].failure_pattern.as_deref(), Some("^test .* FAILED$"));
+        assert_eq!(config.harvest.timeout(), std::time::Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_provider_sections() {
+        let config: AgentCrewConfig = toml::from_str(
+            r#"
+            project_name = "demo"
+            project_root = "."
+            default_agents = ["claude:1"]
+            max_agents = 4
+            version = "0.1.0"
+
+            [agents.claude]
+            command = "/opt/claude/bin/claude"
+            model = "opus"
+            env = ["GITHUB_TOKEN"]
+            api_key_env = "ANTHROPIC_API_KEY"
+            timeout_minutes = 90
+
+            [agents.gpt]
+            enabled = false
+
+            [worktree]
+            preserve_logs = false
+
+            [ui]
+            refresh_rate = 250
+            "#,
+        )
+        .expect("Should parse");
+
+        let claude = &config.agents["claude"];
+        assert!(claude.enabled);
+        assert_eq!(claude.model.as_deref(), Some("opus"));
+        assert_eq!(
+            claude.env_allowlist(),
+            Some(vec!["GITHUB_TOKEN".to_string(), "ANTHROPIC_API_KEY".to_string()])
+        );
+        assert_eq!(claude.timeout(), Some(Duration::from_secs(90 * 60)));
+        assert_eq!(AgentProviderConfig::default().env_allowlist(), None);
+        assert!(!config.is_enabled("gpt"));
+        assert!(config.is_enabled("jules"));
+        assert!(!config.worktree.preserve_logs);
+        assert!(!config.worktree.keep_branches);
+        assert_eq!(config.ui.refresh_interval(), Duration::from_millis(250));
+        assert_eq!(config.ui.history_lines, UiConfig::default().history_lines);
+        config.validate().expect("Should be valid");
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let error = |toml: &str| {
+            let toml = format!(
+                "project_name = \"demo\"\nproject_root = \".\"\ndefault_agents = []\nmax_agents = 4\nversion = \"0.1.0\"\n{}",
+                toml
+            );
+            let config: Result<AgentCrewConfig, _> = toml::from_str(&toml);
+            match config {
+                Ok(config) => config.validate().unwrap_err().to_string(),
+                Err(e) => e.to_string(),
+            }
+        };
+
+        assert!(error("[agents.claude]\nmax_instance = 2").contains("unknown field `max_instance`"));
+        assert!(error("[ui]\ntheme = \"dark\"").contains("unknown field `theme`"));
+        assert!(error("[agents.claud]\nmodel = \"opus\"")
+            .contains("Unknown provider in [agents.claud]"));
+        assert!(error("[agents.gpt]\nmax_instances = 0").contains("enabled = false"));
+        assert!(error("[agents.gpt]\ntimeout_minutes = 0").contains("timeout_minutes"));
+        assert_eq!(
+            error("[agents.gpt]\ncommand = \" \""),
+            "[agents.gpt] command is empty"
+        );
+        assert!(error("[agents.gpt]\nenv = [\"OPENAI KEY\"]")
+            .contains("'OPENAI KEY' is not an environment variable name"));
+        assert!(error("[ui]\nrefresh_rate = 5").contains("between 50 and 10000"));
+    }
+}
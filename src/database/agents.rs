@@ -0,0 +1,294 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::{sql_timestamp, AgentRecord, AgentStatus, Database};
+
+const AGENT_COLUMNS: &str = "id, session_id, agent_type, instance_number, worktree_path, \
+    branch_name, status, progress, started_at, last_activity, process_id, options";
+
+impl Database {
+    /// Register a new agent instance in the `initializing` state
+    pub async fn create_agent(
+        &self,
+        session_id: &str,
+        agent_type: &str,
+        instance_number: u32,
+        options: &BTreeMap<String, String>,
+    ) -> Result<AgentRecord> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let options = if options.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(options)?)
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO agents (id, session_id, agent_type, instance_number, status, options)
+            VALUES (?, ?, ?, ?, 'initializing', ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(session_id)
+        .bind(agent_type)
+        .bind(instance_number)
+        .bind(options)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to create agent {}-{}", agent_type, instance_number))?;
+
+        self.get_agent(&id).await
+    }
+
+    /// Fetch an agent by id
+    pub async fn get_agent(&self, agent_id: &str) -> Result<AgentRecord> {
+        sqlx::query_as::<_, AgentRecord>(&format!(
+            "SELECT {} FROM agents WHERE id = ?",
+            AGENT_COLUMNS
+        ))
+        .bind(agent_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_id))
+    }
+
+    /// List all agents of a session ordered by type and instance number
+    pub async fn list_agents(&self, session_id: &str) -> Result<Vec<AgentRecord>> {
+        let agents = sqlx::query_as::<_, AgentRecord>(&format!(
+            "SELECT {} FROM agents WHERE session_id = ? ORDER BY agent_type, instance_number",
+            AGENT_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(agents)
+    }
+
+    /// List agents of every session that are not in a terminal status
+    pub async fn list_unfinished_agents(&self) -> Result<Vec<AgentRecord>> {
+        let agents = sqlx::query_as::<_, AgentRecord>(&format!(
+            "SELECT {} FROM agents \
+             WHERE status IN ('initializing', 'running', 'waiting', 'paused') \
+             ORDER BY started_at",
+            AGENT_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(agents)
+    }
+
+    /// Find an agent of a session by its name, e.g. `claude-1`
+    pub async fn find_agent(&self, session_id: &str, name: &str) -> Result<Option<AgentRecord>> {
+        let Some((agent_type, number)) = name.rsplit_once('-') else {
+            return Ok(None);
+        };
+        let Ok(number) = number.parse::<i64>() else {
+            return Ok(None);
+        };
+
+        let agent = sqlx::query_as::<_, AgentRecord>(&format!(
+            "SELECT {} FROM agents WHERE session_id = ? AND agent_type = ? AND instance_number = ?",
+            AGENT_COLUMNS
+        ))
+        .bind(session_id)
+        .bind(agent_type)
+        .bind(number)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(agent)
+    }
+
+    /// Move an agent to a new status
+    pub async fn update_agent_status(&self, agent_id: &str, status: AgentStatus) -> Result<()> {
+        sqlx::query(
+            "UPDATE agents SET status = ?, last_activity = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(status)
+        .bind(agent_id)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to set status of agent {}", agent_id))?;
+        Ok(())
+    }
+
+    /// Record the OS process running an agent (`None` once it exited)
+    pub async fn set_agent_process(&self, agent_id: &str, pid: Option<u32>) -> Result<()> {
+        sqlx::query("UPDATE agents SET process_id = ?, last_activity = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(pid)
+            .bind(agent_id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to record process of agent {}", agent_id))?;
+        Ok(())
+    }
+
+    /// Bump the last activity timestamp of an agent
+    pub async fn touch_agent(&self, agent_id: &str) -> Result<()> {
+        sqlx::query("UPDATE agents SET last_activity = CURRENT_TIMESTAMP WHERE id = ?")
+            .bind(agent_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Insert an agent row as recorded elsewhere, or update the existing one,
+    /// with the given status and no process
+    pub async fn restore_agent(&self, agent: &AgentRecord, status: AgentStatus) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO agents (id, session_id, agent_type, instance_number, worktree_path,
+                branch_name, status, progress, started_at, options)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                worktree_path = excluded.worktree_path, branch_name = excluded.branch_name,
+                status = excluded.status, process_id = NULL,
+                last_activity = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(&agent.id)
+        .bind(&agent.session_id)
+        .bind(&agent.agent_type)
+        .bind(agent.instance_number)
+        .bind(&agent.worktree_path)
+        .bind(&agent.branch_name)
+        .bind(status)
+        .bind(agent.progress)
+        .bind(sql_timestamp(agent.started_at))
+        .bind(&agent.options)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to restore agent {}", agent.name()))?;
+
+        Ok(())
+    }
+
+    /// Forget an agent's removed worktree and, when it was deleted as well,
+    /// its branch
+    pub async fn clear_agent_worktree(&self, agent_id: &str, branch_deleted: bool) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE agents SET worktree_path = NULL,
+                branch_name = CASE WHEN ? THEN NULL ELSE branch_name END
+            WHERE id = ?
+            "#,
+        )
+        .bind(branch_deleted)
+        .bind(agent_id)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to clear worktree of agent {}", agent_id))?;
+
+        Ok(())
+    }
+
+    /// Record the worktree and branch provisioned for an agent
+    pub async fn set_agent_worktree(
+        &self,
+        agent_id: &str,
+        worktree_path: &Path,
+        branch_name: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE agents
+            SET worktree_path = ?, branch_name = ?, last_activity = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+        )
+        .bind(worktree_path.to_string_lossy().as_ref())
+        .bind(branch_name)
+        .bind(agent_id)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to record worktree for agent {}", agent_id))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::AgentStatus;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_agent_lifecycle_rows() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("prompt", r#"{"claude":2}"#, "main", "abc123")
+            .await
+            .expect("Should create session");
+
+        let options = BTreeMap::from([("model".to_string(), "opus".to_string())]);
+        let first = db
+            .create_agent(&session.id, "claude", 1, &options)
+            .await
+            .expect("Should create agent");
+        db.create_agent(&session.id, "claude", 2, &BTreeMap::new())
+            .await
+            .expect("Should create agent");
+        assert_eq!(first.name(), "claude-1");
+        assert_eq!(first.status, AgentStatus::Initializing);
+        assert_eq!(first.options(), options);
+
+        db.set_agent_worktree(
+            &first.id,
+            Path::new("/tmp/wt/claude-1"),
+            "agentcrew/x/claude-1",
+        )
+        .await
+        .expect("Should record worktree");
+
+        let agents = db
+            .list_agents(&session.id)
+            .await
+            .expect("Should list agents");
+        assert_eq!(agents.len(), 2);
+        assert_eq!(agents[0].worktree_path.as_deref(), Some("/tmp/wt/claude-1"));
+        assert_eq!(
+            agents[0].branch_name.as_deref(),
+            Some("agentcrew/x/claude-1")
+        );
+        assert!(agents[1].worktree_path.is_none());
+        assert!(agents[1].options.is_none());
+
+        db.update_agent_status(&first.id, AgentStatus::Dismissed)
+            .await
+            .expect("Should update status");
+        db.set_agent_process(&first.id, Some(4242))
+            .await
+            .expect("Should record pid");
+        let found = db
+            .find_agent(&session.id, "claude-1")
+            .await
+            .expect("Should query agent")
+            .expect("Should find agent");
+        assert_eq!(found.status, AgentStatus::Dismissed);
+        assert_eq!(found.process_id, Some(4242));
+        assert!(db.find_agent(&session.id, "claude-9").await.unwrap().is_none());
+        assert!(db.find_agent(&session.id, "nonsense").await.unwrap().is_none());
+
+        db.restore_agent(&found, AgentStatus::Initializing)
+            .await
+            .expect("Should restore agent");
+        let restored = db.get_agent(&first.id).await.unwrap();
+        assert_eq!(restored.status, AgentStatus::Initializing);
+        assert_eq!(restored.process_id, None);
+        assert_eq!(restored.options(), options);
+
+        db.clear_agent_worktree(&first.id, false).await.unwrap();
+        let cleared = db.get_agent(&first.id).await.unwrap();
+        assert!(cleared.worktree_path.is_none());
+        assert_eq!(cleared.branch_name.as_deref(), Some("agentcrew/x/claude-1"));
+        db.clear_agent_worktree(&first.id, true).await.unwrap();
+        assert!(db.get_agent(&first.id).await.unwrap().branch_name.is_none());
+    }
+}
@@ -0,0 +1,149 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::path::Path;
+
+use super::{sql_timestamp, Database, ExecProcessRecord};
+
+const EXEC_COLUMNS: &str =
+    "id, session_id, agent_id, command, pid, start_time, log_path, started_at, stopped_at";
+
+impl Database {
+    /// Start tracking a command detached into an agent's worktree
+    pub async fn record_exec_process(
+        &self,
+        session_id: &str,
+        agent_id: &str,
+        command: &str,
+        pid: u32,
+        start_time: Option<u64>,
+        log_path: &Path,
+    ) -> Result<ExecProcessRecord> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO exec_processes (session_id, agent_id, command, pid, start_time, log_path, started_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(session_id)
+        .bind(agent_id)
+        .bind(command)
+        .bind(pid as i64)
+        .bind(start_time.map(|ticks| ticks as i64))
+        .bind(log_path.to_string_lossy())
+        .bind(sql_timestamp(Utc::now()))
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to record process {}", pid))?
+        .last_insert_rowid();
+
+        let process = sqlx::query_as::<_, ExecProcessRecord>(&format!(
+            "SELECT {} FROM exec_processes WHERE id = ?",
+            EXEC_COLUMNS
+        ))
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(process)
+    }
+
+    /// Detached commands of a session that have not been stopped, oldest first
+    pub async fn running_exec_processes(&self, session_id: &str) -> Result<Vec<ExecProcessRecord>> {
+        let processes = sqlx::query_as::<_, ExecProcessRecord>(&format!(
+            "SELECT {} FROM exec_processes WHERE session_id = ? AND stopped_at IS NULL ORDER BY id",
+            EXEC_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(processes)
+    }
+
+    /// Detached commands of one agent that have not been stopped, oldest first
+    pub async fn running_agent_exec_processes(
+        &self,
+        agent_id: &str,
+    ) -> Result<Vec<ExecProcessRecord>> {
+        let processes = sqlx::query_as::<_, ExecProcessRecord>(&format!(
+            "SELECT {} FROM exec_processes WHERE agent_id = ? AND stopped_at IS NULL ORDER BY id",
+            EXEC_COLUMNS
+        ))
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(processes)
+    }
+
+    /// Stop tracking a detached command
+    pub async fn mark_exec_stopped(&self, id: i64) -> Result<()> {
+        sqlx::query("UPDATE exec_processes SET stopped_at = ? WHERE id = ?")
+            .bind(sql_timestamp(Utc::now()))
+            .bind(id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to update process #{}", id))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_track_exec_processes() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("serve", r#"{"mock":1}"#, "main", "abc123")
+            .await
+            .unwrap();
+        let agent = db
+            .create_agent(&session.id, "mock", 1, &BTreeMap::new())
+            .await
+            .unwrap();
+
+        let first = db
+            .record_exec_process(
+                &session.id,
+                &agent.id,
+                "npm run dev",
+                4242,
+                Some(1234),
+                Path::new("dev.log"),
+            )
+            .await
+            .expect("Should record process");
+        assert_eq!(first.pid, 4242);
+        assert_eq!(first.start_time, Some(1234));
+        assert_eq!(first.stopped_at, None);
+        db.record_exec_process(
+            &session.id,
+            &agent.id,
+            "cargo watch",
+            4343,
+            None,
+            Path::new("w.log"),
+        )
+        .await
+        .unwrap();
+
+        db.mark_exec_stopped(first.id).await.unwrap();
+        let running = db.running_exec_processes(&session.id).await.unwrap();
+        assert_eq!(running.len(), 1);
+        assert_eq!(running[0].command, "cargo watch");
+        let running = db.running_agent_exec_processes(&agent.id).await.unwrap();
+        assert_eq!(running.len(), 1);
+        assert!(db
+            .running_agent_exec_processes("other-agent")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,49 @@
+use anyhow::{Context, Result};
+
+use super::{sql_timestamp, Database, FileChangeRecord};
+
+const FILE_CHANGE_COLUMNS: &str = "id, agent_id, session_id, file_path, change_type, \
+    COALESCE(lines_added, 0) AS lines_added, COALESCE(lines_removed, 0) AS lines_removed, \
+    commit_hash, timestamp";
+
+impl Database {
+    /// Store the paths touched by one commit
+    pub async fn record_file_changes(&self, changes: &[FileChangeRecord]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for change in changes {
+            sqlx::query(
+                r#"
+                INSERT INTO file_changes (agent_id, session_id, file_path, change_type,
+                    lines_added, lines_removed, commit_hash, timestamp)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(&change.agent_id)
+            .bind(&change.session_id)
+            .bind(&change.file_path)
+            .bind(change.change_type)
+            .bind(change.lines_added)
+            .bind(change.lines_removed)
+            .bind(&change.commit_hash)
+            .bind(sql_timestamp(change.timestamp))
+            .execute(&mut *tx)
+            .await
+            .with_context(|| format!("Failed to record change of {}", change.file_path))?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every recorded file change of an agent, oldest first
+    pub async fn list_file_changes(&self, agent_id: &str) -> Result<Vec<FileChangeRecord>> {
+        let changes = sqlx::query_as::<_, FileChangeRecord>(&format!(
+            "SELECT {} FROM file_changes WHERE agent_id = ? ORDER BY timestamp, id",
+            FILE_CHANGE_COLUMNS
+        ))
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(changes)
+    }
+}
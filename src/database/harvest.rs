@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+
+use super::{sql_timestamp, Database, HarvestResultRecord};
+
+const HARVEST_COLUMNS: &str = "id, harvest_id, session_id, agent_id, step, command, exit_code, \
+    duration_ms, failures, timed_out, output, head_commit, harvested_at";
+
+impl Database {
+    /// Store the result of one harvest step and return its id
+    pub async fn record_harvest_result(&self, result: &HarvestResultRecord) -> Result<i64> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO harvest_results (harvest_id, session_id, agent_id, step, command,
+                exit_code, duration_ms, failures, timed_out, output, head_commit, harvested_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&result.harvest_id)
+        .bind(&result.session_id)
+        .bind(&result.agent_id)
+        .bind(&result.step)
+        .bind(&result.command)
+        .bind(result.exit_code)
+        .bind(result.duration_ms)
+        .bind(result.failures)
+        .bind(result.timed_out)
+        .bind(&result.output)
+        .bind(&result.head_commit)
+        .bind(sql_timestamp(result.harvested_at))
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to record harvest step {}", result.step))?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Results of every harvest of a session, in the order they ran
+    pub async fn list_harvest_results(&self, session_id: &str) -> Result<Vec<HarvestResultRecord>> {
+        let results = sqlx::query_as::<_, HarvestResultRecord>(&format!(
+            "SELECT {} FROM harvest_results WHERE session_id = ? ORDER BY id",
+            HARVEST_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(results)
+    }
+
+    /// Results of the most recent harvest of a session, in the order they ran
+    pub async fn latest_harvest(&self, session_id: &str) -> Result<Vec<HarvestResultRecord>> {
+        let results = sqlx::query_as::<_, HarvestResultRecord>(&format!(
+            "SELECT {} FROM harvest_results WHERE harvest_id = ( \
+                 SELECT harvest_id FROM harvest_results WHERE session_id = ? \
+                 ORDER BY harvested_at DESC, id DESC LIMIT 1 \
+             ) ORDER BY id",
+            HARVEST_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,276 @@
+use anyhow::{Context, Result};
+
+use super::{sql_timestamp, Database, DeliveryStatus, InteractionRecord, InteractionType};
+
+const INTERACTION_COLUMNS: &str = "id, agent_id, session_id, type, content, metadata, \
+    COALESCE(requires_response, FALSE) AS requires_response, responded_at, timestamp, \
+    delivery_status";
+
+impl Database {
+    /// Record an interaction of an agent and return its id
+    pub async fn record_interaction(
+        &self,
+        agent_id: &str,
+        session_id: &str,
+        interaction_type: InteractionType,
+        content: &str,
+        metadata: Option<&str>,
+    ) -> Result<i64> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO interactions (agent_id, session_id, type, content, metadata, requires_response)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(agent_id)
+        .bind(session_id)
+        .bind(interaction_type)
+        .bind(content)
+        .bind(metadata)
+        .bind(interaction_type == InteractionType::Question)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to record interaction of agent {}", agent_id))?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Record a brief or broadcast sent to one agent and whether it arrived
+    pub async fn record_delivery(
+        &self,
+        agent_id: &str,
+        session_id: &str,
+        interaction_type: InteractionType,
+        content: &str,
+        delivery_status: DeliveryStatus,
+        metadata: Option<&str>,
+    ) -> Result<i64> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO interactions (agent_id, session_id, type, content, metadata, delivery_status)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(agent_id)
+        .bind(session_id)
+        .bind(interaction_type)
+        .bind(content)
+        .bind(metadata)
+        .bind(delivery_status)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to record delivery to agent {}", agent_id))?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Insert an interaction recorded elsewhere, keeping its timestamps and
+    /// delivery state, and return its new id
+    pub async fn import_interaction(&self, interaction: &InteractionRecord) -> Result<i64> {
+        let id = sqlx::query(
+            r#"
+            INSERT INTO interactions (agent_id, session_id, type, content, metadata,
+                requires_response, responded_at, timestamp, delivery_status)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&interaction.agent_id)
+        .bind(&interaction.session_id)
+        .bind(interaction.interaction_type)
+        .bind(&interaction.content)
+        .bind(&interaction.metadata)
+        .bind(interaction.requires_response)
+        .bind(interaction.responded_at.map(sql_timestamp))
+        .bind(sql_timestamp(interaction.timestamp))
+        .bind(interaction.delivery_status)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to import interaction {}", interaction.id))?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    /// Checkpoints recorded for an agent, oldest first
+    pub async fn list_checkpoints(&self, agent_id: &str) -> Result<Vec<InteractionRecord>> {
+        let checkpoints = sqlx::query_as::<_, InteractionRecord>(&format!(
+            "SELECT {} FROM interactions WHERE agent_id = ? AND type = 'checkpoint' \
+             ORDER BY timestamp, id",
+            INTERACTION_COLUMNS
+        ))
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(checkpoints)
+    }
+
+    /// Unanswered questions of every agent, oldest first
+    pub async fn pending_questions(&self) -> Result<Vec<InteractionRecord>> {
+        let questions = sqlx::query_as::<_, InteractionRecord>(&format!(
+            "SELECT {} FROM interactions \
+             WHERE type = 'question' AND requires_response = TRUE AND responded_at IS NULL \
+             ORDER BY timestamp, id",
+            INTERACTION_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(questions)
+    }
+
+    /// Mark the agent's pending questions answered and record the response.
+    /// Returns the ids of the questions that were answered.
+    pub async fn answer_questions(
+        &self,
+        agent_id: &str,
+        session_id: &str,
+        response: &str,
+    ) -> Result<Vec<i64>> {
+        let mut tx = self.pool.begin().await?;
+
+        let answered: Vec<i64> = sqlx::query_scalar(
+            r#"
+            UPDATE interactions SET responded_at = CURRENT_TIMESTAMP
+            WHERE agent_id = ? AND type = 'question' AND requires_response = TRUE
+                AND responded_at IS NULL
+            RETURNING id
+            "#,
+        )
+        .bind(agent_id)
+        .fetch_all(&mut *tx)
+        .await
+        .with_context(|| format!("Failed to answer questions of agent {}", agent_id))?;
+
+        let metadata = serde_json::json!({ "questions": answered }).to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO interactions (agent_id, session_id, type, content, metadata)
+            VALUES (?, ?, 'response', ?, ?)
+            "#,
+        )
+        .bind(agent_id)
+        .bind(session_id)
+        .bind(response)
+        .bind(metadata)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(answered)
+    }
+
+    /// Stop expecting answers from an agent that exited
+    pub async fn expire_questions(&self, agent_id: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE interactions SET requires_response = FALSE
+            WHERE agent_id = ? AND type = 'question' AND responded_at IS NULL
+            "#,
+        )
+        .bind(agent_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// All interactions of an agent in chronological order
+    pub async fn list_interactions(&self, agent_id: &str) -> Result<Vec<InteractionRecord>> {
+        let interactions = sqlx::query_as::<_, InteractionRecord>(&format!(
+            "SELECT {} FROM interactions WHERE agent_id = ? ORDER BY timestamp, id",
+            INTERACTION_COLUMNS
+        ))
+        .bind(agent_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(interactions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_question_answer_lifecycle() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("prompt", r#"{"mock":2}"#, "main", "abc123")
+            .await
+            .expect("Should create session");
+        let first = db
+            .create_agent(&session.id, "mock", 1, &BTreeMap::new())
+            .await
+            .expect("Should create agent");
+        let second = db
+            .create_agent(&session.id, "mock", 2, &BTreeMap::new())
+            .await
+            .expect("Should create agent");
+
+        let question = db
+            .record_interaction(
+                &first.id,
+                &session.id,
+                InteractionType::Question,
+                "Use SQLite?",
+                None,
+            )
+            .await
+            .expect("Should record question");
+        db.record_interaction(
+            &second.id,
+            &session.id,
+            InteractionType::Question,
+            "Which port?",
+            None,
+        )
+        .await
+        .expect("Should record question");
+        assert_eq!(db.pending_questions().await.unwrap().len(), 2);
+        assert_eq!(db.get_stats().await.unwrap().pending_questions_count, 2);
+
+        let answered = db
+            .answer_questions(&first.id, &session.id, "yes")
+            .await
+            .expect("Should answer");
+        assert_eq!(answered, vec![question]);
+
+        let history = db.list_interactions(&first.id).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].responded_at.is_some());
+        assert_eq!(history[1].interaction_type, InteractionType::Response);
+        assert_eq!(history[1].content, "yes");
+
+        db.expire_questions(&second.id)
+            .await
+            .expect("Should expire");
+        assert!(db.pending_questions().await.unwrap().is_empty());
+
+        db.record_delivery(
+            &second.id,
+            &session.id,
+            InteractionType::Broadcast,
+            "stop",
+            DeliveryStatus::Failed,
+            Some(r#"{"urgent":true}"#),
+        )
+        .await
+        .expect("Should record delivery");
+        let broadcast = db
+            .list_interactions(&second.id)
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(broadcast.interaction_type, InteractionType::Broadcast);
+        assert_eq!(broadcast.delivery_status, Some(DeliveryStatus::Failed));
+    }
+}
@@ -1,15 +1,38 @@
 use sqlx::sqlite::SqlitePool;
+use sqlx::Connection;
 use anyhow::{Context, Result};
-use chrono::{Utc, Duration};
-use std::path::PathBuf;
+use chrono::{DateTime, Utc, Duration};
+use std::path::Path;
+
+mod agents;
+mod exec;
+mod file_changes;
+mod harvest;
+mod interactions;
+mod models;
+mod ports;
+mod sessions;
+
+pub use models::{
+    AgentRecord, AgentStatus, ChangeType, DeliveryStatus, ExecProcessRecord, FileChangeRecord,
+    HarvestResultRecord, InteractionRecord, InteractionType, PortAllocation, SessionRecord,
+    SessionStatus,
+};
+
+/// Format a timestamp the way SQLite's `CURRENT_TIMESTAMP` does, so bound
+/// values sort correctly next to column defaults
+fn sql_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
+}
 
 /// Database connection and management
+#[derive(Clone)]
 pub struct Database {
     pool: SqlitePool,
 }
 
 /// Current database schema version
-const SCHEMA_VERSION: i32 = 1;
+const SCHEMA_VERSION: i32 = 12;
 
 /// Schema migration definition
 struct Migration {
@@ -100,11 +123,208 @@ const MIGRATIONS: &[Migration] = &[
             CREATE INDEX idx_sessions_started_at ON sessions(started_at);
         "#,
     },
+    Migration {
+        version: 2,
+        description: "Track base commit of sessions and branch of agents",
+        sql: r#"
+            ALTER TABLE sessions ADD COLUMN base_branch TEXT;
+            ALTER TABLE sessions ADD COLUMN base_commit TEXT;
+            ALTER TABLE agents ADD COLUMN branch_name TEXT;
+        "#,
+    },
+    Migration {
+        version: 3,
+        description: "Per-instance agent options",
+        sql: r#"
+            ALTER TABLE agents ADD COLUMN options TEXT; -- JSON: {"model": "opus"}
+        "#,
+    },
+    Migration {
+        version: 4,
+        description: "Validate agent_type against the provider registry instead of a CHECK",
+        sql: r#"
+            CREATE TABLE agents_new (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                agent_type TEXT NOT NULL, -- Provider name, see agents::ProviderRegistry
+                instance_number INTEGER NOT NULL,
+                worktree_path TEXT,
+                status TEXT NOT NULL CHECK (status IN ('initializing', 'running', 'waiting', 'completed', 'failed', 'paused')),
+                progress INTEGER DEFAULT 0 CHECK (progress >= 0 AND progress <= 100),
+                started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                last_activity DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                process_id INTEGER,
+                branch_name TEXT,
+                options TEXT,
+                FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            );
+
+            INSERT INTO agents_new (id, session_id, agent_type, instance_number, worktree_path, status,
+                progress, started_at, last_activity, process_id, branch_name, options)
+            SELECT id, session_id, agent_type, instance_number, worktree_path, status,
+                progress, started_at, last_activity, process_id, branch_name, options
+            FROM agents;
+
+            DROP TABLE agents;
+            ALTER TABLE agents_new RENAME TO agents;
+
+            CREATE INDEX idx_agents_session_id ON agents(session_id);
+            CREATE INDEX idx_agents_status ON agents(status);
+        "#,
+    },
+    Migration {
+        version: 5,
+        description: "Add dismissed agent status",
+        sql: r#"
+            CREATE TABLE agents_new (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                agent_type TEXT NOT NULL, -- Provider name, see agents::ProviderRegistry
+                instance_number INTEGER NOT NULL,
+                worktree_path TEXT,
+                status TEXT NOT NULL CHECK (status IN ('initializing', 'running', 'waiting', 'completed', 'failed', 'paused', 'dismissed')),
+                progress INTEGER DEFAULT 0 CHECK (progress >= 0 AND progress <= 100),
+                started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                last_activity DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                process_id INTEGER,
+                branch_name TEXT,
+                options TEXT,
+                FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            );
+
+            INSERT INTO agents_new (id, session_id, agent_type, instance_number, worktree_path, status,
+                progress, started_at, last_activity, process_id, branch_name, options)
+            SELECT id, session_id, agent_type, instance_number, worktree_path, status,
+                progress, started_at, last_activity, process_id, branch_name, options
+            FROM agents;
+
+            DROP TABLE agents;
+            ALTER TABLE agents_new RENAME TO agents;
+
+            CREATE INDEX idx_agents_session_id ON agents(session_id);
+            CREATE INDEX idx_agents_status ON agents(status);
+        "#,
+    },
+    Migration {
+        version: 6,
+        description: "Track delivery of briefs and broadcasts",
+        sql: r#"
+            CREATE TABLE interactions_new (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                agent_id TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                type TEXT NOT NULL CHECK (type IN ('question', 'response', 'status', 'log', 'error', 'checkpoint', 'brief', 'broadcast')),
+                content TEXT NOT NULL,
+                metadata TEXT, -- JSON for additional structured data
+                requires_response BOOLEAN DEFAULT FALSE,
+                responded_at DATETIME,
+                timestamp DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                delivery_status TEXT CHECK (delivery_status IN ('delivered', 'failed')),
+                FOREIGN KEY(agent_id) REFERENCES agents(id) ON DELETE CASCADE,
+                FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            );
+
+            INSERT INTO interactions_new (id, agent_id, session_id, type, content, metadata,
+                requires_response, responded_at, timestamp)
+            SELECT id, agent_id, session_id, type, content, metadata,
+                requires_response, responded_at, timestamp
+            FROM interactions;
+
+            DROP TABLE interactions;
+            ALTER TABLE interactions_new RENAME TO interactions;
+
+            CREATE INDEX idx_interactions_agent_id ON interactions(agent_id);
+            CREATE INDEX idx_interactions_session_id ON interactions(session_id);
+            CREATE INDEX idx_interactions_type ON interactions(type);
+            CREATE INDEX idx_interactions_requires_response ON interactions(requires_response);
+        "#,
+    },
+    Migration {
+        version: 7,
+        description: "Record verification results of harvest runs",
+        sql: r#"
+            CREATE TABLE harvest_results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                harvest_id TEXT NOT NULL, -- Groups the steps of one harvest run
+                session_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                step TEXT NOT NULL,
+                command TEXT NOT NULL,
+                exit_code INTEGER, -- NULL when killed by a signal or timeout
+                duration_ms INTEGER NOT NULL,
+                failures INTEGER NOT NULL DEFAULT 0,
+                timed_out BOOLEAN NOT NULL DEFAULT FALSE,
+                output TEXT, -- Tail of the combined stdout and stderr
+                harvested_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(agent_id) REFERENCES agents(id) ON DELETE CASCADE,
+                FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            );
+
+            CREATE INDEX idx_harvest_results_session_id ON harvest_results(session_id);
+            CREATE INDEX idx_harvest_results_agent_id ON harvest_results(agent_id);
+        "#,
+    },
+    Migration {
+        version: 8,
+        description: "Track commands detached into agent worktrees",
+        sql: r#"
+            CREATE TABLE exec_processes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                session_id TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                command TEXT NOT NULL,
+                pid INTEGER NOT NULL, -- Leader of the command's process group
+                log_path TEXT NOT NULL,
+                started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                stopped_at DATETIME, -- NULL while the process is tracked as running
+                FOREIGN KEY(agent_id) REFERENCES agents(id) ON DELETE CASCADE,
+                FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            );
+
+            CREATE INDEX idx_exec_processes_agent_id ON exec_processes(agent_id);
+        "#,
+    },
+    Migration {
+        version: 9,
+        description: "Reserve a block of ports for each agent",
+        sql: r#"
+            CREATE TABLE port_allocations (
+                agent_id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                port_start INTEGER NOT NULL UNIQUE,
+                port_count INTEGER NOT NULL,
+                allocated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(agent_id) REFERENCES agents(id) ON DELETE CASCADE,
+                FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            );
+        "#,
+    },
+    Migration {
+        version: 10,
+        description: "Remember the agent picked as a session's winner",
+        sql: r#"
+            ALTER TABLE sessions ADD COLUMN winner_agent_id TEXT; -- Adopted by 'agentcrew adopt' without an agent
+        "#,
+    },
+    Migration {
+        version: 11,
+        description: "Record when detached commands started to detect reused pids",
+        sql: r#"
+            ALTER TABLE exec_processes ADD COLUMN start_time INTEGER; -- Leader's start time in clock ticks since boot
+        "#,
+    },
+    Migration {
+        version: 12,
+        description: "Record the commit each harvest step ran against",
+        sql: r#"
+            ALTER TABLE harvest_results ADD COLUMN head_commit TEXT; -- Worktree HEAD, which is what adopt merges
+        "#,
+    },
 ];
 
 impl Database {
     /// Create a new database connection
-    pub async fn new(database_path: &PathBuf) -> Result<Self> {
+    pub async fn new(database_path: &Path) -> Result<Self> {
         let database_url = format!("sqlite://{}?mode=rwc", database_path.display());
         
         let pool = SqlitePool::connect(&database_url)
@@ -126,26 +346,68 @@ impl Database {
 
     /// Run database migrations
     async fn migrate(&self) -> Result<()> {
+        self.migrate_to(SCHEMA_VERSION).await
+    }
+
+    /// Run database migrations up to and including `target_version`
+    async fn migrate_to(&self, target_version: i32) -> Result<()> {
         let current_version = self.get_schema_version().await?;
         
-        if current_version >= SCHEMA_VERSION {
+        if current_version >= target_version {
             return Ok(());
         }
 
-        println!("🔄 Migrating database from version {} to {}", current_version, SCHEMA_VERSION);
+        println!("🔄 Migrating database from version {} to {}", current_version, target_version);
+
+        // Table rebuilds drop tables that others reference; keep SQLite from
+        // cascading those drops into dependent rows while migrating
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+        let applied = Self::apply_migrations(&mut conn, MIGRATIONS, current_version, target_version).await;
+        // Restore the pragma even when a migration failed, since the
+        // connection goes back to the pool
+        let restored = sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await;
+        applied?;
+        restored?;
+
+        Ok(())
+    }
 
-        // Run migrations in order
-        for migration in MIGRATIONS {
-            if migration.version > current_version {
+    /// Apply `migrations` newer than `current_version` up to `target_version`,
+    /// each in its own transaction that is only committed when it left no
+    /// dangling foreign keys behind
+    async fn apply_migrations(
+        conn: &mut sqlx::SqliteConnection,
+        migrations: &[Migration],
+        current_version: i32,
+        target_version: i32,
+    ) -> Result<()> {
+        for migration in migrations {
+            if migration.version > current_version && migration.version <= target_version {
                 println!("  📝 Applying migration {}: {}", migration.version, migration.description);
                 
-                let mut tx = self.pool.begin().await?;
+                let mut tx = Connection::begin(&mut *conn).await?;
                 
                 // Execute the migration SQL
                 sqlx::query(migration.sql)
                     .execute(&mut *tx)
                     .await
                     .with_context(|| format!("Failed to apply migration {}", migration.version))?;
+
+                let violations: Vec<(String, i64, String)> =
+                    sqlx::query_as("SELECT \"table\", rowid, parent FROM pragma_foreign_key_check")
+                        .fetch_all(&mut *tx)
+                        .await?;
+                if let Some((table, rowid, parent)) = violations.first() {
+                    anyhow::bail!(
+                        "Migration {} left {} row(s) with dangling foreign keys (first: {} row {} references {})",
+                        migration.version,
+                        violations.len(),
+                        table,
+                        rowid,
+                        parent
+                    );
+                }
                 
                 // Update schema version
                 sqlx::query("INSERT OR REPLACE INTO schema_version (version) VALUES (?)")
@@ -158,7 +420,6 @@ impl Database {
                 println!("  ✅ Migration {} applied successfully", migration.version);
             }
         }
-
         Ok(())
     }
 
@@ -193,15 +454,28 @@ impl Database {
 
         let mut tx = self.pool.begin().await?;
 
-        // Delete old completed/failed sessions and cascade to related tables
+        // Delete old finished sessions and cascade to related tables; an
+        // active session whose agents have all stopped counts as finished
         let deleted_sessions = sqlx::query(
             r#"
             DELETE FROM sessions 
-            WHERE status IN ('completed', 'failed') 
+            WHERE (
+                status IN ('completed', 'failed')
+                OR (
+                    status = 'active'
+                    AND EXISTS (SELECT 1 FROM agents WHERE agents.session_id = sessions.id)
+                    AND NOT EXISTS (
+                        SELECT 1 FROM agents
+                        WHERE agents.session_id = sessions.id
+                        AND agents.status NOT IN ('completed', 'failed', 'dismissed')
+                    )
+                )
+            )
             AND started_at < ?
+            AND id NOT IN (SELECT session_id FROM agents WHERE worktree_path IS NOT NULL)
             "#
         )
-        .bind(cutoff_date)
+        .bind(sql_timestamp(cutoff_date))
         .execute(&mut *tx)
         .await?
         .rows_affected();
@@ -210,7 +484,7 @@ impl Database {
         let deleted_interactions = sqlx::query(
             "DELETE FROM interactions WHERE timestamp < ? AND session_id NOT IN (SELECT id FROM sessions)"
         )
-        .bind(cutoff_date)
+        .bind(sql_timestamp(cutoff_date))
         .execute(&mut *tx)
         .await?
         .rows_affected();
@@ -273,6 +547,46 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    #[tokio::test]
+    async fn test_failed_migration_restores_foreign_keys() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db")).await.expect("Should create database");
+        let version = db.get_schema_version().await.unwrap();
+        let broken = [
+            Migration {
+                version: version + 1,
+                description: "Orphaned agent",
+                sql: "INSERT INTO agents (id, session_id, agent_type, instance_number, status) VALUES ('a', 'missing', 'mock', 1, 'running')",
+            },
+            Migration {
+                version: version + 2,
+                description: "Syntax error",
+                sql: "ALTER TABLE",
+            },
+        ];
+
+        let mut conn = db.pool.acquire().await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await.unwrap();
+        let error = Database::apply_migrations(&mut conn, &broken, version, version + 1)
+            .await
+            .unwrap_err();
+        assert!(format!("{:#}", error).contains("dangling foreign keys"), "{:#}", error);
+        let error = Database::apply_migrations(&mut conn, &broken[1..], version, version + 2)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Failed to apply migration"));
+        sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await.unwrap();
+        drop(conn);
+
+        // Neither migration was recorded and the orphan was rolled back
+        assert_eq!(db.get_schema_version().await.unwrap(), version);
+        let agents: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM agents").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(agents, 0);
+        db.migrate_to(version).await.expect("Should be a no-op");
+        let enabled: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(enabled, 1);
+    }
+
     #[tokio::test]
     async fn test_database_creation_and_migration() {
         let temp_dir = TempDir::new().expect("Should create temp dir");
@@ -295,6 +609,41 @@ mod tests {
         assert!(table_count >= 5); // At least our main tables + schema_version
     }
 
+    #[tokio::test]
+    async fn test_agents_rebuild_keeps_dependent_rows() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db_path = temp_dir.path().join("test.db");
+        let pool = SqlitePool::connect(&format!("sqlite://{}?mode=rwc", db_path.display()))
+            .await
+            .expect("Should connect");
+        let db = Database { pool };
+        db.migrate_to(3).await.expect("Should migrate to v3");
+
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, prompt, status, agents_requested) VALUES ('s1', 'p', 'active', '{}');
+            INSERT INTO agents (id, session_id, agent_type, instance_number, status) VALUES ('a1', 's1', 'claude', 1, 'running');
+            INSERT INTO interactions (agent_id, session_id, type, content) VALUES ('a1', 's1', 'log', 'hello');
+            "#,
+        )
+        .execute(db.pool())
+        .await
+        .expect("Should insert v3 rows");
+
+        db.migrate().await.expect("Should migrate to latest");
+
+        let interactions = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM interactions")
+            .fetch_one(db.pool())
+            .await
+            .expect("Should count interactions");
+        assert_eq!(interactions, 1);
+
+        sqlx::query("INSERT INTO agents (id, session_id, agent_type, instance_number, status) VALUES ('a2', 's1', 'mock', 1, 'running')")
+            .execute(db.pool())
+            .await
+            .expect("Should accept any registered provider");
+    }
+
     #[tokio::test]
     async fn test_database_stats() {
         let temp_dir = TempDir::new().expect("Should create temp dir");
@@ -318,5 +667,64 @@ mod tests {
         
         // Should not error even with no data
         db.cleanup_old_sessions(30).await.expect("Should cleanup without error");
+
+        let session = db.create_session("old", "{}", "main", "abc123").await.unwrap();
+        let agent = db
+            .create_agent(&session.id, "claude", 1, &std::collections::BTreeMap::new())
+            .await
+            .unwrap();
+        db.set_agent_worktree(&agent.id, std::path::Path::new("/tmp/wt"), "agentcrew/x/claude-1")
+            .await
+            .unwrap();
+        sqlx::query("UPDATE sessions SET status = 'completed', started_at = ? WHERE id = ?")
+            .bind(Utc::now() - Duration::days(40))
+            .bind(&session.id)
+            .execute(db.pool())
+            .await
+            .unwrap();
+
+        // Sessions whose worktrees are still on disk are kept
+        db.cleanup_old_sessions(30).await.unwrap();
+        assert!(db.get_session(&session.id).await.is_ok());
+
+        db.clear_agent_worktree(&agent.id, true).await.unwrap();
+        db.cleanup_old_sessions(30).await.unwrap();
+        assert!(db.get_session(&session.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_prunes_active_sessions_whose_agents_stopped() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+
+        let mut sessions = Vec::new();
+        for agent_status in ["dismissed", "running"] {
+            let session = db.create_session("old", "{}", "main", "abc123").await.unwrap();
+            let agent = db
+                .create_agent(&session.id, "claude", 1, &std::collections::BTreeMap::new())
+                .await
+                .unwrap();
+            sqlx::query("UPDATE agents SET status = ? WHERE id = ?")
+                .bind(agent_status)
+                .bind(&agent.id)
+                .execute(db.pool())
+                .await
+                .unwrap();
+            sqlx::query("UPDATE sessions SET started_at = ? WHERE id = ?")
+                .bind(Utc::now() - Duration::days(40))
+                .bind(&session.id)
+                .execute(db.pool())
+                .await
+                .unwrap();
+            sessions.push(session);
+        }
+
+        db.cleanup_old_sessions(30).await.unwrap();
+        // Never adopted, so still 'active', but every agent has stopped
+        assert!(db.get_session(&sessions[0].id).await.is_err());
+        // An agent is still at work
+        assert!(db.get_session(&sessions[1].id).await.is_ok());
     }
 }
\ No newline at end of file
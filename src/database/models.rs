@@ -0,0 +1,284 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Lifecycle status of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    Active,
+    Completed,
+    Failed,
+    Paused,
+}
+
+impl SessionStatus {
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Paused => "paused",
+        }
+    }
+}
+
+impl std::fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Lifecycle status of a single agent instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AgentStatus {
+    Initializing,
+    Running,
+    Waiting,
+    Completed,
+    Failed,
+    Paused,
+    Dismissed,
+}
+
+impl AgentStatus {
+    /// Database representation of the status
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Initializing => "initializing",
+            Self::Running => "running",
+            Self::Waiting => "waiting",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Paused => "paused",
+            Self::Dismissed => "dismissed",
+        }
+    }
+
+    /// Whether the agent has stopped for good
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Dismissed)
+    }
+}
+
+impl std::fmt::Display for AgentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Kind of an `interactions` row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum InteractionType {
+    Question,
+    Response,
+    Status,
+    Log,
+    Error,
+    Checkpoint,
+    Brief,
+    Broadcast,
+}
+
+/// Whether a brief or broadcast reached its recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Delivered,
+    Failed,
+}
+
+/// How a file changed in a checkpoint commit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    Created,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+/// A row of the `sessions` table
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SessionRecord {
+    pub id: String,
+    pub name: Option<String>,
+    pub prompt: String,
+    pub status: SessionStatus,
+    /// JSON object mapping provider to requested instance count
+    pub agents_requested: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_by: Option<String>,
+    /// Branch that was checked out when the session was deployed
+    pub base_branch: Option<String>,
+    /// Commit every agent branch was forked from
+    pub base_commit: Option<String>,
+    /// Agent marked as the one whose work to adopt
+    #[serde(default)]
+    pub winner_agent_id: Option<String>,
+}
+
+impl SessionRecord {
+    /// Short identifier used in branch names and directory layouts
+    pub fn slug(&self) -> &str {
+        session_slug(&self.id)
+    }
+}
+
+/// A row of the `agents` table
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AgentRecord {
+    pub id: String,
+    pub session_id: String,
+    pub agent_type: String,
+    pub instance_number: i64,
+    pub worktree_path: Option<String>,
+    pub branch_name: Option<String>,
+    pub status: AgentStatus,
+    pub progress: i64,
+    pub started_at: DateTime<Utc>,
+    pub last_activity: DateTime<Utc>,
+    pub process_id: Option<i64>,
+    /// JSON object of per-instance options from the agent spec
+    pub options: Option<String>,
+}
+
+impl AgentRecord {
+    /// Human friendly name such as `claude-1`
+    pub fn name(&self) -> String {
+        format!("{}-{}", self.agent_type, self.instance_number)
+    }
+
+    /// Slug of the session this agent belongs to
+    pub fn session_slug(&self) -> &str {
+        session_slug(&self.session_id)
+    }
+
+    /// Per-instance options such as `model=opus`
+    pub fn options(&self) -> BTreeMap<String, String> {
+        self.options
+            .as_deref()
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// A row of the `interactions` table
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InteractionRecord {
+    pub id: i64,
+    pub agent_id: String,
+    pub session_id: String,
+    #[sqlx(rename = "type")]
+    pub interaction_type: InteractionType,
+    pub content: String,
+    /// JSON for additional structured data
+    pub metadata: Option<String>,
+    pub requires_response: bool,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub timestamp: DateTime<Utc>,
+    /// Set for briefs and broadcasts
+    pub delivery_status: Option<DeliveryStatus>,
+}
+
+/// A row of the `file_changes` table: one path touched by a checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct FileChangeRecord {
+    pub id: i64,
+    pub agent_id: String,
+    pub session_id: String,
+    pub file_path: String,
+    pub change_type: ChangeType,
+    pub lines_added: i64,
+    pub lines_removed: i64,
+    pub commit_hash: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A row of the `harvest_results` table: one verification step of one agent
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct HarvestResultRecord {
+    pub id: i64,
+    /// Groups the steps of one harvest run
+    pub harvest_id: String,
+    pub session_id: String,
+    pub agent_id: String,
+    pub step: String,
+    pub command: String,
+    /// `None` when the step was killed by a signal or timed out
+    pub exit_code: Option<i64>,
+    pub duration_ms: i64,
+    pub failures: i64,
+    pub timed_out: bool,
+    /// Tail of the combined stdout and stderr
+    pub output: Option<String>,
+    /// Commit the step ran against; the worktree had no other changes
+    pub head_commit: Option<String>,
+    pub harvested_at: DateTime<Utc>,
+}
+
+impl HarvestResultRecord {
+    /// Whether the step exited successfully
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0) && !self.timed_out
+    }
+}
+
+/// A row of the `exec_processes` table: a command left running in a worktree
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExecProcessRecord {
+    pub id: i64,
+    pub session_id: String,
+    pub agent_id: String,
+    pub command: String,
+    /// Leader of the command's process group
+    pub pid: i64,
+    /// Leader's start time in clock ticks since boot, to tell a reused pid apart
+    pub start_time: Option<i64>,
+    pub log_path: String,
+    pub started_at: DateTime<Utc>,
+    pub stopped_at: Option<DateTime<Utc>>,
+}
+
+/// A row of the `port_allocations` table: the ports reserved for one agent
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PortAllocation {
+    pub agent_id: String,
+    pub session_id: String,
+    pub port_start: i64,
+    pub port_count: i64,
+    pub allocated_at: DateTime<Utc>,
+}
+
+impl PortAllocation {
+    /// Last port of the block
+    pub fn port_end(&self) -> i64 {
+        self.port_start + self.port_count - 1
+    }
+
+    /// Environment variables telling a process which ports it may use
+    pub fn env(&self) -> Vec<(String, String)> {
+        vec![
+            ("PORT".to_string(), self.port_start.to_string()),
+            ("AGENTCREW_PORT".to_string(), self.port_start.to_string()),
+            (
+                "AGENTCREW_PORT_RANGE".to_string(),
+                format!("{}-{}", self.port_start, self.port_end()),
+            ),
+        ]
+    }
+}
+
+/// Short form of a session id (first 8 characters of the UUID)
+pub fn session_slug(session_id: &str) -> &str {
+    session_id.get(..8).unwrap_or(session_id)
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+
+use super::{Database, PortAllocation};
+
+const PORT_COLUMNS: &str = "agent_id, session_id, port_start, port_count, allocated_at";
+
+impl Database {
+    /// Ports reserved for an agent, reserving the lowest free block of
+    /// `block_size` ports at or above `base` on first use
+    pub async fn allocate_ports(
+        &self,
+        session_id: &str,
+        agent_id: &str,
+        base: u16,
+        block_size: u16,
+    ) -> Result<PortAllocation> {
+        if let Some(allocation) = self.agent_ports(agent_id).await? {
+            return Ok(allocation);
+        }
+        if block_size == 0 {
+            anyhow::bail!("ports.block_size must be at least 1");
+        }
+
+        let taken: Vec<(i64, i64)> =
+            sqlx::query_as("SELECT port_start, port_count FROM port_allocations")
+                .fetch_all(&self.pool)
+                .await?;
+        let size = i64::from(block_size);
+        let mut start = i64::from(base);
+        while taken
+            .iter()
+            .any(|(taken_start, count)| start < taken_start + count && *taken_start < start + size)
+        {
+            start += size;
+        }
+        if start + size - 1 > i64::from(u16::MAX) {
+            anyhow::bail!("No free block of {} ports left above {}", block_size, base);
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO port_allocations (agent_id, session_id, port_start, port_count)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(agent_id)
+        .bind(session_id)
+        .bind(start)
+        .bind(size)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to reserve ports {}-{}", start, start + size - 1))?;
+
+        self.agent_ports(agent_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Port allocation of {} disappeared", agent_id))
+    }
+
+    /// Ports reserved for an agent, if any
+    pub async fn agent_ports(&self, agent_id: &str) -> Result<Option<PortAllocation>> {
+        let allocation = sqlx::query_as::<_, PortAllocation>(&format!(
+            "SELECT {} FROM port_allocations WHERE agent_id = ?",
+            PORT_COLUMNS
+        ))
+        .bind(agent_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(allocation)
+    }
+
+    /// Return an agent's ports to the pool
+    pub async fn release_ports(&self, agent_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM port_allocations WHERE agent_id = ?")
+            .bind(agent_id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to release ports of {}", agent_id))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_allocate_and_release_ports() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("serve", r#"{"mock":3}"#, "main", "abc123")
+            .await
+            .unwrap();
+        let mut agents = Vec::new();
+        for instance in 1..=3 {
+            agents.push(
+                db.create_agent(&session.id, "mock", instance, &BTreeMap::new())
+                    .await
+                    .unwrap(),
+            );
+        }
+
+        let first = db
+            .allocate_ports(&session.id, &agents[0].id, 4000, 10)
+            .await
+            .expect("Should allocate ports");
+        assert_eq!((first.port_start, first.port_end()), (4000, 4009));
+        let second = db
+            .allocate_ports(&session.id, &agents[1].id, 4000, 10)
+            .await
+            .unwrap();
+        assert_eq!(second.port_start, 4010);
+        // Allocating again returns the same block
+        let again = db
+            .allocate_ports(&session.id, &agents[0].id, 4000, 10)
+            .await
+            .unwrap();
+        assert_eq!(again.port_start, 4000);
+        assert!(again
+            .env()
+            .contains(&("AGENTCREW_PORT_RANGE".to_string(), "4000-4009".to_string())));
+
+        // A freed block is handed out again
+        db.release_ports(&agents[0].id).await.unwrap();
+        assert!(db.agent_ports(&agents[0].id).await.unwrap().is_none());
+        let third = db
+            .allocate_ports(&session.id, &agents[2].id, 4000, 10)
+            .await
+            .unwrap();
+        assert_eq!(third.port_start, 4000);
+
+        assert!(db
+            .allocate_ports(&session.id, &agents[0].id, u16::MAX - 5, 10)
+            .await
+            .is_err());
+    }
+}
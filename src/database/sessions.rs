@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+
+use super::{sql_timestamp, Database, SessionRecord, SessionStatus};
+
+const SESSION_COLUMNS: &str = "id, name, prompt, status, agents_requested, started_at, \
+    completed_at, created_by, base_branch, base_commit, winner_agent_id";
+
+impl Database {
+    /// Create a new active session and return the stored row
+    pub async fn create_session(
+        &self,
+        prompt: &str,
+        agents_requested: &str,
+        base_branch: &str,
+        base_commit: &str,
+    ) -> Result<SessionRecord> {
+        let id = uuid::Uuid::new_v4().to_string();
+
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, prompt, status, agents_requested, base_branch, base_commit)
+            VALUES (?, ?, 'active', ?, ?, ?)
+            "#,
+        )
+        .bind(&id)
+        .bind(prompt)
+        .bind(agents_requested)
+        .bind(base_branch)
+        .bind(base_commit)
+        .execute(&self.pool)
+        .await
+        .with_context(|| "Failed to create session")?;
+
+        self.get_session(&id).await
+    }
+
+    /// Fetch a session by id
+    pub async fn get_session(&self, session_id: &str) -> Result<SessionRecord> {
+        sqlx::query_as::<_, SessionRecord>(&format!(
+            "SELECT {} FROM sessions WHERE id = ?",
+            SESSION_COLUMNS
+        ))
+        .bind(session_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Session not found: {}", session_id))
+    }
+
+    /// Find a session by name or id prefix (such as its slug)
+    pub async fn find_session(&self, reference: &str) -> Result<SessionRecord> {
+        let sessions = sqlx::query_as::<_, SessionRecord>(&format!(
+            "SELECT {} FROM sessions WHERE name = ? OR id LIKE ? || '%' ORDER BY name = ? DESC",
+            SESSION_COLUMNS
+        ))
+        .bind(reference)
+        .bind(reference)
+        .bind(reference)
+        .fetch_all(&self.pool)
+        .await?;
+
+        match sessions.as_slice() {
+            [] => anyhow::bail!("No session matches '{}'", reference),
+            [session] => Ok(session.clone()),
+            [first, ..] if first.name.as_deref() == Some(reference) => Ok(first.clone()),
+            _ => anyhow::bail!("'{}' matches {} sessions; use more of the id", reference, sessions.len()),
+        }
+    }
+
+    /// Most recent session, preferring active ones
+    pub async fn latest_session(&self) -> Result<Option<SessionRecord>> {
+        let session = sqlx::query_as::<_, SessionRecord>(&format!(
+            "SELECT {} FROM sessions ORDER BY status = 'active' DESC, started_at DESC, rowid DESC LIMIT 1",
+            SESSION_COLUMNS
+        ))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(session)
+    }
+
+    /// Every session, most recent first
+    pub async fn list_sessions(&self) -> Result<Vec<SessionRecord>> {
+        let sessions = sqlx::query_as::<_, SessionRecord>(&format!(
+            "SELECT {} FROM sessions ORDER BY started_at DESC, rowid DESC",
+            SESSION_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    /// Name a session, e.g. when it is saved
+    pub async fn set_session_name(&self, session_id: &str, name: &str) -> Result<()> {
+        sqlx::query("UPDATE sessions SET name = ? WHERE id = ?")
+            .bind(name)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to name session {}", session_id))?;
+        Ok(())
+    }
+
+    /// Insert a session row as recorded elsewhere, or update the name and
+    /// status of the existing one
+    pub async fn restore_session(&self, session: &SessionRecord) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, name, prompt, status, agents_requested, started_at,
+                completed_at, created_by, base_branch, base_commit, winner_agent_id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name, status = excluded.status,
+                completed_at = excluded.completed_at, winner_agent_id = excluded.winner_agent_id
+            "#,
+        )
+        .bind(&session.id)
+        .bind(&session.name)
+        .bind(&session.prompt)
+        .bind(session.status)
+        .bind(&session.agents_requested)
+        .bind(sql_timestamp(session.started_at))
+        .bind(session.completed_at.map(sql_timestamp))
+        .bind(&session.created_by)
+        .bind(&session.base_branch)
+        .bind(&session.base_commit)
+        .bind(&session.winner_agent_id)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to restore session {}", session.id))?;
+        Ok(())
+    }
+
+    /// Mark the agent whose work the session should adopt, or clear the mark
+    pub async fn set_session_winner(&self, session_id: &str, agent_id: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE sessions SET winner_agent_id = ? WHERE id = ?")
+            .bind(agent_id)
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to mark the winner of session {}", session_id))?;
+        Ok(())
+    }
+
+    /// Change a session's status, stamping `completed_at` once it is finished
+    pub async fn update_session_status(&self, session_id: &str, status: SessionStatus) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE sessions SET status = ?,
+                completed_at = CASE WHEN ? THEN CURRENT_TIMESTAMP ELSE NULL END
+            WHERE id = ?
+            "#,
+        )
+        .bind(status)
+        .bind(matches!(status, SessionStatus::Completed | SessionStatus::Failed))
+        .bind(session_id)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to update status of session {}", session_id))?;
+        Ok(())
+    }
+
+    /// Delete a session together with its agents and history
+    pub async fn delete_session(&self, session_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE id = ?")
+            .bind(session_id)
+            .execute(&self.pool)
+            .await
+            .with_context(|| format!("Failed to delete session {}", session_id))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_create_and_delete_session() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+
+        let session = db
+            .create_session("do things", r#"{"claude":1}"#, "main", "abc123")
+            .await
+            .expect("Should create session");
+        assert_eq!(session.status, SessionStatus::Active);
+        assert_eq!(session.base_branch.as_deref(), Some("main"));
+        assert_eq!(session.slug().len(), 8);
+
+        let latest = db.latest_session().await.expect("Should query latest");
+        assert_eq!(latest.map(|s| s.id), Some(session.id.clone()));
+
+        db.update_session_status(&session.id, SessionStatus::Completed)
+            .await
+            .expect("Should complete session");
+        let completed = db.get_session(&session.id).await.unwrap();
+        assert_eq!(completed.status, SessionStatus::Completed);
+        assert!(completed.completed_at.is_some());
+
+        db.set_session_name(&session.id, "first").await.unwrap();
+        db.set_session_winner(&session.id, Some("agent-2")).await.unwrap();
+        assert_eq!(db.list_sessions().await.unwrap().len(), 1);
+        let mut saved = db.get_session(&session.id).await.unwrap();
+        assert_eq!(saved.name.as_deref(), Some("first"));
+        assert_eq!(db.find_session("first").await.unwrap().id, session.id);
+        assert_eq!(db.find_session(session.slug()).await.unwrap().id, session.id);
+        assert!(db.find_session("zzzz").await.is_err());
+
+        db.delete_session(&session.id)
+            .await
+            .expect("Should delete session");
+        assert!(db.get_session(&session.id).await.is_err());
+
+        saved.name = Some("again".to_string());
+        saved.status = SessionStatus::Active;
+        saved.completed_at = None;
+        db.restore_session(&saved)
+            .await
+            .expect("Should restore deleted session");
+        db.restore_session(&saved)
+            .await
+            .expect("Should restore existing session");
+        let restored = db.get_session(&session.id).await.unwrap();
+        assert_eq!(restored.status, SessionStatus::Active);
+        assert_eq!(restored.name.as_deref(), Some("again"));
+        assert_eq!(restored.prompt, "do things");
+        assert_eq!(restored.winner_agent_id.as_deref(), Some("agent-2"));
+    }
+
+    #[tokio::test]
+    async fn test_restored_session_sorts_by_start() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let mut older = db
+            .create_session("older", r#"{"claude":1}"#, "main", "abc123")
+            .await
+            .unwrap();
+        let newer = db
+            .create_session("newer", r#"{"claude":1}"#, "main", "abc123")
+            .await
+            .unwrap();
+
+        // A session imported from elsewhere that started a minute earlier
+        db.delete_session(&older.id).await.unwrap();
+        older.started_at = newer.started_at - chrono::Duration::minutes(1);
+        db.restore_session(&older).await.unwrap();
+
+        let latest = db.latest_session().await.unwrap().unwrap();
+        assert_eq!(latest.id, newer.id);
+        assert_eq!(db.get_session(&older.id).await.unwrap().started_at, older.started_at);
+    }
+}
@@ -0,0 +1,242 @@
+use anyhow::{Context, Result};
+use git2::build::CheckoutBuilder;
+use git2::{Commit, Index, MergeOptions, Oid, Repository, Signature, Sort};
+use std::fmt;
+use std::str::FromStr;
+
+/// How an agent's branch is brought into the checked out branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdoptStrategy {
+    /// Merge commit with the agent's tip as second parent
+    Merge,
+    /// All of the agent's changes as one new commit
+    Squash,
+    /// Replay each of the agent's commits on top of HEAD
+    Rebase,
+}
+
+impl FromStr for AdoptStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "merge" => Ok(Self::Merge),
+            "squash" => Ok(Self::Squash),
+            "rebase" => Ok(Self::Rebase),
+            other => anyhow::bail!(
+                "Unknown strategy '{}' (expected merge, squash or rebase)",
+                other
+            ),
+        }
+    }
+}
+
+impl fmt::Display for AdoptStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Merge => "merge",
+            Self::Squash => "squash",
+            Self::Rebase => "rebase",
+        })
+    }
+}
+
+/// Bring `theirs` into the branch checked out in `repo` and return the new
+/// HEAD. Everything is computed in memory first, so on conflicts neither the
+/// branch nor the working directory is touched.
+pub fn adopt(
+    repo: &Repository,
+    theirs: &Commit<'_>,
+    strategy: AdoptStrategy,
+    message: &str,
+) -> Result<Oid> {
+    let head = repo
+        .head()
+        .with_context(|| "Failed to get HEAD reference")?
+        .peel_to_commit()
+        .with_context(|| "HEAD does not point to a commit")?;
+    if head.id() == theirs.id() || repo.graph_descendant_of(head.id(), theirs.id())? {
+        anyhow::bail!("Nothing to adopt: {} is already part of HEAD", theirs.id());
+    }
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now("agentcrew", "agentcrew@local"))?;
+
+    let new_head = match strategy {
+        AdoptStrategy::Merge => {
+            let index = repo.merge_commits(&head, theirs, Some(&MergeOptions::new()))?;
+            let tree = write_tree(repo, index)?;
+            repo.commit(
+                None,
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &[&head, theirs],
+            )?
+        }
+        AdoptStrategy::Squash => {
+            let index = repo.merge_commits(&head, theirs, Some(&MergeOptions::new()))?;
+            let tree = write_tree(repo, index)?;
+            repo.commit(None, &signature, &signature, message, &tree, &[&head])?
+        }
+        AdoptStrategy::Rebase => {
+            let base = repo
+                .merge_base(head.id(), theirs.id())
+                .with_context(|| "Agent branch shares no history with HEAD")?;
+            let mut walk = repo.revwalk()?;
+            walk.push(theirs.id())?;
+            walk.hide(base)?;
+            walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+            let mut tip = head.clone();
+            for oid in walk {
+                let commit = repo.find_commit(oid?)?;
+                if commit.parent_count() > 1 {
+                    anyhow::bail!(
+                        "Cannot rebase merge commit {}; use --strategy merge",
+                        commit.id()
+                    );
+                }
+                let index = repo.cherrypick_commit(&commit, &tip, 0, None)?;
+                let tree = write_tree(repo, index).with_context(|| {
+                    format!("Failed to replay {}", commit.summary().unwrap_or_default())
+                })?;
+                let oid = repo.commit(
+                    None,
+                    &commit.author(),
+                    &signature,
+                    commit.message().unwrap_or_default(),
+                    &tree,
+                    &[&tip],
+                )?;
+                tip = repo.find_commit(oid)?;
+            }
+            tip.id()
+        }
+    };
+
+    // Safe checkout refuses before writing anything if local files would be lost
+    let new_commit = repo.find_commit(new_head)?;
+    repo.checkout_tree(new_commit.as_object(), Some(CheckoutBuilder::new().safe()))
+        .with_context(|| "Failed to update the working directory")?;
+    repo.head()?
+        .set_target(new_head, &format!("agentcrew adopt ({})", strategy))
+        .with_context(|| "Failed to move HEAD")?;
+    Ok(new_head)
+}
+
+/// Write a merged index as a tree, failing with the conflicting paths
+fn write_tree(repo: &Repository, mut index: Index) -> Result<git2::Tree<'_>> {
+    if index.has_conflicts() {
+        let mut paths: Vec<String> = index
+            .conflicts()?
+            .filter_map(|conflict| {
+                let conflict = conflict.ok()?;
+                let entry = conflict.our.or(conflict.their).or(conflict.ancestor)?;
+                Some(String::from_utf8_lossy(&entry.path).into_owned())
+            })
+            .collect();
+        paths.dedup();
+        anyhow::bail!("Conflicts in {}", paths.join(", "));
+    }
+    let oid = index.write_tree_to(repo)?;
+    Ok(repo.find_tree(oid)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::tests::commit_files;
+    use crate::git::GitUtils;
+    use tempfile::TempDir;
+
+    /// Repository with `main` checked out and an `agent` branch forked from it
+    fn setup(agent_files: &[(&str, Option<&str>)]) -> (TempDir, Repository) {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = Repository::init(temp_dir.path()).expect("Should init repo");
+        let base = commit_files(&repo, "main", None, &[("shared.rs", Some("a\nb\nc\n"))]);
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+
+        let first = commit_files(&repo, "agent", Some(base), &[("agent.rs", Some("1\n"))]);
+        commit_files(&repo, "agent", Some(first), agent_files);
+        (temp_dir, repo)
+    }
+
+    /// Commit on `main` and check it out
+    fn advance_main(repo: &Repository, files: &[(&str, Option<&str>)]) {
+        let head = repo.head().unwrap().target().unwrap();
+        commit_files(repo, "main", Some(head), files);
+        repo.checkout_head(Some(CheckoutBuilder::new().force()))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_adopt_strategies() {
+        for strategy in [
+            AdoptStrategy::Merge,
+            AdoptStrategy::Squash,
+            AdoptStrategy::Rebase,
+        ] {
+            let (temp_dir, repo) = setup(&[("shared.rs", Some("a\nb\nC\n"))]);
+            advance_main(&repo, &[("main.rs", Some("m\n"))]);
+            let main_tip = repo.head().unwrap().target().unwrap();
+            let theirs = crate::git::diff::branch_tip(&repo, "agent").unwrap();
+
+            let new_head = adopt(&repo, &theirs, strategy, "Adopt agent").expect("Should adopt");
+            let commit = repo.find_commit(new_head).unwrap();
+            assert_eq!(repo.head().unwrap().target(), Some(new_head));
+            assert_eq!(
+                std::fs::read_to_string(temp_dir.path().join("shared.rs")).unwrap(),
+                "a\nb\nC\n"
+            );
+            assert!(temp_dir.path().join("agent.rs").exists());
+            assert!(temp_dir.path().join("main.rs").exists());
+            assert!(!GitUtils::is_dirty(temp_dir.path()).unwrap());
+
+            match strategy {
+                AdoptStrategy::Merge => assert_eq!(commit.parent_count(), 2),
+                AdoptStrategy::Squash => {
+                    assert_eq!(commit.parent_count(), 1);
+                    assert_eq!(commit.message(), Some("Adopt agent"));
+                }
+                AdoptStrategy::Rebase => {
+                    // Both agent commits replayed on top of main
+                    let grandparent = commit.parent(0).unwrap().parent(0).unwrap();
+                    assert_eq!(grandparent.id(), main_tip);
+                    assert_eq!(commit.message(), Some("change"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_conflict_leaves_repo_untouched() {
+        let (temp_dir, repo) = setup(&[("shared.rs", Some("a\nB\nc\n"))]);
+        advance_main(&repo, &[("shared.rs", Some("a\nX\nc\n"))]);
+        let before = repo.head().unwrap().target().unwrap();
+        let theirs = crate::git::diff::branch_tip(&repo, "agent").unwrap();
+
+        for strategy in [
+            AdoptStrategy::Merge,
+            AdoptStrategy::Squash,
+            AdoptStrategy::Rebase,
+        ] {
+            let error = adopt(&repo, &theirs, strategy, "Adopt agent").unwrap_err();
+            assert!(format!("{:#}", error).contains("shared.rs"));
+            assert_eq!(repo.head().unwrap().target(), Some(before));
+            assert!(!GitUtils::is_dirty(temp_dir.path()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_strategy_parsing() {
+        assert_eq!(
+            "squash".parse::<AdoptStrategy>().unwrap(),
+            AdoptStrategy::Squash
+        );
+        assert!("octopus".parse::<AdoptStrategy>().is_err());
+    }
+}
@@ -0,0 +1,421 @@
+use anyhow::{Context, Result};
+use git2::{
+    Commit, Delta, Diff, DiffFindOptions, DiffFormat, DiffStatsFormat, Oid, Patch, Repository,
+};
+use std::collections::BTreeMap;
+
+/// How a file changed between two commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+impl ChangeKind {
+    /// One-letter code as printed by `git diff --name-status`
+    pub fn letter(&self) -> char {
+        match self {
+            Self::Added => 'A',
+            Self::Modified => 'M',
+            Self::Deleted => 'D',
+            Self::Renamed => 'R',
+        }
+    }
+}
+
+/// One changed file with its line counts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub additions: usize,
+    pub deletions: usize,
+}
+
+/// Files changed by two agents relative to their common base
+#[derive(Debug, Default)]
+pub struct Overlap<'a> {
+    pub both: Vec<(&'a FileChange, &'a FileChange)>,
+    pub only_left: Vec<&'a FileChange>,
+    pub only_right: Vec<&'a FileChange>,
+}
+
+/// Abbreviated commit id
+pub fn short_id(oid: Oid) -> String {
+    oid.to_string()[..8].to_string()
+}
+
+/// Commit at the tip of a local branch
+pub fn branch_tip<'r>(repo: &'r Repository, branch: &str) -> Result<Commit<'r>> {
+    repo.find_branch(branch, git2::BranchType::Local)
+        .with_context(|| format!("Branch not found: {}", branch))?
+        .get()
+        .peel_to_commit()
+        .with_context(|| format!("Branch {} does not point to a commit", branch))
+}
+
+/// Tree-to-tree diff between two commits with rename detection
+pub fn diff_commits<'r>(
+    repo: &'r Repository,
+    from: &Commit<'_>,
+    to: &Commit<'_>,
+) -> Result<Diff<'r>> {
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&from.tree()?), Some(&to.tree()?), None)
+        .with_context(|| format!("Failed to diff {} and {}", from.id(), to.id()))?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(diff)
+}
+
+/// Per-file summary of a diff
+pub fn file_changes(diff: &Diff<'_>) -> Result<Vec<FileChange>> {
+    let mut changes = Vec::new();
+    for index in 0..diff.deltas().len() {
+        if let Some(patch) = Patch::from_diff(diff, index)? {
+            changes.push(file_change(&patch)?);
+        }
+    }
+    Ok(changes)
+}
+
+fn file_change(patch: &Patch<'_>) -> Result<FileChange> {
+    let delta = patch.delta();
+    let kind = match delta.status() {
+        Delta::Added | Delta::Untracked | Delta::Copied => ChangeKind::Added,
+        Delta::Deleted => ChangeKind::Deleted,
+        Delta::Renamed => ChangeKind::Renamed,
+        _ => ChangeKind::Modified,
+    };
+    let file = match kind {
+        ChangeKind::Deleted => delta.old_file(),
+        _ => delta.new_file(),
+    };
+    let path = file
+        .path()
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let (_, additions, deletions) = patch.line_stats()?;
+
+    Ok(FileChange {
+        path,
+        kind,
+        additions,
+        deletions,
+    })
+}
+
+/// A line of one side of a side-by-side diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SideLine {
+    pub number: u32,
+    pub text: String,
+}
+
+/// What a side-by-side row shows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowKind {
+    /// Start of a hunk, with its `@@ ... @@` header
+    Hunk(String),
+    /// Same on both sides
+    Context,
+    /// Replaced: the left line became the right line
+    Changed,
+    /// Only on the left
+    Removed,
+    /// Only on the right
+    Added,
+}
+
+/// One row of a side-by-side diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SideBySideRow {
+    pub kind: RowKind,
+    pub left: Option<SideLine>,
+    pub right: Option<SideLine>,
+}
+
+/// A changed file with its hunks laid out side by side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub change: FileChange,
+    /// Empty for binary files
+    pub rows: Vec<SideBySideRow>,
+}
+
+/// Every changed file of a diff laid out side by side, pairing removed
+/// lines with the added lines that replace them
+pub fn side_by_side(diff: &Diff<'_>) -> Result<Vec<FileDiff>> {
+    let mut files = Vec::new();
+    for index in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(diff, index)? else {
+            continue;
+        };
+        let mut rows = Vec::new();
+        for hunk_index in 0..patch.num_hunks() {
+            let (hunk, lines) = patch.hunk(hunk_index)?;
+            let header = String::from_utf8_lossy(hunk.header())
+                .trim_end()
+                .to_string();
+            rows.push(SideBySideRow {
+                kind: RowKind::Hunk(header),
+                left: None,
+                right: None,
+            });
+
+            let (mut removed, mut added) = (Vec::new(), Vec::new());
+            for line_index in 0..lines {
+                let line = patch.line_in_hunk(hunk_index, line_index)?;
+                let text = String::from_utf8_lossy(line.content())
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string();
+                match line.origin() {
+                    '-' => removed.push(SideLine {
+                        number: line.old_lineno().unwrap_or_default(),
+                        text,
+                    }),
+                    '+' => added.push(SideLine {
+                        number: line.new_lineno().unwrap_or_default(),
+                        text,
+                    }),
+                    ' ' => {
+                        pair_lines(&mut rows, &mut removed, &mut added);
+                        rows.push(SideBySideRow {
+                            kind: RowKind::Context,
+                            left: Some(SideLine {
+                                number: line.old_lineno().unwrap_or_default(),
+                                text: text.clone(),
+                            }),
+                            right: Some(SideLine {
+                                number: line.new_lineno().unwrap_or_default(),
+                                text,
+                            }),
+                        });
+                    }
+                    _ => {}
+                }
+            }
+            pair_lines(&mut rows, &mut removed, &mut added);
+        }
+        files.push(FileDiff {
+            change: file_change(&patch)?,
+            rows,
+        });
+    }
+    Ok(files)
+}
+
+/// Flush a run of removed and added lines as changed rows side by side,
+/// followed by whatever one side has more of
+fn pair_lines(
+    rows: &mut Vec<SideBySideRow>,
+    removed: &mut Vec<SideLine>,
+    added: &mut Vec<SideLine>,
+) {
+    let mut removed = removed.drain(..);
+    let mut added = added.drain(..);
+    loop {
+        let row = match (removed.next(), added.next()) {
+            (None, None) => break,
+            (left, right) => SideBySideRow {
+                kind: match (&left, &right) {
+                    (Some(_), Some(_)) => RowKind::Changed,
+                    (Some(_), None) => RowKind::Removed,
+                    _ => RowKind::Added,
+                },
+                left,
+                right,
+            },
+        };
+        rows.push(row);
+    }
+}
+
+/// Split two agents' changes into files both touched and files only one touched
+pub fn overlap<'a>(left: &'a [FileChange], right: &'a [FileChange]) -> Overlap<'a> {
+    let right_by_path: BTreeMap<&str, &FileChange> = right
+        .iter()
+        .map(|change| (change.path.as_str(), change))
+        .collect();
+
+    let mut result = Overlap::default();
+    for change in left {
+        match right_by_path.get(change.path.as_str()) {
+            Some(other) => result.both.push((change, other)),
+            None => result.only_left.push(change),
+        }
+    }
+    result.only_right = right
+        .iter()
+        .filter(|change| !left.iter().any(|other| other.path == change.path))
+        .collect();
+    result
+}
+
+/// `git diff --stat` style summary of a diff
+pub fn stat_text(diff: &Diff<'_>) -> Result<String> {
+    let stats = diff.stats()?.to_buf(DiffStatsFormat::FULL, 80)?;
+    Ok(String::from_utf8_lossy(&stats).into_owned())
+}
+
+/// Unified patch of a diff
+pub fn patch_text(diff: &Diff<'_>) -> Result<String> {
+    let mut patch = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            patch.push(line.origin());
+        }
+        patch.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })?;
+    Ok(patch)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use git2::{Oid, Signature};
+    use tempfile::TempDir;
+
+    /// Commit `files` (top-level only) on top of `parent`, updating `branch`
+    pub(crate) fn commit_files(
+        repo: &Repository,
+        branch: &str,
+        parent: Option<Oid>,
+        files: &[(&str, Option<&str>)],
+    ) -> Oid {
+        let parent = parent.map(|oid| repo.find_commit(oid).unwrap());
+        let base_tree = parent.as_ref().map(|commit| commit.tree().unwrap());
+        let mut builder = repo.treebuilder(base_tree.as_ref()).unwrap();
+        for (path, content) in files {
+            match content {
+                Some(content) => {
+                    let blob = repo.blob(content.as_bytes()).unwrap();
+                    builder.insert(path, blob, 0o100644).unwrap();
+                }
+                None => {
+                    builder.remove(path).unwrap();
+                }
+            }
+        }
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(
+            Some(&format!("refs/heads/{}", branch)),
+            &sig,
+            &sig,
+            "change",
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_compare_two_branches() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = Repository::init(temp_dir.path()).expect("Should init repo");
+        let base = commit_files(
+            &repo,
+            "main",
+            None,
+            &[("shared.rs", Some("a\nb\nc\n")), ("old.rs", Some("x\n"))],
+        );
+        commit_files(
+            &repo,
+            "left",
+            Some(base),
+            &[
+                ("shared.rs", Some("a\nB\nc\n")),
+                ("left.rs", Some("1\n2\n")),
+            ],
+        );
+        commit_files(
+            &repo,
+            "right",
+            Some(base),
+            &[("shared.rs", Some("a\nb\nc\nd\n")), ("old.rs", None)],
+        );
+
+        let base = repo.find_commit(base).unwrap();
+        let left = branch_tip(&repo, "left").expect("Should find left");
+        let right = branch_tip(&repo, "right").expect("Should find right");
+        assert!(branch_tip(&repo, "missing").is_err());
+
+        let left_changes = file_changes(&diff_commits(&repo, &base, &left).unwrap()).unwrap();
+        let right_changes = file_changes(&diff_commits(&repo, &base, &right).unwrap()).unwrap();
+        assert_eq!(
+            left_changes,
+            vec![
+                FileChange {
+                    path: "left.rs".to_string(),
+                    kind: ChangeKind::Added,
+                    additions: 2,
+                    deletions: 0
+                },
+                FileChange {
+                    path: "shared.rs".to_string(),
+                    kind: ChangeKind::Modified,
+                    additions: 1,
+                    deletions: 1
+                },
+            ]
+        );
+
+        let overlap = overlap(&left_changes, &right_changes);
+        assert_eq!(overlap.both.len(), 1);
+        assert_eq!(overlap.both[0].1.additions, 1);
+        assert_eq!(overlap.only_left[0].path, "left.rs");
+        assert_eq!(overlap.only_right[0].path, "old.rs");
+        assert_eq!(overlap.only_right[0].kind, ChangeKind::Deleted);
+
+        let tips = diff_commits(&repo, &left, &right).unwrap();
+        let patch = patch_text(&tips).unwrap();
+        assert!(patch.contains("-B\n"));
+        assert!(patch.contains("+d\n"));
+        assert!(stat_text(&tips).unwrap().contains("3 files changed"));
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = Repository::init(temp_dir.path()).expect("Should init repo");
+        let left = commit_files(
+            &repo,
+            "left",
+            None,
+            &[("lib.rs", Some("a\nb\nc\nd\ne\n")), ("old.rs", Some("x\n"))],
+        );
+        let right = commit_files(&repo, "right", None, &[("lib.rs", Some("a\nB\nc\ne\nf\n"))]);
+        let (left, right) = (
+            repo.find_commit(left).unwrap(),
+            repo.find_commit(right).unwrap(),
+        );
+
+        let files = side_by_side(&diff_commits(&repo, &left, &right).unwrap()).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].change.path, "lib.rs");
+        let kinds: Vec<&RowKind> = files[0].rows.iter().map(|row| &row.kind).collect();
+        assert!(matches!(kinds[0], RowKind::Hunk(header) if header.starts_with("@@ -1,5 +1,5 @@")));
+        assert_eq!(
+            kinds[1..],
+            [
+                &RowKind::Context,
+                &RowKind::Changed,
+                &RowKind::Context,
+                &RowKind::Removed,
+                &RowKind::Context,
+                &RowKind::Added
+            ]
+        );
+        let changed = &files[0].rows[2];
+        assert_eq!(changed.left.as_ref().unwrap().text, "b");
+        assert_eq!(changed.right.as_ref().unwrap().text, "B");
+        assert_eq!(files[0].rows[6].right.as_ref().unwrap().number, 5);
+
+        assert_eq!(files[1].change.kind, ChangeKind::Deleted);
+        assert_eq!(files[1].rows[1].kind, RowKind::Removed);
+    }
+}
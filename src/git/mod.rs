@@ -2,6 +2,9 @@ use std::path::Path;
 use anyhow::{Context, Result};
 use git2::Repository;
 
+pub mod adopt;
+pub mod diff;
+
 /// Git utilities for agentcrew
 pub struct GitUtils;
 
@@ -48,11 +51,28 @@ impl GitUtils {
         let current_dir = std::env::current_dir()
             .with_context(|| "Failed to get current directory")?;
         
-        let repo = Self::find_git_repository(&current_dir)?;
-        let statuses = repo.statuses(None)
+        Self::is_dirty(&current_dir)
+    }
+
+    /// Check if the working tree containing `path` has uncommitted changes.
+    /// Ignored files and agentcrew's own `.agentcrew/` state do not count.
+    pub fn is_dirty(path: &Path) -> Result<bool> {
+        Ok(Self::changed_files(path)? > 0)
+    }
+
+    /// Count the paths with uncommitted, non-ignored changes in the working
+    /// tree containing `path`, outside `.agentcrew/`
+    pub fn changed_files(path: &Path) -> Result<usize> {
+        let repo = Self::find_git_repository(path)?;
+        let mut options = git2::StatusOptions::new();
+        options.include_untracked(true).recurse_untracked_dirs(true).include_ignored(false);
+        let statuses = repo.statuses(Some(&mut options))
             .with_context(|| "Failed to get repository status")?;
-        
-        Ok(!statuses.is_empty())
+
+        Ok(statuses
+            .iter()
+            .filter(|entry| !entry.path().is_some_and(|path| path.starts_with(".agentcrew/")))
+            .count())
     }
 
     /// Get the current branch name
@@ -76,7 +96,6 @@ impl GitUtils {
 mod tests {
     use super::*;
     use tempfile::TempDir;
-    use std::fs;
 
     #[test]
     fn test_is_git_repository_false() {
@@ -91,6 +110,21 @@ mod tests {
         std::env::set_current_dir(original_dir).expect("Should restore dir");
     }
 
+    #[test]
+    fn test_is_dirty_ignores_agentcrew_state() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        Repository::init(temp_dir.path()).expect("Should init repo");
+        std::fs::create_dir_all(temp_dir.path().join(".agentcrew/sessions")).unwrap();
+        std::fs::write(temp_dir.path().join(".agentcrew/agentcrew.db"), "").unwrap();
+        std::fs::write(temp_dir.path().join(".agentcrew/config.toml"), "").unwrap();
+        std::fs::write(temp_dir.path().join(".agentcrew/sessions/demo.json"), "{}").unwrap();
+        assert!(!GitUtils::is_dirty(temp_dir.path()).unwrap());
+
+
+        std::fs::write(temp_dir.path().join("notes.txt"), "wip").unwrap();
+        assert!(GitUtils::is_dirty(temp_dir.path()).unwrap());
+    }
+
     #[test]
     fn test_repository_name() {
         // This test only runs if we're actually in a git repository
@@ -0,0 +1,7 @@
+//! Verification pipeline run by `agentcrew harvest` and ranking of its results
+
+mod pipeline;
+mod ranking;
+
+pub use pipeline::{run_step, Harvester, StepOutput};
+pub use ranking::{rank, AgentScore};
@@ -0,0 +1,335 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use git2::Repository;
+use nix::sys::signal::Signal;
+use regex::Regex;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+use crate::config::{HarvestConfig, HarvestStep};
+use crate::database::{AgentRecord, Database, HarvestResultRecord};
+use crate::git::GitUtils;
+use crate::process::signals;
+
+/// Lines of step output kept in `harvest_results.output`
+const OUTPUT_TAIL_LINES: usize = 50;
+
+/// Outcome of running one shell command
+#[derive(Debug)]
+pub struct StepOutput {
+    /// `None` when the command was killed by a signal or timed out
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+    pub timed_out: bool,
+    /// Stdout followed by stderr
+    pub output: String,
+}
+
+impl StepOutput {
+    /// Whether the command exited successfully
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0) && !self.timed_out
+    }
+}
+
+/// Run `command` with `sh -c` in `dir`, killing its process group after `timeout`
+pub async fn run_step(command: &str, dir: &Path, timeout: Duration) -> Result<StepOutput> {
+    let started = Instant::now();
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run '{}' in {}", command, dir.display()))?;
+    let pid = child.id();
+
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => {
+            let output = output.with_context(|| format!("Failed to wait for '{}'", command))?;
+            let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            Ok(StepOutput {
+                exit_code: output.status.code(),
+                duration: started.elapsed(),
+                timed_out: false,
+                output: text,
+            })
+        }
+        Err(_) => {
+            // The shell was killed on drop; take down anything it started
+            if let Some(pid) = pid {
+                let _ = signals::signal_group(pid, Signal::SIGKILL);
+            }
+            Ok(StepOutput {
+                exit_code: None,
+                duration: started.elapsed(),
+                timed_out: true,
+                output: String::new(),
+            })
+        }
+    }
+}
+
+/// Runs the configured pipeline in agent worktrees and records the results
+pub struct Harvester {
+    id: String,
+    steps: Vec<(HarvestStep, Option<Regex>)>,
+    timeout: Duration,
+}
+
+impl Harvester {
+    /// Prepare a harvest run from the `[harvest]` configuration
+    pub fn new(config: &HarvestConfig) -> Result<Self> {
+        if config.steps.is_empty() {
+            anyhow::bail!(
+                "No harvest steps configured. Add [[harvest.steps]] with a name and command to .agentcrew/config.toml"
+            );
+        }
+
+        let steps = config
+            .steps
+            .iter()
+            .map(|step| {
+                let pattern = step
+                    .failure_pattern
+                    .as_deref()
+                    .map(|pattern| {
+                        Regex::new(&format!("(?m){}", pattern)).with_context(|| {
+                            format!("Invalid failure_pattern of harvest step {}", step.name)
+                        })
+                    })
+                    .transpose()?;
+                Ok((step.clone(), pattern))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            steps,
+            timeout: config.timeout(),
+        })
+    }
+
+    /// Identifier grouping the results of this run
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Run every step in the agent's worktree and store the results. Only
+    /// committed work is harvested, since that is all `adopt` merges.
+    pub async fn harvest_agent(
+        &self,
+        db: &Database,
+        agent: &AgentRecord,
+    ) -> Result<Vec<HarvestResultRecord>> {
+        let worktree = agent
+            .worktree_path
+            .as_deref()
+            .map(Path::new)
+            .filter(|path| path.exists())
+            .ok_or_else(|| anyhow::anyhow!("{} has no worktree", agent.name()))?;
+        let head = Repository::open(worktree)
+            .and_then(|repo| Ok(repo.head()?.peel_to_commit()?.id()))
+            .with_context(|| format!("{} has no commits", agent.name()))?;
+        if GitUtils::is_dirty(worktree)? {
+            anyhow::bail!(
+                "uncommitted changes would not be adopted; run 'agentcrew checkpoint --agent {}' first",
+                agent.name()
+            );
+        }
+
+        let mut results = Vec::new();
+        for (step, pattern) in &self.steps {
+            let output = run_step(&step.command, worktree, self.timeout).await?;
+            let failures = match pattern {
+                Some(pattern) => pattern.find_iter(&output.output).count(),
+                None => 0,
+            };
+            let failures = match (output.passed(), failures) {
+                (false, 0) => 1,
+                (_, failures) => failures,
+            };
+
+            let mut result = HarvestResultRecord {
+                id: 0,
+                harvest_id: self.id.clone(),
+                session_id: agent.session_id.clone(),
+                agent_id: agent.id.clone(),
+                step: step.name.clone(),
+                command: step.command.clone(),
+                exit_code: output.exit_code.map(i64::from),
+                duration_ms: output.duration.as_millis() as i64,
+                failures: failures as i64,
+                timed_out: output.timed_out,
+                output: Some(tail_lines(&output.output, OUTPUT_TAIL_LINES)),
+                head_commit: Some(head.to_string()),
+                harvested_at: Utc::now(),
+            };
+            result.id = db.record_harvest_result(&result).await?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
+/// Last `count` lines of `text`
+fn tail_lines(text: &str, count: usize) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::ProviderRegistry;
+    use crate::database::AgentStatus;
+    use crate::harvest::rank;
+    use crate::process::Supervisor;
+    use crate::worktree::checkpoint;
+    use git2::Signature;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    /// Commit everything in the worktree of `repo` as its first commit
+    fn commit_all(repo: &Repository) {
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["*"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = Signature::now("test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &sig, &sig, "initial", &tree, &[])
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_step() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+
+        let output = run_step(
+            "echo out; echo err >&2; exit 3",
+            temp_dir.path(),
+            Duration::from_secs(5),
+        )
+        .await
+        .expect("Should run");
+        assert_eq!(output.exit_code, Some(3));
+        assert!(!output.passed());
+        assert_eq!(output.output, "out\nerr\n");
+
+        let output = run_step("sleep 5", temp_dir.path(), Duration::from_millis(100))
+            .await
+            .expect("Should run");
+        assert!(output.timed_out);
+        assert!(output.duration < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_deploy_then_harvest_ranks_agents() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("build it", r#"{"mock":2}"#, "main", "abc123")
+            .await
+            .expect("Should create session");
+
+        // mock-1 leaves a passing tree, mock-2 leaves two failing tests behind
+        let scripts = [
+            r#"write = { path = "PASS", content = "ok" }"#,
+            r#"write = { path = "FAILURES", content = "FAIL one\nFAIL two\n" }"#,
+        ];
+        let options = BTreeMap::from([
+            ("delay_ms".to_string(), "0".to_string()),
+            ("script".to_string(), "script.toml".to_string()),
+        ]);
+        let supervisor = Supervisor::new(db.clone(), ProviderRegistry::builtin());
+        let mut agents = Vec::new();
+        for (index, script) in scripts.iter().enumerate() {
+            let worktree = temp_dir.path().join(format!("mock-{}", index + 1));
+            let repo = Repository::init(&worktree).unwrap();
+            std::fs::write(
+                worktree.join("script.toml"),
+                format!("[[steps]]\n{}\n", script),
+            )
+            .unwrap();
+            commit_all(&repo);
+            let agent = db
+                .create_agent(&session.id, "mock", index as u32 + 1, &options)
+                .await
+                .expect("Should create agent");
+            db.set_agent_worktree(
+                &agent.id,
+                &worktree,
+                &format!("agentcrew/x/mock-{}", index + 1),
+            )
+            .await
+            .expect("Should set worktree");
+            let agent = db.get_agent(&agent.id).await.unwrap();
+            supervisor
+                .launch(&agent, "build it")
+                .await
+                .expect("Should launch");
+            agents.push(agent);
+        }
+        supervisor.wait_all().await;
+
+        let config: HarvestConfig = toml::from_str(
+            r#"
+            [[steps]]
+            name = "build"
+            command = "test -f PASS"
+
+            [[steps]]
+            name = "test"
+            command = "cat FAILURES 2>/dev/null; test ! -e FAILURES"
+            failure_pattern = "^FAIL"
+            "#,
+        )
+        .expect("Should parse");
+        let harvester = Harvester::new(&config).expect("Should build harvester");
+        for agent in &agents {
+            let agent = db.get_agent(&agent.id).await.unwrap();
+            assert_eq!(agent.status, AgentStatus::Completed);
+            // Work the agent did not commit is not what adopt would merge
+            let error = harvester.harvest_agent(&db, &agent).await.unwrap_err();
+            assert!(error.to_string().contains("uncommitted changes"));
+
+            let checkpoint = checkpoint(&db, &agent, "done")
+                .await
+                .expect("Should checkpoint")
+                .expect("Should commit the agent's work");
+            let results = harvester
+                .harvest_agent(&db, &agent)
+                .await
+                .expect("Should harvest");
+            let head = checkpoint.commit.to_string();
+            assert!(results
+                .iter()
+                .all(|result| result.head_commit.as_deref() == Some(head.as_str())));
+        }
+
+        let results = db.latest_harvest(&session.id).await.unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(results
+            .iter()
+            .all(|result| result.harvest_id == harvester.id()));
+
+        let ranking = rank(&agents, &results);
+        assert_eq!(ranking[0].agent.name(), "mock-1");
+        assert_eq!(ranking[0].passed(), 2);
+        assert_eq!(ranking[0].failures(), 0);
+        assert_eq!(ranking[1].agent.name(), "mock-2");
+        assert_eq!(ranking[1].passed(), 0);
+        assert_eq!(ranking[1].failures(), 3);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::database::{AgentRecord, HarvestResultRecord};
+
+/// Harvest results of one agent
+#[derive(Debug)]
+pub struct AgentScore<'a> {
+    pub agent: &'a AgentRecord,
+    /// Step results in the order they ran
+    pub results: Vec<&'a HarvestResultRecord>,
+}
+
+impl AgentScore<'_> {
+    /// Number of steps that passed
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|result| result.passed()).count()
+    }
+
+    /// Failures counted over all steps
+    pub fn failures(&self) -> i64 {
+        self.results.iter().map(|result| result.failures).sum()
+    }
+
+    /// Total time spent in the pipeline
+    pub fn duration_ms(&self) -> i64 {
+        self.results.iter().map(|result| result.duration_ms).sum()
+    }
+}
+
+/// Rank agents by passed steps, then fewest failures, then fastest pipeline.
+/// Agents without results are left out.
+pub fn rank<'a>(
+    agents: &'a [AgentRecord],
+    results: &'a [HarvestResultRecord],
+) -> Vec<AgentScore<'a>> {
+    let mut scores: Vec<AgentScore> = agents
+        .iter()
+        .map(|agent| AgentScore {
+            agent,
+            results: results
+                .iter()
+                .filter(|result| result.agent_id == agent.id)
+                .collect(),
+        })
+        .filter(|score| !score.results.is_empty())
+        .collect();
+
+    scores.sort_by_key(|score| {
+        (
+            std::cmp::Reverse(score.passed()),
+            score.failures(),
+            score.duration_ms(),
+        )
+    });
+    scores
+}
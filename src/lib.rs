@@ -0,0 +1,12 @@
+//! agentcrew - parallel AI agent orchestration in separate git worktrees
+
+pub mod agents;
+pub mod cli;
+pub mod config;
+pub mod database;
+pub mod git;
+pub mod harvest;
+pub mod process;
+pub mod session;
+pub mod ui;
+pub mod worktree;
@@ -1,12 +1,10 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
-mod cli;
-mod config;
-mod database;
-mod git;
-
-use cli::CommandHandler;
+use agentcrew::agents::ProviderRegistry;
+use agentcrew::cli::{CommandHandler, DiffMode};
+use agentcrew::git::adopt::AdoptStrategy;
 
 #[derive(Parser)]
 #[command(name = "agentcrew")]
@@ -23,15 +21,23 @@ enum Commands {
     Init,
     /// Launch agents in separate worktrees
     Deploy {
-        /// Agent specification (e.g., claude:2,gpt:1,jules:1)
+        /// Agent specification (e.g., claude:2,gpt:1,jules:1 or claude:2@model=opus).
+        /// Defaults to `default_agents` from config.toml
         #[arg(long)]
-        agents: String,
+        agents: Option<String>,
         /// Prompt to send to all agents
         #[arg(long)]
         prompt: String,
     },
     /// Display all active agents and progress
-    Status,
+    Status {
+        /// Print the status as JSON for scripts
+        #[arg(long)]
+        json: bool,
+        /// Refresh every N seconds until Ctrl-C
+        #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "2")]
+        watch: Option<u64>,
+    },
     /// Launch interactive terminal UI
     Tui,
     /// Show available agent types and capabilities
@@ -73,24 +79,42 @@ enum Commands {
         /// Response message
         response: String,
     },
+    /// List agent questions waiting for an answer
+    Questions,
     /// Priority message to all agents
     Broadcast {
-        /// Mark as urgent message
+        /// Interrupt (or pause) each agent's current turn while delivering the message
         #[arg(long)]
         urgent: bool,
         /// Message to broadcast
         message: String,
     },
     /// List all agent worktrees and their branches
-    Worktrees,
+    Worktrees {
+        /// Re-create, re-register or prune worktrees out of sync with git
+        #[arg(long)]
+        repair: bool,
+    },
     /// Run commands across worktrees
     Exec {
         /// Run on all agents
-        #[arg(long)]
+        #[arg(long, conflicts_with = "agent")]
         all: bool,
         /// Specific agent to run on
         #[arg(long)]
         agent: Option<String>,
+        /// Maximum number of worktrees to run in at once (defaults to the CPU count)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+        /// Start the command in the background and track its process
+        #[arg(long)]
+        detach: bool,
+        /// List the background commands of the current session
+        #[arg(long, conflicts_with_all = ["detach", "stop"])]
+        list: bool,
+        /// Stop background commands started with --detach
+        #[arg(long, conflicts_with = "detach")]
+        stop: bool,
         /// Command to execute
         #[arg(last = true)]
         command: Vec<String>,
@@ -105,11 +129,26 @@ enum Commands {
         /// Agent name
         #[arg(long)]
         agent: String,
+        /// Only show the last N lines
+        #[arg(long)]
+        tail: Option<usize>,
+        /// Only show lines since a time (e.g. 10m, 2h, 1d or an RFC 3339 timestamp)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only show lines matching a regular expression
+        #[arg(long)]
+        grep: Option<String>,
+        /// Only show one stream (stdout, stderr or agentcrew)
+        #[arg(long)]
+        stream: Option<String>,
     },
     /// Follow agent's progress in real-time
     Follow {
         /// Agent name
-        agent: String,
+        agent: Option<String>,
+        /// Interleave the output of every agent in the current session
+        #[arg(long, conflicts_with = "agent")]
+        all: bool,
     },
     /// Commit agent progress
     Checkpoint {
@@ -120,15 +159,50 @@ enum Commands {
         #[arg(long)]
         message: String,
     },
+    /// List an agent's checkpoints
+    Checkpoints {
+        /// Agent name
+        #[arg(long)]
+        agent: String,
+    },
+    /// Reset an agent's worktree to an earlier checkpoint
+    Rollback {
+        /// Agent name
+        #[arg(long)]
+        agent: String,
+        /// Checkpoint id (#12) or commit prefix
+        #[arg(long)]
+        to: String,
+        /// Relaunch the agent instead of briefing it about the rollback
+        #[arg(long)]
+        restart: bool,
+    },
     /// Compare code changes between agents
     Diff {
         /// First agent
         agent1: String,
         /// Second agent
         agent2: String,
+        /// Show a diffstat of the two branch tips
+        #[arg(long, conflicts_with_all = ["name_only", "patch"])]
+        stat: bool,
+        /// Show only the paths that differ between the two branch tips
+        #[arg(long, conflicts_with = "patch")]
+        name_only: bool,
+        /// Show the full patch between the two branch tips
+        #[arg(long)]
+        patch: bool,
     },
     /// Collect and analyze results from all completed agents
     Harvest,
+    /// Bring an agent's work into the current branch
+    Adopt {
+        /// Agent name; defaults to the winner marked in the TUI
+        agent: Option<String>,
+        /// How to bring the work in: merge, squash or rebase
+        #[arg(long, default_value = "merge")]
+        strategy: AdoptStrategy,
+    },
     /// Save current multi-agent session
     Save {
         /// Session name
@@ -138,11 +212,61 @@ enum Commands {
     Load {
         /// Session name
         name: String,
+        /// Reset branches to the saved commits even if that discards later commits
+        #[arg(long)]
+        force: bool,
     },
     /// List previous sessions and their outcomes
     History,
-    /// Clean up completed worktrees and temporary files
-    Clean,
+    /// Pack a session into a tar file for another machine
+    Export {
+        /// Session name or id prefix
+        session: String,
+        /// Tar file to write
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Unpack a session exported from another clone
+    Import {
+        /// Tar file written by 'agentcrew export'
+        file: PathBuf,
+    },
+    /// Remove worktrees and branches of finished agents
+    Clean {
+        /// Also remove worktrees with uncommitted or unharvested changes
+        #[arg(long)]
+        force: bool,
+        /// Show what would be removed without touching anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Only clean agents idle for longer than this (e.g. 12h or 7d)
+        #[arg(long)]
+        older_than: Option<String>,
+        /// Remove worktrees but keep their branches
+        #[arg(long)]
+        keep_branches: bool,
+    },
+    /// Run the background daemon that supervises agents
+    Daemon {
+        /// Stop the running daemon and dismiss its agents
+        #[arg(long)]
+        stop: bool,
+    },
+    /// Inspect the layered configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective configuration
+    Show {
+        /// Show where each value was set: default, global, project or env
+        #[arg(long)]
+        origin: bool,
+    },
 }
 
 #[tokio::main]
@@ -154,17 +278,15 @@ async fn main() -> Result<()> {
             CommandHandler::init().await
         }
         Commands::Deploy { agents, prompt } => {
-            println!("🤖 Deploying agents: {}", agents);
+            if let Some(agents) = &agents {
+                println!("🤖 Deploying agents: {}", agents);
+            }
             println!("📝 Prompt: {}", prompt);
-            deploy_agents(&agents, &prompt).await
-        }
-        Commands::Status => {
-            println!("📊 Checking agent status...");
-            show_status().await
+            CommandHandler::deploy(agents.as_deref(), &prompt).await
         }
+        Commands::Status { json, watch } => CommandHandler::status(json, watch).await,
         Commands::Tui => {
-            println!("🖥️  Launching TUI interface...");
-            launch_tui().await
+            CommandHandler::tui().await
         }
         Commands::List => {
             println!("📋 Available agent types:");
@@ -172,183 +294,169 @@ async fn main() -> Result<()> {
         }
         Commands::Pause { agent } => {
             println!("⏸️  Pausing agent: {}", agent);
-            pause_agent(&agent).await
+            CommandHandler::pause(&agent).await
         }
         Commands::Resume { agent } => {
             println!("▶️  Resuming agent: {}", agent);
-            resume_agent(&agent).await
+            CommandHandler::resume(&agent).await
         }
         Commands::Restart { agent } => {
             println!("🔄 Restarting agent: {}", agent);
-            restart_agent(&agent).await
+            CommandHandler::restart(&agent).await
         }
         Commands::Dismiss { agent } => {
             println!("👋 Dismissing agent: {}", agent);
-            dismiss_agent(&agent).await
+            CommandHandler::dismiss(&agent).await
         }
         Commands::Brief { message } => {
             println!("📢 Briefing all agents: {}", message);
-            brief_agents(&message).await
+            CommandHandler::brief(&message).await
         }
         Commands::Respond { agent, response } => {
             println!("💬 Responding to {}: {}", agent, response);
-            respond_to_agent(&agent, &response).await
+            CommandHandler::respond(&agent, &response).await
         }
+        Commands::Questions => CommandHandler::questions().await,
         Commands::Broadcast { urgent, message } => {
             let urgency = if urgent { "🚨 URGENT" } else { "📡" };
             println!("{} Broadcasting: {}", urgency, message);
-            broadcast_message(&message, urgent).await
+            CommandHandler::broadcast(&message, urgent).await
         }
-        Commands::Worktrees => {
+        Commands::Worktrees { repair } => {
             println!("🌳 Agent worktrees:");
-            list_worktrees().await
+            CommandHandler::worktrees(repair).await
         }
         Commands::Exec {
             all,
             agent,
+            jobs,
+            detach,
+            list,
+            stop,
             command,
         } => {
-            if all {
-                println!("🔧 Executing on all agents: {:?}", command);
-                exec_all(&command).await
-            } else if let Some(agent_name) = agent {
-                println!("🔧 Executing on {}: {:?}", agent_name, command);
-                exec_agent(&agent_name, &command).await
+            if list {
+                return CommandHandler::exec_list().await;
+            }
+            if !all && agent.is_none() {
+                anyhow::bail!("Must specify either --all or --agent");
+            }
+            if stop {
+                CommandHandler::exec_stop(agent.as_deref()).await
             } else {
-                anyhow::bail!("Must specify either --all or --agent")
+                match &agent {
+                    Some(agent_name) => println!("🔧 Executing on {}: {:?}", agent_name, command),
+                    None => println!("🔧 Executing on all agents: {:?}", command),
+                }
+                CommandHandler::exec(agent.as_deref(), &command, jobs, detach).await
             }
         }
         Commands::Switch { agent } => {
             println!("🔀 Switching to {}'s worktree", agent);
             switch_to_agent(&agent).await
         }
-        Commands::Logs { agent } => {
+        Commands::Logs {
+            agent,
+            tail,
+            since,
+            grep,
+            stream,
+        } => {
             println!("📄 Showing logs for: {}", agent);
-            show_logs(&agent).await
+            CommandHandler::logs(
+                &agent,
+                tail,
+                since.as_deref(),
+                grep.as_deref(),
+                stream.as_deref(),
+            )
+            .await
         }
-        Commands::Follow { agent } => {
-            println!("👀 Following {}'s progress...", agent);
-            follow_agent(&agent).await
+        Commands::Follow { agent, all } => {
+            match &agent {
+                Some(agent) => println!("👀 Following {}'s progress...", agent),
+                None => println!("👀 Following all agents..."),
+            }
+            CommandHandler::follow(agent.as_deref(), all).await
         }
         Commands::Checkpoint { agent, message } => {
             println!("✅ Checkpointing {}: {}", agent, message);
-            checkpoint_agent(&agent, &message).await
+            CommandHandler::checkpoint(&agent, &message).await
+        }
+        Commands::Checkpoints { agent } => CommandHandler::checkpoints(&agent).await,
+        Commands::Rollback { agent, to, restart } => {
+            println!("⏪ Rolling back {} to {}...", agent, to);
+            CommandHandler::rollback(&agent, &to, restart).await
         }
-        Commands::Diff { agent1, agent2 } => {
-            println!("🔍 Comparing {} vs {}", agent1, agent2);
-            diff_agents(&agent1, &agent2).await
+        Commands::Diff {
+            agent1,
+            agent2,
+            stat,
+            name_only,
+            patch,
+        } => {
+            let mode = if stat {
+                DiffMode::Stat
+            } else if name_only {
+                DiffMode::NameOnly
+            } else if patch {
+                DiffMode::Patch
+            } else {
+                println!("🔍 Comparing {} vs {}", agent1, agent2);
+                DiffMode::Summary
+            };
+            CommandHandler::diff(&agent1, &agent2, mode).await
         }
-        Commands::Harvest => {
-            println!("🌾 Harvesting results from all agents...");
-            harvest_results().await
+        Commands::Harvest => CommandHandler::harvest().await,
+        Commands::Adopt { agent, strategy } => {
+            CommandHandler::adopt(agent.as_deref(), strategy).await
         }
         Commands::Save { name } => {
             println!("💾 Saving session: {}", name);
-            save_session(&name).await
+            CommandHandler::save(&name).await
         }
-        Commands::Load { name } => {
+        Commands::Load { name, force } => {
             println!("📁 Loading session: {}", name);
-            load_session(&name).await
+            CommandHandler::load(&name, force).await
         }
         Commands::History => {
             println!("📚 Session history:");
-            show_history().await
+            CommandHandler::history().await
+        }
+        Commands::Export { session, output } => {
+            println!("📦 Exporting session {}...", session);
+            CommandHandler::export(&session, &output).await
         }
-        Commands::Clean => {
-            println!("🧹 Cleaning up completed worktrees...");
-            clean_worktrees().await
+        Commands::Import { file } => {
+            println!("📥 Importing {}...", file.display());
+            CommandHandler::import(&file).await
+        }
+        Commands::Clean {
+            force,
+            dry_run,
+            older_than,
+            keep_branches,
+        } => {
+            println!("🧹 Cleaning up finished agents...");
+            CommandHandler::clean(force, dry_run, older_than.as_deref(), keep_branches).await
         }
+        Commands::Daemon { stop } => CommandHandler::daemon(stop).await,
+        Commands::Config {
+            command: ConfigCommand::Show { origin },
+        } => CommandHandler::config_show(origin),
     }
 }
 
 // Basic stub implementations - we'll expand these incrementally
 
-async fn deploy_agents(_agents: &str, _prompt: &str) -> Result<()> {
-    println!("  🌿 Creating git worktrees...");
-    println!("  🤖 Spawning agent processes...");
-    println!("  🎯 Sending initial prompt...");
-    println!("  🎉 Agents deployed successfully!");
-    Ok(())
-}
-
-async fn show_status() -> Result<()> {
-    println!("  📊 Active agents: 0");
-    println!("  🌳 Worktrees: 0");
-    println!("  ⏳ Pending questions: 0");
-    Ok(())
-}
-
-async fn launch_tui() -> Result<()> {
-    println!("  🖥️  TUI interface not yet implemented");
-    println!("  💡 Use 'agentcrew status' for now");
-    Ok(())
-}
-
 async fn list_agents() -> Result<()> {
-    println!("  🧠 claude - Anthropic Claude Code (local execution)");
-    println!("  🤖 gpt - OpenAI GPT/Codex (API-based)");
-    println!("  🌟 jules - Google Jules/Gemini (GitHub integration)");
+    for provider in ProviderRegistry::builtin().iter() {
+        println!("  {} {} - {}", provider.icon(), provider.name(), provider.description());
+    }
     Ok(())
 }
 
 // Placeholder implementations for all other commands
-async fn pause_agent(_agent: &str) -> Result<()> {
-    Ok(())
-}
-async fn resume_agent(_agent: &str) -> Result<()> {
-    Ok(())
-}
-async fn restart_agent(_agent: &str) -> Result<()> {
-    Ok(())
-}
-async fn dismiss_agent(_agent: &str) -> Result<()> {
-    Ok(())
-}
-async fn brief_agents(_message: &str) -> Result<()> {
-    Ok(())
-}
-async fn respond_to_agent(_agent: &str, _response: &str) -> Result<()> {
-    Ok(())
-}
-async fn broadcast_message(_message: &str, _urgent: bool) -> Result<()> {
-    Ok(())
-}
-async fn list_worktrees() -> Result<()> {
-    Ok(())
-}
-async fn exec_all(_command: &[String]) -> Result<()> {
-    Ok(())
-}
-async fn exec_agent(_agent: &str, _command: &[String]) -> Result<()> {
-    Ok(())
-}
 async fn switch_to_agent(_agent: &str) -> Result<()> {
     Ok(())
 }
-async fn show_logs(_agent: &str) -> Result<()> {
-    Ok(())
-}
-async fn follow_agent(_agent: &str) -> Result<()> {
-    Ok(())
-}
-async fn checkpoint_agent(_agent: &str, _message: &str) -> Result<()> {
-    Ok(())
-}
-async fn diff_agents(_agent1: &str, _agent2: &str) -> Result<()> {
-    Ok(())
-}
-async fn harvest_results() -> Result<()> {
-    Ok(())
-}
-async fn save_session(_name: &str) -> Result<()> {
-    Ok(())
-}
-async fn load_session(_name: &str) -> Result<()> {
-    Ok(())
-}
-async fn show_history() -> Result<()> {
-    Ok(())
-}
-async fn clean_worktrees() -> Result<()> {
-    Ok(())
-}
@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+
+/// How long to wait for a freshly spawned daemon to accept connections
+const DAEMON_STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Prefix of an output line through which an agent asks the user a question,
+/// e.g. `[[agentcrew:question]] Should I add a migration?`. Agents find it in
+/// the `AGENTCREW_QUESTION_MARKER` environment variable.
+pub const QUESTION_MARKER: &str = "[[agentcrew:question]]";
+
+/// The question asked by an output line, if it carries the question marker
+pub fn parse_question(line: &str) -> Option<&str> {
+    let question = line.trim_start().strip_prefix(QUESTION_MARKER)?.trim();
+    (!question.is_empty()).then_some(question)
+}
+
+/// A request sent from the CLI to the daemon (one JSON object per line)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Check the daemon is alive
+    Ping,
+    /// Launch every agent of a session that is not running yet
+    Launch {
+        session_id: String,
+    },
+    /// List agents the daemon is supervising
+    Status,
+    Pause {
+        agent_id: String,
+    },
+    Resume {
+        agent_id: String,
+    },
+    Restart {
+        agent_id: String,
+    },
+    Dismiss {
+        agent_id: String,
+    },
+    /// Deliver a message to one agent
+    Send {
+        agent_id: String,
+        message: String,
+    },
+    /// Answer an agent's pending questions
+    Respond {
+        agent_id: String,
+        message: String,
+    },
+    /// Deliver instructions to every running agent
+    Brief {
+        message: String,
+    },
+    /// Deliver a priority message to every running agent; urgent messages
+    /// interrupt the agent's current turn first
+    Broadcast {
+        message: String,
+        urgent: bool,
+    },
+    /// Dismiss all agents and stop the daemon
+    Shutdown,
+}
+
+/// An agent supervised by the daemon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeAgent {
+    pub agent_id: String,
+    pub name: String,
+    pub pid: Option<u32>,
+}
+
+/// Outcome of delivering a message to one agent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub agent: String,
+    pub error: Option<String>,
+}
+
+/// The daemon's answer to a [`Request`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Error {
+        message: String,
+    },
+    Pong {
+        pid: u32,
+    },
+    Launched {
+        started: Vec<RuntimeAgent>,
+        failed: Vec<Delivery>,
+    },
+    Status {
+        agents: Vec<RuntimeAgent>,
+    },
+    Dismissed {
+        exit_code: Option<i32>,
+    },
+    Delivered {
+        deliveries: Vec<Delivery>,
+    },
+    /// Ids of the questions a response answered
+    Answered {
+        questions: Vec<i64>,
+    },
+}
+
+/// Connection to the agentcrew daemon
+pub struct DaemonClient {
+    stream: BufReader<UnixStream>,
+}
+
+impl DaemonClient {
+    /// Connect to a running daemon
+    pub async fn connect(socket_path: &Path) -> Result<Self> {
+        let stream = UnixStream::connect(socket_path)
+            .await
+            .with_context(|| format!("Failed to connect to daemon at {}", socket_path.display()))?;
+        Ok(Self {
+            stream: BufReader::new(stream),
+        })
+    }
+
+    /// Connect to the daemon, starting it in the background if it is not running
+    pub async fn connect_or_spawn(socket_path: &Path, log_path: &Path) -> Result<Self> {
+        if let Ok(client) = Self::connect(socket_path).await {
+            return Ok(client);
+        }
+
+        println!("  🛰️  Starting agentcrew daemon...");
+        spawn_daemon(log_path)?;
+
+        let deadline = tokio::time::Instant::now() + DAEMON_STARTUP_TIMEOUT;
+        loop {
+            match Self::connect(socket_path).await {
+                Ok(client) => return Ok(client),
+                Err(e) if tokio::time::Instant::now() >= deadline => {
+                    return Err(
+                        e.context(format!("Daemon did not start, see {}", log_path.display()))
+                    )
+                }
+                Err(_) => tokio::time::sleep(Duration::from_millis(100)).await,
+            }
+        }
+    }
+
+    /// Send a request and wait for the response. Daemon errors become `Err`.
+    pub async fn request(&mut self, request: &Request) -> Result<Response> {
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        self.stream.get_mut().write_all(line.as_bytes()).await?;
+
+        let mut reply = String::new();
+        if self.stream.read_line(&mut reply).await? == 0 {
+            anyhow::bail!("Daemon closed the connection");
+        }
+        match serde_json::from_str(&reply).with_context(|| "Invalid response from daemon")? {
+            Response::Error { message } => Err(anyhow::anyhow!(message)),
+            response => Ok(response),
+        }
+    }
+}
+
+/// Start `agentcrew daemon` detached from the current terminal
+fn spawn_daemon(log_path: &Path) -> Result<()> {
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("Failed to open daemon log: {}", log_path.display()))?;
+
+    let exe: PathBuf =
+        std::env::current_exe().with_context(|| "Failed to locate agentcrew binary")?;
+    std::process::Command::new(exe)
+        .arg("daemon")
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .process_group(0)
+        .spawn()
+        .with_context(|| "Failed to start daemon")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_round_trip() {
+        let request = Request::Send {
+            agent_id: "a1".to_string(),
+            message: "yes".to_string(),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"type":"send","agent_id":"a1","message":"yes"}"#);
+
+        let response: Response =
+            serde_json::from_str(r#"{"type":"dismissed","exit_code":null}"#).unwrap();
+        assert!(matches!(response, Response::Dismissed { exit_code: None }));
+    }
+
+    #[test]
+    fn test_parse_question() {
+        assert_eq!(
+            parse_question("[[agentcrew:question]] Use SQLite? "),
+            Some("Use SQLite?")
+        );
+        assert_eq!(parse_question("  [[agentcrew:question]]ok?"), Some("ok?"));
+        assert_eq!(parse_question("[[agentcrew:question]]   "), None);
+        assert_eq!(parse_question("Use SQLite?"), None);
+    }
+}
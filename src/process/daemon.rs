@@ -0,0 +1,410 @@
+use anyhow::{Context, Result};
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{broadcast, Notify};
+
+use super::communication::{Delivery, Request, Response, RuntimeAgent};
+use super::exec::stop_agent_commands;
+use super::{signals, Supervisor, SupervisorEvent, DEFAULT_TERMINATE_GRACE};
+use crate::agents::AgentEvent;
+use crate::database::{AgentStatus, Database, DeliveryStatus, InteractionType};
+
+/// Long-running process that owns the [`Supervisor`] and serves CLI requests
+/// over a Unix socket, so agents outlive the command that deployed them.
+pub struct Daemon {
+    db: Database,
+    supervisor: Arc<Supervisor>,
+    shutdown: Notify,
+}
+
+impl Daemon {
+    /// Create a daemon serving requests with `supervisor`
+    pub fn new(db: Database, supervisor: Arc<Supervisor>) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            supervisor,
+            shutdown: Notify::new(),
+        })
+    }
+
+    /// Listen on `socket_path` until a shutdown request, SIGTERM or Ctrl-C,
+    /// then dismiss every agent and remove the socket
+    pub async fn serve(self: Arc<Self>, socket_path: &Path) -> Result<()> {
+        let listener = bind(socket_path)?;
+        self.reconcile().await?;
+        tokio::spawn(log_events(self.supervisor.subscribe()));
+        println!(
+            "🛰️  agentcrew daemon (pid {}) listening on {}",
+            std::process::id(),
+            socket_path.display()
+        );
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (stream, _) = accepted?;
+                    let daemon = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = daemon.handle_connection(stream).await {
+                            eprintln!("⚠️  Client connection failed: {:#}", e);
+                        }
+                    });
+                }
+                _ = self.shutdown.notified() => break,
+                _ = terminate.recv() => break,
+                _ = tokio::signal::ctrl_c() => break,
+            }
+        }
+
+        println!("👋 Daemon shutting down, dismissing agents...");
+        self.supervisor.dismiss_all(DEFAULT_TERMINATE_GRACE).await;
+        let _ = std::fs::remove_file(socket_path);
+        Ok(())
+    }
+
+    /// Agents left running by a daemon that died cannot be supervised again:
+    /// stop any surviving process and mark them failed
+    async fn reconcile(&self) -> Result<()> {
+        for agent in self.db.list_unfinished_agents().await? {
+            // Freshly deployed agents that were never launched are left alone
+            if agent.status == AgentStatus::Initializing && agent.process_id.is_none() {
+                continue;
+            }
+            if let Some(pid) = agent.process_id {
+                let _ = signals::terminate(pid as u32, DEFAULT_TERMINATE_GRACE).await;
+            }
+            self.db.set_agent_process(&agent.id, None).await?;
+            self.db
+                .update_agent_status(&agent.id, AgentStatus::Failed)
+                .await?;
+            println!("  ⚠️  {} was orphaned and marked failed", agent.name());
+        }
+        Ok(())
+    }
+
+    async fn handle_connection(&self, stream: UnixStream) -> Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            let response = match serde_json::from_str::<Request>(&line) {
+                Ok(Request::Shutdown) => {
+                    self.shutdown.notify_one();
+                    Response::Ok
+                }
+                Ok(request) => self
+                    .handle(request)
+                    .await
+                    .unwrap_or_else(|e| Response::Error {
+                        message: format!("{:#}", e),
+                    }),
+                Err(e) => Response::Error {
+                    message: format!("Invalid request: {}", e),
+                },
+            };
+
+            let mut reply = serde_json::to_string(&response)?;
+            reply.push('\n');
+            writer.write_all(reply.as_bytes()).await?;
+        }
+        Ok(())
+    }
+
+    async fn handle(&self, request: Request) -> Result<Response> {
+        let response = match request {
+            Request::Ping => Response::Pong {
+                pid: std::process::id(),
+            },
+            Request::Launch { session_id } => {
+                let session = self.db.get_session(&session_id).await?;
+                let mut started = Vec::new();
+                let mut failed = Vec::new();
+                for agent in self.db.list_agents(&session.id).await? {
+                    if agent.status != AgentStatus::Initializing {
+                        continue;
+                    }
+                    match self.supervisor.launch(&agent, &session.prompt).await {
+                        Ok(()) => started.push(self.runtime_agent(&agent.id, agent.name()).await),
+                        Err(e) => failed.push(Delivery {
+                            agent: agent.name(),
+                            error: Some(format!("{:#}", e)),
+                        }),
+                    }
+                }
+                Response::Launched { started, failed }
+            }
+            Request::Status => {
+                let mut agents = Vec::new();
+                for (agent_id, name) in self.supervisor.running().await {
+                    agents.push(self.runtime_agent(&agent_id, name).await);
+                }
+                agents.sort_by(|a, b| a.name.cmp(&b.name));
+                Response::Status { agents }
+            }
+            Request::Pause { agent_id } => {
+                self.supervisor.pause(&agent_id).await?;
+                Response::Ok
+            }
+            Request::Resume { agent_id } => {
+                self.supervisor.resume(&agent_id).await?;
+                Response::Ok
+            }
+            Request::Restart { agent_id } => {
+                let agent = self.db.get_agent(&agent_id).await?;
+                let session = self.db.get_session(&agent.session_id).await?;
+                if !self.supervisor.is_running(&agent.id).await {
+                    // Left over from a previous daemon or a foreground run
+                    if let Some(pid) = agent.process_id {
+                        signals::terminate(pid as u32, DEFAULT_TERMINATE_GRACE).await?;
+                    }
+                }
+                self.supervisor
+                    .restart(&agent, &session.prompt, DEFAULT_TERMINATE_GRACE)
+                    .await?;
+                Response::Launched {
+                    started: vec![self.runtime_agent(&agent.id, agent.name()).await],
+                    failed: Vec::new(),
+                }
+            }
+            Request::Dismiss { agent_id } => {
+                if self.supervisor.is_running(&agent_id).await {
+                    let exit_code = self
+                        .supervisor
+                        .dismiss(&agent_id, DEFAULT_TERMINATE_GRACE)
+                        .await?;
+                    return Ok(Response::Dismissed { exit_code });
+                }
+
+                let agent = self.db.get_agent(&agent_id).await?;
+                if let Some(pid) = agent.process_id {
+                    signals::terminate(pid as u32, DEFAULT_TERMINATE_GRACE).await?;
+                }
+                self.db.set_agent_process(&agent.id, None).await?;
+                self.db
+                    .update_agent_status(&agent.id, AgentStatus::Dismissed)
+                    .await?;
+                stop_agent_commands(&self.db, &agent.id, DEFAULT_TERMINATE_GRACE).await?;
+                self.db.release_ports(&agent.id).await?;
+                Response::Dismissed { exit_code: None }
+            }
+            Request::Send { agent_id, message } => {
+                self.supervisor.send(&agent_id, &message).await?;
+                Response::Ok
+            }
+            Request::Respond { agent_id, message } => {
+                let agent = self.db.get_agent(&agent_id).await?;
+                self.supervisor.send(&agent.id, &message).await?;
+                let questions = self
+                    .db
+                    .answer_questions(&agent.id, &agent.session_id, &message)
+                    .await?;
+                Response::Answered { questions }
+            }
+            Request::Brief { message } => {
+                self.deliver_all(InteractionType::Brief, &message, false)
+                    .await?
+            }
+            Request::Broadcast { message, urgent } => {
+                self.deliver_all(InteractionType::Broadcast, &message, urgent)
+                    .await?
+            }
+            Request::Shutdown => Response::Ok,
+        };
+        Ok(response)
+    }
+
+    /// Send a message to every running agent and record one interaction per
+    /// recipient with its delivery status
+    async fn deliver_all(
+        &self,
+        interaction_type: InteractionType,
+        message: &str,
+        urgent: bool,
+    ) -> Result<Response> {
+        let mut deliveries = Vec::new();
+        for (agent_id, name) in self.supervisor.running().await {
+            let delivered = if urgent {
+                self.supervisor.send_urgent(&agent_id, message).await
+            } else {
+                self.supervisor.send(&agent_id, message).await
+            };
+
+            let error = delivered.err().map(|e| format!("{:#}", e));
+            let (status, metadata) = match &error {
+                None => (DeliveryStatus::Delivered, json!({ "urgent": urgent })),
+                Some(error) => (
+                    DeliveryStatus::Failed,
+                    json!({ "urgent": urgent, "error": error }),
+                ),
+            };
+            let agent = self.db.get_agent(&agent_id).await?;
+            self.db
+                .record_delivery(
+                    &agent.id,
+                    &agent.session_id,
+                    interaction_type,
+                    message,
+                    status,
+                    Some(&metadata.to_string()),
+                )
+                .await?;
+            deliveries.push(Delivery { agent: name, error });
+        }
+        deliveries.sort_by(|a, b| a.agent.cmp(&b.agent));
+        Ok(Response::Delivered { deliveries })
+    }
+
+    async fn runtime_agent(&self, agent_id: &str, name: String) -> RuntimeAgent {
+        RuntimeAgent {
+            agent_id: agent_id.to_string(),
+            name,
+            pid: self.supervisor.pid(agent_id).await,
+        }
+    }
+}
+
+/// Bind the daemon socket, replacing a stale one left by a crashed daemon
+fn bind(socket_path: &Path) -> Result<UnixListener> {
+    if socket_path.exists() {
+        if std::os::unix::net::UnixStream::connect(socket_path).is_ok() {
+            anyhow::bail!("A daemon is already listening on {}", socket_path.display());
+        }
+        std::fs::remove_file(socket_path)
+            .with_context(|| format!("Failed to remove stale socket: {}", socket_path.display()))?;
+    }
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    UnixListener::bind(socket_path)
+        .with_context(|| format!("Failed to listen on {}", socket_path.display()))
+}
+
+/// Record questions and exits of supervised agents in the daemon log
+async fn log_events(mut events: broadcast::Receiver<SupervisorEvent>) {
+    loop {
+        match events.recv().await {
+            Ok(SupervisorEvent {
+                agent_name, event, ..
+            }) => match event {
+                AgentEvent::Output { .. } => {}
+                AgentEvent::Question { text } => println!("❓ [{}] {}", agent_name, text),
+                AgentEvent::Exited { code: Some(code) } => {
+                    println!("🏁 [{}] exited with code {}", agent_name, code)
+                }
+                AgentEvent::Exited { code: None } => println!("🏁 [{}] terminated", agent_name),
+            },
+            Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::ProviderRegistry;
+    use crate::process::DaemonClient;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_rpc_round_trip() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("build it", r#"{"mock":1}"#, "main", "abc123")
+            .await
+            .expect("Should create session");
+        let options = BTreeMap::from([("delay_ms".to_string(), "0".to_string())]);
+        let agent = db
+            .create_agent(&session.id, "mock", 1, &options)
+            .await
+            .expect("Should create agent");
+        db.set_agent_worktree(&agent.id, temp_dir.path(), "agentcrew/x/mock-1")
+            .await
+            .expect("Should set worktree");
+
+        let socket = temp_dir.path().join("agentcrew.sock");
+        let supervisor = Supervisor::new(db.clone(), ProviderRegistry::builtin());
+        let daemon = Daemon::new(db.clone(), supervisor);
+        let server = tokio::spawn({
+            let socket = socket.clone();
+            async move { daemon.serve(&socket).await }
+        });
+
+        let mut client = None;
+        for _ in 0..50 {
+            if let Ok(connected) = DaemonClient::connect(&socket).await {
+                client = Some(connected);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let mut client = client.expect("Should connect to daemon");
+
+        let pong = client.request(&Request::Ping).await.expect("Should ping");
+        assert!(matches!(pong, Response::Pong { pid } if pid == std::process::id()));
+
+        let launched = client
+            .request(&Request::Launch {
+                session_id: session.id.clone(),
+            })
+            .await
+            .expect("Should launch");
+        let Response::Launched { started, failed } = launched else {
+            panic!("Unexpected response: {:?}", launched);
+        };
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].name, "mock-1");
+        assert!(failed.is_empty());
+
+        let status = client
+            .request(&Request::Status)
+            .await
+            .expect("Should report status");
+        assert!(matches!(status, Response::Status { ref agents } if agents.len() == 1));
+
+        // Errors from the supervisor come back as `Err`
+        assert!(client
+            .request(&Request::Pause {
+                agent_id: "missing".to_string()
+            })
+            .await
+            .is_err());
+
+        for _ in 0..100 {
+            if db.get_agent(&agent.id).await.unwrap().status == AgentStatus::Waiting {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(db.pending_questions().await.unwrap().len(), 1);
+        let answered = client
+            .request(&Request::Respond {
+                agent_id: agent.id.clone(),
+                message: "y".to_string(),
+            })
+            .await
+            .expect("Should answer the agent");
+        assert!(matches!(answered, Response::Answered { ref questions } if questions.len() == 1));
+        assert!(db.pending_questions().await.unwrap().is_empty());
+
+        client
+            .request(&Request::Shutdown)
+            .await
+            .expect("Should shut down");
+        server
+            .await
+            .expect("Server task should not panic")
+            .expect("Server should stop cleanly");
+        assert!(!socket.exists());
+    }
+}
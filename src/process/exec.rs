@@ -0,0 +1,276 @@
+//! Shell commands run in agent worktrees by `agentcrew exec`
+
+use anyhow::{Context, Result};
+use std::fs::OpenOptions;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use super::signals;
+use crate::database::{Database, ExecProcessRecord};
+
+/// How a command run in one worktree ended
+#[derive(Debug, Clone, Copy)]
+pub struct ExecOutcome {
+    /// `None` when the command was killed by a signal
+    pub exit_code: Option<i32>,
+    pub duration: Duration,
+}
+
+impl ExecOutcome {
+    /// Whether the command exited successfully
+    pub fn passed(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Whether a detached command's process group is still running, and was not
+/// replaced by an unrelated process that reused its pid
+pub fn is_running(process: &ExecProcessRecord) -> bool {
+    let start_time = process.start_time.map(|ticks| ticks as u64);
+    signals::is_same_group(process.pid as u32, start_time)
+}
+
+/// Stop the detached commands of an agent before its ports or worktree are
+/// released. Returns how many were still running.
+pub async fn stop_agent_commands(db: &Database, agent_id: &str, grace: Duration) -> Result<usize> {
+    let mut stopped = 0;
+    for process in db.running_agent_exec_processes(agent_id).await? {
+        if is_running(&process) {
+            signals::terminate(process.pid as u32, grace).await?;
+            stopped += 1;
+        }
+        db.mark_exec_stopped(process.id).await?;
+    }
+    Ok(stopped)
+}
+
+/// Join command arguments into a line for `sh -c`. A single argument is used
+/// as-is so it can hold a whole pipeline; otherwise arguments are quoted as
+/// needed.
+pub fn shell_command(args: &[String]) -> String {
+    if let [line] = args {
+        return line.clone();
+    }
+    args.iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Run `command` with `sh -c` in `dir`, printing every output line behind
+/// `prefix` as it arrives
+pub async fn run_prefixed(
+    command: &str,
+    dir: &Path,
+    env: &[(String, String)],
+    prefix: &str,
+) -> Result<ExecOutcome> {
+    let started = Instant::now();
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to run '{}' in {}", command, dir.display()))?;
+
+    let stdout = child.stdout.take().map(|out| {
+        let prefix = prefix.to_string();
+        tokio::spawn(async move { print_lines(out, &prefix, false).await })
+    });
+    let stderr = child.stderr.take().map(|err| {
+        let prefix = prefix.to_string();
+        tokio::spawn(async move { print_lines(err, &prefix, true).await })
+    });
+    let status = child
+        .wait()
+        .await
+        .with_context(|| format!("Failed to wait for '{}'", command))?;
+    for printer in [stdout, stderr].into_iter().flatten() {
+        let _ = printer.await;
+    }
+
+    Ok(ExecOutcome {
+        exit_code: status.code(),
+        duration: started.elapsed(),
+    })
+}
+
+async fn print_lines(reader: impl AsyncRead + Unpin, prefix: &str, stderr: bool) {
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut line).await, Ok(n) if n > 0) {
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\n', '\r']);
+        if stderr {
+            eprintln!("{} {}", prefix, text);
+        } else {
+            println!("{} {}", prefix, text);
+        }
+        line.clear();
+    }
+}
+
+/// Start `command` with `sh -c` in `dir` as the leader of a new process group
+/// that outlives agentcrew, appending its output to `log_path`. Returns the pid.
+pub fn spawn_detached(
+    command: &str,
+    dir: &Path,
+    env: &[(String, String)],
+    log_path: &Path,
+) -> Result<u32> {
+    if let Some(parent) = log_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)
+        .with_context(|| format!("Failed to open {}", log_path.display()))?;
+
+    let child = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .current_dir(dir)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .process_group(0)
+        .spawn()
+        .with_context(|| format!("Failed to run '{}' in {}", command, dir.display()))?;
+    Ok(child.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_shell_command() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            shell_command(&args(&["npm test && npm run lint"])),
+            "npm test && npm run lint"
+        );
+        assert_eq!(
+            shell_command(&args(&["cargo", "test", "--", "--nocapture"])),
+            "cargo test -- --nocapture"
+        );
+        assert_eq!(
+            shell_command(&args(&["echo", "it's here", ""])),
+            r"echo 'it'\''s here' ''"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_prefixed_and_detached() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let env = vec![("AGENTCREW_AGENT".to_string(), "mock-1".to_string())];
+
+        let outcome = run_prefixed(
+            "test \"$AGENTCREW_AGENT\" = mock-1",
+            temp_dir.path(),
+            &env,
+            "[mock-1]",
+        )
+        .await
+        .expect("Should run command");
+        assert!(outcome.passed());
+        let outcome = run_prefixed("echo oops >&2; exit 3", temp_dir.path(), &env, "[mock-1]")
+            .await
+            .expect("Should run command");
+        assert_eq!(outcome.exit_code, Some(3));
+
+        let log = temp_dir.path().join("exec/mock-1.log");
+        let pid = spawn_detached(
+            "echo \"serving $AGENTCREW_AGENT\"; exec sleep 30",
+            temp_dir.path(),
+            &env,
+            &log,
+        )
+        .expect("Should spawn command");
+        assert!(signals::is_alive(pid));
+        for _ in 0..50 {
+            if std::fs::read_to_string(&log)
+                .unwrap_or_default()
+                .contains("serving mock-1")
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(std::fs::read_to_string(&log).unwrap(), "serving mock-1\n");
+
+        signals::terminate(pid, Duration::from_millis(200))
+            .await
+            .expect("Should stop command");
+    }
+
+    #[tokio::test]
+    async fn test_stop_agent_commands() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("serve", r#"{"mock":1}"#, "main", "abc123")
+            .await
+            .unwrap();
+        let agent = db
+            .create_agent(&session.id, "mock", 1, &Default::default())
+            .await
+            .unwrap();
+
+        let log = temp_dir.path().join("exec/mock-1.log");
+        // The leader exits at once; the server it started keeps the group alive
+        let pid =
+            spawn_detached("sleep 30 &", temp_dir.path(), &[], &log).expect("Should spawn command");
+        db.record_exec_process(
+            &session.id,
+            &agent.id,
+            "sleep 30 &",
+            pid,
+            signals::start_time(pid),
+            &log,
+        )
+        .await
+        .unwrap();
+        // Reap the leader like init would once agentcrew has exited
+        nix::sys::wait::waitpid(nix::unistd::Pid::from_raw(pid as i32), None)
+            .expect("Should reap sh");
+
+        let stopped = stop_agent_commands(&db, &agent.id, Duration::from_secs(2))
+            .await
+            .expect("Should stop commands");
+        assert_eq!(stopped, 1);
+        assert!(!signals::group_alive(pid));
+        assert!(db
+            .running_agent_exec_processes(&agent.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,426 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, SecondsFormat, Utc};
+use regex::Regex;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+/// Stream tag of lines written by agentcrew itself rather than the agent
+pub const EVENT_STREAM: &str = "agentcrew";
+
+/// Every stream tag that can appear in an agent log
+pub const LOG_STREAMS: [&str; 3] = ["stdout", "stderr", EVENT_STREAM];
+
+/// Size at which an agent log is rotated
+pub const DEFAULT_MAX_LOG_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Number of rotated files kept next to the active log
+pub const DEFAULT_MAX_LOG_FILES: usize = 3;
+
+/// Path of an agent's active log: `<logs_dir>/<session>/<agent>.log`
+pub fn agent_log_path(logs_dir: &Path, session_slug: &str, agent_name: &str) -> PathBuf {
+    logs_dir
+        .join(session_slug)
+        .join(format!("{}.log", agent_name))
+}
+
+/// Path of the `index`-th rotated file, e.g. `claude-1.log.2`
+pub fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Limits of a rotating log
+#[derive(Debug, Clone, Copy)]
+pub struct LogRotation {
+    pub max_bytes: u64,
+    pub max_files: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_bytes: DEFAULT_MAX_LOG_BYTES,
+            max_files: DEFAULT_MAX_LOG_FILES,
+        }
+    }
+}
+
+/// Append-only, size-rotated log of one agent
+pub struct AgentLog {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    rotation: LogRotation,
+}
+
+impl AgentLog {
+    /// Open (or create) the log at `path` for appending
+    pub fn open(path: &Path, rotation: LogRotation) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create log directory: {}", parent.display()))?;
+        }
+        let file = open_append(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            size,
+            rotation,
+        })
+    }
+
+    /// Append one timestamped line tagged with its stream
+    pub fn write(&mut self, stream: &str, text: &str) -> Result<()> {
+        let entry = LogEntry {
+            timestamp: Utc::now(),
+            stream: stream.to_string(),
+            text: text.replace(['\r', '\n'], " "),
+        };
+        let line = format!("{}\n", entry);
+
+        if self.size > 0 && self.size + line.len() as u64 > self.rotation.max_bytes {
+            self.rotate()?;
+        }
+        self.file
+            .write_all(line.as_bytes())
+            .with_context(|| format!("Failed to write log: {}", self.path.display()))?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift `log.N` to `log.N+1`, dropping the oldest, and start a fresh file
+    fn rotate(&mut self) -> Result<()> {
+        if self.rotation.max_files > 0 {
+            let _ = fs::remove_file(rotated_path(&self.path, self.rotation.max_files));
+            for index in (1..self.rotation.max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        } else {
+            fs::remove_file(&self.path)?;
+        }
+
+        self.file = open_append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log: {}", path.display()))
+}
+
+/// One line of an agent log: `<rfc3339> [<stream>] <text>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub stream: String,
+    pub text: String,
+}
+
+impl LogEntry {
+    /// Parse a line written by [`AgentLog`]
+    pub fn parse(line: &str) -> Option<Self> {
+        let (timestamp, rest) = line.split_once(' ')?;
+        let timestamp = DateTime::parse_from_rfc3339(timestamp)
+            .ok()?
+            .with_timezone(&Utc);
+        let (stream, text) = rest.strip_prefix('[')?.split_once(']')?;
+        Some(Self {
+            timestamp,
+            stream: stream.to_string(),
+            text: text.strip_prefix(' ').unwrap_or(text).to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            self.stream,
+            self.text
+        )
+    }
+}
+
+/// Delete a log and its rotated files
+pub fn remove_log(path: &Path) -> Result<()> {
+    let mut index = 1;
+    while rotated_path(path, index).exists() {
+        let rotated = rotated_path(path, index);
+        fs::remove_file(&rotated)
+            .with_context(|| format!("Failed to remove log: {}", rotated.display()))?;
+        index += 1;
+    }
+    if path.exists() {
+        fs::remove_file(path)
+            .with_context(|| format!("Failed to remove log: {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Read every entry of a log including its rotated files, oldest first
+pub fn read_entries(path: &Path) -> Result<Vec<LogEntry>> {
+    let mut files = Vec::new();
+    let mut index = 1;
+    while rotated_path(path, index).exists() {
+        files.push(rotated_path(path, index));
+        index += 1;
+    }
+    files.reverse();
+    if path.exists() {
+        files.push(path.to_path_buf());
+    }
+
+    let mut entries = Vec::new();
+    for file in files {
+        let content = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read log: {}", file.display()))?;
+        entries.extend(content.lines().filter_map(LogEntry::parse));
+    }
+    Ok(entries)
+}
+
+/// Incremental reader of an agent log, like `tail -f`.
+///
+/// Follows the active file across rotations: when the path points to a new
+/// file, the rest of the previous one is read from `<log>.1` first.
+pub struct LogTail {
+    path: PathBuf,
+    inode: Option<u64>,
+    offset: u64,
+    partial: Vec<u8>,
+}
+
+impl LogTail {
+    /// Tail a log starting at its current end
+    pub fn from_end(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        Self {
+            path: path.to_path_buf(),
+            inode: metadata.as_ref().map(|metadata| metadata.ino()),
+            offset: metadata.map_or(0, |metadata| metadata.len()),
+            partial: Vec::new(),
+        }
+    }
+
+    /// Entries appended since the last poll
+    pub fn poll(&mut self) -> Result<Vec<LogEntry>> {
+        let metadata = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        match self.inode {
+            Some(inode) if inode != metadata.ino() => {
+                let rotated = rotated_path(&self.path, 1);
+                if fs::metadata(&rotated).is_ok_and(|old| old.ino() == inode) {
+                    self.read_from(&rotated, &mut entries)?;
+                }
+                self.offset = 0;
+                self.partial.clear();
+            }
+            // Truncated in place
+            _ if metadata.len() < self.offset => {
+                self.offset = 0;
+                self.partial.clear();
+            }
+            _ => {}
+        }
+        self.inode = Some(metadata.ino());
+
+        let path = self.path.clone();
+        self.read_from(&path, &mut entries)?;
+        Ok(entries)
+    }
+
+    fn read_from(&mut self, path: &Path, entries: &mut Vec<LogEntry>) -> Result<()> {
+        let mut file =
+            File::open(path).with_context(|| format!("Failed to open log: {}", path.display()))?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let read = file.read_to_end(&mut self.partial)?;
+        self.offset += read as u64;
+
+        // Keep an unterminated last line until the rest of it is written
+        let Some(end) = self.partial.iter().rposition(|byte| *byte == b'\n') else {
+            return Ok(());
+        };
+        let complete: Vec<u8> = self.partial.drain(..=end).collect();
+        entries.extend(
+            String::from_utf8_lossy(&complete)
+                .lines()
+                .filter_map(LogEntry::parse),
+        );
+        Ok(())
+    }
+}
+
+/// Criteria for selecting log entries
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub pattern: Option<Regex>,
+    pub stream: Option<String>,
+}
+
+impl LogFilter {
+    /// Whether an entry passes every configured criterion
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.since.is_none_or(|since| entry.timestamp >= since)
+            && self
+                .stream
+                .as_deref()
+                .is_none_or(|stream| entry.stream == stream)
+            && self
+                .pattern
+                .as_ref()
+                .is_none_or(|pattern| pattern.is_match(&entry.text))
+    }
+}
+
+/// Parse `--since`: an RFC 3339 timestamp, a date (`2024-05-01`) or a
+/// relative duration such as `30s`, `10m`, `2h` or `1d`
+pub fn parse_since(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    let split = value.len() - value.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid --since value: {}", value))?;
+    let duration = match unit {
+        "s" => Duration::seconds(amount),
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        _ => anyhow::bail!(
+            "Invalid --since value: {} (use e.g. 30s, 10m, 2h, 1d or a timestamp)",
+            value
+        ),
+    };
+    Ok(now - duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_write_rotate_and_read_back() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let path = agent_log_path(temp_dir.path(), "abcd1234", "claude-1");
+        let rotation = LogRotation {
+            max_bytes: 200,
+            max_files: 2,
+        };
+
+        let mut log = AgentLog::open(&path, rotation).expect("Should open log");
+        for i in 0..20 {
+            let stream = if i % 2 == 0 { "stdout" } else { "stderr" };
+            log.write(stream, &format!("line {}", i))
+                .expect("Should write");
+        }
+
+        assert!(rotated_path(&path, 1).exists());
+        assert!(rotated_path(&path, 2).exists());
+        assert!(!rotated_path(&path, 3).exists());
+
+        let entries = read_entries(&path).expect("Should read log");
+        assert!(entries.len() < 20, "oldest rotated lines are dropped");
+        assert_eq!(entries.last().unwrap().text, "line 19");
+        assert!(entries
+            .windows(2)
+            .all(|pair| pair[0].timestamp <= pair[1].timestamp));
+
+        let filter = LogFilter {
+            stream: Some("stderr".to_string()),
+            pattern: Some(Regex::new("1[0-9]").unwrap()),
+            ..Default::default()
+        };
+        let texts: Vec<_> = entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .map(|entry| entry.text.as_str())
+            .collect();
+        assert_eq!(texts.last(), Some(&"line 19"));
+        assert!(texts
+            .iter()
+            .all(|text| !text.ends_with(['0', '2', '4', '6', '8'])));
+    }
+
+    #[test]
+    fn test_tail_follows_rotation() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let path = agent_log_path(temp_dir.path(), "abcd1234", "claude-1");
+        let mut tail = LogTail::from_end(&path);
+        assert!(tail.poll().expect("Missing log is fine").is_empty());
+
+        let mut log = AgentLog::open(
+            &path,
+            LogRotation {
+                max_bytes: 300,
+                max_files: 1,
+            },
+        )
+        .expect("Should open log");
+        let mut seen = Vec::new();
+        for i in 0..12 {
+            log.write("stdout", &format!("line {}", i))
+                .expect("Should write");
+            if i % 3 == 0 {
+                seen.extend(tail.poll().expect("Should poll"));
+            }
+        }
+        seen.extend(tail.poll().expect("Should poll"));
+
+        assert!(rotated_path(&path, 1).exists());
+        let texts: Vec<_> = seen.iter().map(|entry| entry.text.clone()).collect();
+        let expected: Vec<_> = (0..12).map(|i| format!("line {}", i)).collect();
+        assert_eq!(texts, expected);
+    }
+
+    #[test]
+    fn test_parse_entry_and_since() {
+        let entry = LogEntry::parse("2024-05-01T10:00:00.000Z [stderr] oops [x]").unwrap();
+        assert_eq!(entry.stream, "stderr");
+        assert_eq!(entry.text, "oops [x]");
+        assert_eq!(
+            entry.to_string(),
+            "2024-05-01T10:00:00.000Z [stderr] oops [x]"
+        );
+        assert!(LogEntry::parse("not a log line").is_none());
+
+        let now = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(parse_since("2h", now).unwrap(), now - Duration::hours(2));
+        assert_eq!(
+            parse_since("2024-05-01", now).unwrap().to_rfc3339(),
+            "2024-05-01T00:00:00+00:00"
+        );
+        assert!(parse_since("soon", now).is_err());
+        assert!(parse_since("5w", now).is_err());
+    }
+}
@@ -0,0 +1,12 @@
+//! Agent process supervision
+
+pub mod communication;
+mod daemon;
+pub mod exec;
+pub mod logs;
+pub mod signals;
+mod supervisor;
+
+pub use communication::DaemonClient;
+pub use daemon::Daemon;
+pub use supervisor::{Supervisor, SupervisorEvent, DEFAULT_TERMINATE_GRACE};
@@ -0,0 +1,158 @@
+use anyhow::{Context, Result};
+use nix::errno::Errno;
+use nix::sys::signal::{kill, killpg, Signal};
+use nix::unistd::Pid;
+use std::time::Duration;
+
+/// Interval used while waiting for a process to exit
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether a process with this pid is still alive
+pub fn is_alive(pid: u32) -> bool {
+    match kill(Pid::from_raw(pid as i32), None) {
+        Ok(()) => true,
+        // The process exists but belongs to someone else
+        Err(Errno::EPERM) => true,
+        Err(_) => false,
+    }
+}
+
+/// Whether any member of the process group `pgid` is alive, even after its
+/// leader has exited
+pub fn group_alive(pgid: u32) -> bool {
+    match killpg(Pid::from_raw(pgid as i32), None) {
+        Ok(()) => true,
+        Err(Errno::EPERM) => true,
+        Err(_) => false,
+    }
+}
+
+/// Fields of `/proc/<pid>/stat` that follow the command name
+fn stat_fields(pid: u32) -> Option<Vec<String>> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name is parenthesized and may itself contain spaces or ')'
+    let (_, rest) = stat.rsplit_once(')')?;
+    Some(rest.split_whitespace().map(str::to_string).collect())
+}
+
+/// Start time of a live process in clock ticks since boot
+pub fn start_time(pid: u32) -> Option<u64> {
+    stat_fields(pid)?.get(19)?.parse().ok()
+}
+
+/// Whether the process group led by `pid` is still the one whose leader
+/// started at `start_time`, so signalling it cannot hit a process that
+/// reused the pid. Once the leader is gone its pid cannot be reused while
+/// the group has members, so a live group is still the original one.
+pub fn is_same_group(pid: u32, start_time: Option<u64>) -> bool {
+    let Some(fields) = stat_fields(pid) else {
+        return group_alive(pid);
+    };
+    let field = |index: usize| {
+        fields
+            .get(index)
+            .and_then(|field| field.parse::<u64>().ok())
+    };
+    field(2) == Some(pid as u64) && start_time.is_none_or(|started| field(19) == Some(started))
+}
+
+/// Send a signal to the process group led by `pid`
+pub fn signal_group(pid: u32, signal: Signal) -> Result<()> {
+    killpg(Pid::from_raw(pid as i32), signal)
+        .with_context(|| format!("Failed to send {} to process group {}", signal, pid))
+}
+
+/// Suspend an agent process group (SIGSTOP)
+pub fn pause(pid: u32) -> Result<()> {
+    signal_group(pid, Signal::SIGSTOP)
+}
+
+/// Continue a suspended agent process group (SIGCONT)
+pub fn resume(pid: u32) -> Result<()> {
+    signal_group(pid, Signal::SIGCONT)
+}
+
+/// Ask an agent process group to stop with SIGTERM and SIGKILL it once
+/// `grace` has elapsed. The whole group is waited for, even when its leader
+/// already exited. Returns `true` if the group had to be killed.
+pub async fn terminate(pid: u32, grace: Duration) -> Result<bool> {
+    if !group_alive(pid) {
+        return Ok(false);
+    }
+
+    signal_group(pid, Signal::SIGTERM)?;
+    // A stopped process cannot handle SIGTERM until it is continued
+    let _ = signal_group(pid, Signal::SIGCONT);
+
+    let deadline = tokio::time::Instant::now() + grace;
+    while tokio::time::Instant::now() < deadline {
+        if !group_alive(pid) {
+            return Ok(false);
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    if group_alive(pid) {
+        signal_group(pid, Signal::SIGKILL)?;
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[tokio::test]
+    async fn test_pause_resume_terminate_by_pid() {
+        use std::os::unix::process::CommandExt;
+
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .process_group(0)
+            .spawn()
+            .expect("Should spawn sleep");
+        let pid = child.id();
+        assert!(is_alive(pid));
+
+        pause(pid).expect("Should SIGSTOP");
+        resume(pid).expect("Should SIGCONT");
+
+        // Reap the child in the background so it does not linger as a zombie
+        let reaper = std::thread::spawn(move || child.wait());
+        let killed = terminate(pid, Duration::from_secs(2))
+            .await
+            .expect("Should terminate");
+        assert!(!killed);
+        reaper.join().unwrap().expect("Should reap child");
+        assert!(!is_alive(pid));
+    }
+
+    #[tokio::test]
+    async fn test_group_outlives_its_leader() {
+        use std::os::unix::process::CommandExt;
+
+        let mut leader = Command::new("sh")
+            .arg("-c")
+            .arg("sleep 30 &")
+            .stdout(std::process::Stdio::null())
+            .process_group(0)
+            .spawn()
+            .expect("Should spawn sh");
+        let pid = leader.id();
+        let started = start_time(pid).expect("Should read the start time");
+        assert!(is_same_group(pid, Some(started)));
+        assert!(!is_same_group(pid, Some(started + 1)));
+
+        leader.wait().expect("Should reap sh");
+        assert!(!is_alive(pid));
+        assert!(group_alive(pid));
+        assert!(is_same_group(pid, Some(started)));
+
+        terminate(pid, Duration::from_secs(2))
+            .await
+            .expect("Should terminate");
+        assert!(!group_alive(pid));
+    }
+}
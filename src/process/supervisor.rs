@@ -0,0 +1,1226 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex};
+use tokio::task::JoinHandle;
+
+use super::communication::{parse_question, QUESTION_MARKER};
+use super::exec::stop_agent_commands;
+use super::logs::{agent_log_path, AgentLog, LogRotation, EVENT_STREAM};
+use crate::agents::{AgentEvent, AgentSession, ProviderRegistry, SpawnContext};
+use crate::config::{AgentProviderConfig, CheckpointPolicies, CheckpointPolicy, PortConfig};
+use crate::database::{AgentRecord, AgentStatus, Database, InteractionType};
+use crate::git::diff::short_id;
+use crate::git::GitUtils;
+use crate::worktree::checkpoint;
+
+/// How long a dismissed agent gets to shut down before it is killed
+pub const DEFAULT_TERMINATE_GRACE: Duration = Duration::from_secs(10);
+
+/// Minimum interval between two `last_activity` updates of one agent
+const ACTIVITY_THROTTLE: Duration = Duration::from_secs(1);
+
+/// How often time and file-count checkpoint policies are evaluated
+const CHECKPOINT_POLL: Duration = Duration::from_secs(15);
+
+/// How long an agent's tools stay suspended waiting for it to react to an
+/// urgent message
+const URGENT_ACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An event from one of the supervised agents
+#[derive(Debug, Clone)]
+pub struct SupervisorEvent {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub event: AgentEvent,
+}
+
+/// Request sent to an agent's task
+enum AgentCommand {
+    Send(String, oneshot::Sender<Result<()>>),
+    SendUrgent(String, oneshot::Sender<Result<()>>),
+    Pause(oneshot::Sender<Result<()>>),
+    Resume(oneshot::Sender<Result<()>>),
+    Terminate(Duration, oneshot::Sender<Result<Option<i32>>>),
+}
+
+/// Handle to an agent task owned by the supervisor
+struct AgentHandle {
+    name: String,
+    pid: Option<u32>,
+    commands: mpsc::Sender<AgentCommand>,
+    task: JoinHandle<()>,
+}
+
+/// Launches agent sessions and drives their status in the database.
+///
+/// Every agent runs in its own task which owns the [`AgentSession`], reacts to
+/// commands (pause, resume, send, terminate) and moves `agents.status` through
+/// initializing → running ⇄ waiting/paused → completed/failed/dismissed.
+pub struct Supervisor {
+    db: Database,
+    registry: ProviderRegistry,
+    logs_dir: Option<PathBuf>,
+    agents: Mutex<HashMap<String, AgentHandle>>,
+    events: broadcast::Sender<SupervisorEvent>,
+    active: watch::Sender<usize>,
+    checkpoint_policies: std::sync::RwLock<CheckpointPolicies>,
+    ports: std::sync::RwLock<PortConfig>,
+    providers: std::sync::RwLock<BTreeMap<String, AgentProviderConfig>>,
+}
+
+impl Supervisor {
+    /// Create a supervisor recording state in `db`
+    pub fn new(db: Database, registry: ProviderRegistry) -> Arc<Self> {
+        Self::build(db, registry, None)
+    }
+
+    /// Create a supervisor that also writes every agent's output to
+    /// `<logs_dir>/<session>/<agent>.log`
+    pub fn with_logs(db: Database, registry: ProviderRegistry, logs_dir: PathBuf) -> Arc<Self> {
+        Self::build(db, registry, Some(logs_dir))
+    }
+
+    fn build(db: Database, registry: ProviderRegistry, logs_dir: Option<PathBuf>) -> Arc<Self> {
+        let (events, _) = broadcast::channel(1024);
+        let (active, _) = watch::channel(0);
+        Arc::new(Self {
+            db,
+            registry,
+            logs_dir,
+            agents: Mutex::new(HashMap::new()),
+            events,
+            active,
+            checkpoint_policies: std::sync::RwLock::new(CheckpointPolicies::default()),
+            ports: std::sync::RwLock::new(PortConfig::default()),
+            providers: std::sync::RwLock::new(BTreeMap::new()),
+        })
+    }
+
+    /// Set the automatic checkpoint policies applied to agents launched from now on
+    pub fn set_checkpoint_policies(&self, policies: CheckpointPolicies) {
+        *self
+            .checkpoint_policies
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = policies;
+    }
+
+    /// Set the port blocks handed to agents launched from now on
+    pub fn set_port_config(&self, ports: PortConfig) {
+        *self.ports.write().unwrap_or_else(|e| e.into_inner()) = ports;
+    }
+
+    /// Set the `[agents.<name>]` settings applied to agents launched from now on
+    pub fn set_provider_configs(&self, providers: BTreeMap<String, AgentProviderConfig>) {
+        *self.providers.write().unwrap_or_else(|e| e.into_inner()) = providers;
+    }
+
+    fn provider_config(&self, provider: &str) -> AgentProviderConfig {
+        self.providers
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(provider)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn checkpoint_policy(&self, provider: &str) -> CheckpointPolicy {
+        self.checkpoint_policies
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(provider)
+    }
+
+    /// Receive output and lifecycle events of every agent
+    pub fn subscribe(&self) -> broadcast::Receiver<SupervisorEvent> {
+        self.events.subscribe()
+    }
+
+    /// Start an agent in its worktree with the given prompt
+    pub async fn launch(&self, agent: &AgentRecord, prompt: &str) -> Result<()> {
+        let name = agent.name();
+        let mut agents = self.agents.lock().await;
+        if let Some(handle) = agents.get(&agent.id) {
+            if !handle.task.is_finished() {
+                anyhow::bail!("{} is already running", name);
+            }
+        }
+
+        self.db
+            .update_agent_status(&agent.id, AgentStatus::Initializing)
+            .await?;
+
+        let settings = self.provider_config(&agent.agent_type);
+        let session = match self.spawn_session(agent, prompt, &settings).await {
+            Ok(session) => session,
+            Err(e) => {
+                self.db
+                    .update_agent_status(&agent.id, AgentStatus::Failed)
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        let pid = session.pid();
+        let mut log = self.open_log(agent);
+        if let Some(log) = log.as_mut() {
+            let started = match pid {
+                Some(pid) => format!("started (pid {})", pid),
+                None => "started".to_string(),
+            };
+            let _ = log.write(EVENT_STREAM, &started);
+        }
+        self.db.set_agent_process(&agent.id, pid).await?;
+        self.db
+            .update_agent_status(&agent.id, AgentStatus::Running)
+            .await?;
+
+        let (commands, receiver) = mpsc::channel(16);
+        let runner = AgentRunner {
+            db: self.db.clone(),
+            events: self.events.clone(),
+            active: self.active.clone(),
+            agent_id: agent.id.clone(),
+            agent_name: name.clone(),
+            session_id: agent.session_id.clone(),
+            status: AgentStatus::Running,
+            status_before_pause: AgentStatus::Running,
+            dismissed: false,
+            timeout: settings.timeout(),
+            timed_out: false,
+            ack_deadline: None,
+            last_touch: Instant::now(),
+            log,
+            auto_checkpoint: AutoCheckpoint {
+                agent: agent.clone(),
+                policy: self.checkpoint_policy(&agent.agent_type),
+                last: Instant::now(),
+            },
+        };
+        self.active.send_modify(|count| *count += 1);
+        let task = tokio::spawn(runner.run(session, receiver));
+
+        agents.insert(
+            agent.id.clone(),
+            AgentHandle {
+                name,
+                pid,
+                commands,
+                task,
+            },
+        );
+        Ok(())
+    }
+
+    /// Open the agent's log file; logging problems never stop an agent
+    fn open_log(&self, agent: &AgentRecord) -> Option<AgentLog> {
+        let logs_dir = self.logs_dir.as_ref()?;
+        let path = agent_log_path(logs_dir, agent.session_slug(), &agent.name());
+        match AgentLog::open(&path, LogRotation::default()) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("⚠️  Logging disabled for {}: {:#}", agent.name(), e);
+                None
+            }
+        }
+    }
+
+    async fn spawn_session(
+        &self,
+        agent: &AgentRecord,
+        prompt: &str,
+        settings: &AgentProviderConfig,
+    ) -> Result<Box<dyn AgentSession>> {
+        let provider = self
+            .registry
+            .get(&agent.agent_type)
+            .ok_or_else(|| anyhow::anyhow!("Unknown agent provider: {}", agent.agent_type))?;
+        let worktree = agent
+            .worktree_path
+            .as_deref()
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow::anyhow!("{} has no worktree", agent.name()))?;
+        if let Some(variable) = &settings.api_key_env {
+            if std::env::var_os(variable).is_none() {
+                anyhow::bail!(
+                    "{} is not set in the daemon's environment ([agents.{}] api_key_env); set it and restart the daemon with 'agentcrew daemon --stop'",
+                    variable,
+                    agent.agent_type
+                );
+            }
+        }
+
+        let ports = *self.ports.read().unwrap_or_else(|e| e.into_inner());
+        let ports = self
+            .db
+            .allocate_ports(&agent.session_id, &agent.id, ports.base, ports.block_size)
+            .await?;
+        let mut env = vec![
+            ("AGENTCREW_AGENT".to_string(), agent.name()),
+            ("AGENTCREW_SESSION".to_string(), agent.session_id.clone()),
+            (
+                "AGENTCREW_QUESTION_MARKER".to_string(),
+                QUESTION_MARKER.to_string(),
+            ),
+        ];
+        env.extend(ports.env());
+
+        let mut options = agent.options();
+        if let Some(model) = &settings.model {
+            options
+                .entry("model".to_string())
+                .or_insert_with(|| model.clone());
+        }
+
+        let ctx = SpawnContext {
+            agent_name: agent.name(),
+            worktree,
+            prompt: prompt.to_string(),
+            options,
+            env,
+            program: settings.command.clone(),
+            env_allowlist: settings.env_allowlist(),
+        };
+
+        let spawned = provider.spawn(ctx).await;
+        if spawned.is_err() {
+            // Nothing runs on the ports; hand them back
+            if let Err(e) = self.db.release_ports(&agent.id).await {
+                eprintln!("⚠️  Failed to release ports of {}: {:#}", agent.name(), e);
+            }
+        }
+        spawned
+    }
+
+    /// Process id of a supervised agent, if it runs as a separate process
+    pub async fn pid(&self, agent_id: &str) -> Option<u32> {
+        self.agents.lock().await.get(agent_id).and_then(|h| h.pid)
+    }
+
+    /// Whether an agent's task is still running
+    pub async fn is_running(&self, agent_id: &str) -> bool {
+        self.agents
+            .lock()
+            .await
+            .get(agent_id)
+            .is_some_and(|handle| !handle.task.is_finished())
+    }
+
+    /// Ids and names of agents whose task is still running
+    pub async fn running(&self) -> Vec<(String, String)> {
+        self.agents
+            .lock()
+            .await
+            .iter()
+            .filter(|(_, handle)| !handle.task.is_finished())
+            .map(|(id, handle)| (id.clone(), handle.name.clone()))
+            .collect()
+    }
+
+    /// Deliver a message to an agent
+    pub async fn send(&self, agent_id: &str, message: &str) -> Result<()> {
+        let message = message.to_string();
+        self.request(agent_id, |reply| AgentCommand::Send(message, reply))
+            .await?
+    }
+
+    /// Deliver a message ahead of the agent's current turn. Agents that
+    /// cannot be interrupted are paused until the message is written.
+    pub async fn send_urgent(&self, agent_id: &str, message: &str) -> Result<()> {
+        let message = message.to_string();
+        self.request(agent_id, |reply| AgentCommand::SendUrgent(message, reply))
+            .await?
+    }
+
+    /// Suspend an agent
+    pub async fn pause(&self, agent_id: &str) -> Result<()> {
+        self.request(agent_id, AgentCommand::Pause).await?
+    }
+
+    /// Continue a paused agent
+    pub async fn resume(&self, agent_id: &str) -> Result<()> {
+        self.request(agent_id, AgentCommand::Resume).await?
+    }
+
+    /// Stop an agent (SIGTERM, then SIGKILL after `grace`) and wait for its task to finish
+    pub async fn dismiss(&self, agent_id: &str, grace: Duration) -> Result<Option<i32>> {
+        let code = self
+            .request(agent_id, |reply| AgentCommand::Terminate(grace, reply))
+            .await??;
+
+        if let Some(handle) = self.agents.lock().await.remove(agent_id) {
+            let _ = handle.task.await;
+        }
+        Ok(code)
+    }
+
+    /// Dismiss every running agent
+    pub async fn dismiss_all(&self, grace: Duration) {
+        for (agent_id, name) in self.running().await {
+            if let Err(e) = self.dismiss(&agent_id, grace).await {
+                eprintln!("⚠️  Failed to dismiss {}: {:#}", name, e);
+            }
+        }
+    }
+
+    /// Stop an agent if it is running and launch it again
+    pub async fn restart(&self, agent: &AgentRecord, prompt: &str, grace: Duration) -> Result<()> {
+        if self.is_running(&agent.id).await {
+            self.dismiss(&agent.id, grace).await?;
+        }
+        self.launch(agent, prompt).await
+    }
+
+    /// Wait until every launched agent has exited
+    pub async fn wait_all(&self) {
+        let mut active = self.active.subscribe();
+        let _ = active.wait_for(|count| *count == 0).await;
+    }
+
+    async fn request<T>(
+        &self,
+        agent_id: &str,
+        command: impl FnOnce(oneshot::Sender<T>) -> AgentCommand,
+    ) -> Result<T> {
+        let sender = self
+            .agents
+            .lock()
+            .await
+            .get(agent_id)
+            .map(|handle| handle.commands.clone())
+            .ok_or_else(|| anyhow::anyhow!("Agent {} is not running", agent_id))?;
+
+        let (reply, response) = oneshot::channel();
+        sender
+            .send(command(reply))
+            .await
+            .map_err(|_| anyhow::anyhow!("Agent {} has already exited", agent_id))?;
+        response
+            .await
+            .with_context(|| format!("Agent {} stopped before replying", agent_id))
+    }
+}
+
+/// State of one agent task
+struct AgentRunner {
+    db: Database,
+    events: broadcast::Sender<SupervisorEvent>,
+    active: watch::Sender<usize>,
+    agent_id: String,
+    agent_name: String,
+    session_id: String,
+    status: AgentStatus,
+    status_before_pause: AgentStatus,
+    dismissed: bool,
+    /// Time the agent may run before it is stopped
+    timeout: Option<Duration>,
+    timed_out: bool,
+    /// Set while the agent is paused until it reacts to an urgent message
+    ack_deadline: Option<tokio::time::Instant>,
+    last_touch: Instant,
+    log: Option<AgentLog>,
+    auto_checkpoint: AutoCheckpoint,
+}
+
+/// Automatic checkpoint state of one agent
+struct AutoCheckpoint {
+    agent: AgentRecord,
+    policy: CheckpointPolicy,
+    last: Instant,
+}
+
+/// Why a time or file-count checkpoint is due, if it is
+fn checkpoint_reason(
+    policy: &CheckpointPolicy,
+    since_last: Duration,
+    changed_files: usize,
+) -> Option<String> {
+    if let Some(minutes) = policy.every_minutes {
+        if since_last >= Duration::from_secs(minutes * 60) {
+            return Some(format!(
+                "{} minute{}",
+                minutes,
+                if minutes == 1 { "" } else { "s" }
+            ));
+        }
+    }
+    match policy.every_files {
+        Some(files) if changed_files > 0 && changed_files >= files => {
+            Some(format!("{} changed files", changed_files))
+        }
+        _ => None,
+    }
+}
+
+impl AgentRunner {
+    async fn run(
+        mut self,
+        mut session: Box<dyn AgentSession>,
+        mut commands: mpsc::Receiver<AgentCommand>,
+    ) {
+        let policy = self.auto_checkpoint.policy;
+        let periodic = policy.every_minutes.is_some() || policy.every_files.is_some();
+        let mut poll = tokio::time::interval_at(
+            tokio::time::Instant::now() + CHECKPOINT_POLL,
+            CHECKPOINT_POLL,
+        );
+        let timeout = self.timeout;
+        let expired = async {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(expired);
+        // A closed channel is always ready; stop selecting on it once it is
+        let mut commands_open = true;
+        loop {
+            tokio::select! {
+                _ = poll.tick(), if periodic => self.poll_checkpoint().await,
+                _ = &mut expired, if !self.timed_out => self.time_out(session.as_mut()).await,
+                _ = tokio::time::sleep_until(
+                    self.ack_deadline.unwrap_or_else(tokio::time::Instant::now)
+                ), if self.ack_deadline.is_some() => {
+                    self.end_urgent_pause(session.as_mut(), "no reply to the urgent message, resuming")
+                        .await
+                }
+                command = commands.recv(), if commands_open => match command {
+                    Some(command) => self.handle_command(session.as_mut(), command).await,
+                    None => {
+                        // Supervisor went away; do not leave the agent behind.
+                        // The final exit is still drained below and reported.
+                        commands_open = false;
+                        self.dismissed = true;
+                        let _ = session.terminate(DEFAULT_TERMINATE_GRACE).await;
+                    }
+                },
+                event = session.poll_output() => match event {
+                    Ok(Some(event)) => {
+                        // Any output is the agent's reply to an urgent message
+                        if self.ack_deadline.is_some() && !matches!(event, AgentEvent::Exited { .. }) {
+                            self.end_urgent_pause(session.as_mut(), "urgent message acknowledged")
+                                .await;
+                        }
+                        self.handle_event(event).await
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        eprintln!("⚠️  {} failed: {:#}", self.agent_name, e);
+                        let _ = session.terminate(DEFAULT_TERMINATE_GRACE).await;
+                        self.set_status(AgentStatus::Failed).await;
+                        break;
+                    }
+                },
+            }
+        }
+
+        if let Err(e) = self.db.set_agent_process(&self.agent_id, None).await {
+            eprintln!(
+                "⚠️  Failed to clear process of {}: {:#}",
+                self.agent_name, e
+            );
+        }
+        self.active
+            .send_modify(|count| *count = count.saturating_sub(1));
+    }
+
+    /// Stop an agent that ran out of time; it ends up failed
+    async fn time_out(&mut self, session: &mut dyn AgentSession) {
+        self.timed_out = true;
+        let minutes = self.timeout.map_or(0, |timeout| timeout.as_secs() / 60);
+        self.log(
+            EVENT_STREAM,
+            &format!(
+                "timed out after {} minute{}, stopping",
+                minutes,
+                if minutes == 1 { "" } else { "s" }
+            ),
+        );
+        if let Err(e) = session.terminate(DEFAULT_TERMINATE_GRACE).await {
+            eprintln!("⚠️  Failed to stop {}: {:#}", self.agent_name, e);
+        }
+    }
+
+    /// Interrupt the agent before writing an urgent message. Agents that
+    /// cannot be interrupted are paused and only the agent process itself is
+    /// woken to read the message; the tools it started stay suspended until
+    /// it replies. An agent that is already paused stays paused.
+    async fn send_urgent(&mut self, session: &mut dyn AgentSession, message: &str) -> Result<()> {
+        if session.can_interrupt() {
+            session.interrupt().await?;
+            self.log(EVENT_STREAM, "interrupted");
+            return self.send(session, message).await;
+        }
+        if self.status == AgentStatus::Paused {
+            return self.send(session, message).await;
+        }
+
+        session.pause().await?;
+        self.status_before_pause = self.status;
+        self.set_status(AgentStatus::Paused).await;
+        let woken = match self.send(session, message).await {
+            Ok(()) => session.wake().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = woken {
+            self.end_urgent_pause(session, "urgent message not delivered, resuming")
+                .await;
+            return Err(e);
+        }
+        self.ack_deadline = Some(tokio::time::Instant::now() + URGENT_ACK_TIMEOUT);
+        self.log(
+            EVENT_STREAM,
+            "paused until the urgent message is acknowledged",
+        );
+        Ok(())
+    }
+
+    /// Continue an agent paused for an urgent message. A failed resume is
+    /// reported but never leaves the agent marked paused.
+    async fn end_urgent_pause(&mut self, session: &mut dyn AgentSession, reason: &str) {
+        self.ack_deadline = None;
+        if let Err(e) = session.resume().await {
+            eprintln!("⚠️  Failed to resume {}: {:#}", self.agent_name, e);
+        }
+        if self.status == AgentStatus::Paused {
+            self.set_status(self.status_before_pause).await;
+        }
+        self.log(EVENT_STREAM, reason);
+    }
+
+    /// Write a message to the agent; a waiting agent is running again
+    async fn send(&mut self, session: &mut dyn AgentSession, message: &str) -> Result<()> {
+        session.send_message(message).await?;
+        self.log(EVENT_STREAM, &format!("received: {}", message));
+        if self.status == AgentStatus::Waiting {
+            self.set_status(AgentStatus::Running).await;
+        } else if self.status_before_pause == AgentStatus::Waiting {
+            self.status_before_pause = AgentStatus::Running;
+        }
+        Ok(())
+    }
+
+    async fn handle_command(&mut self, session: &mut dyn AgentSession, command: AgentCommand) {
+        match command {
+            AgentCommand::Send(message, reply) => {
+                let result = self.send(session, &message).await;
+                let _ = reply.send(result);
+            }
+            AgentCommand::SendUrgent(message, reply) => {
+                let result = self.send_urgent(session, &message).await;
+                let _ = reply.send(result);
+            }
+            AgentCommand::Pause(reply) => {
+                // The agent now stays paused until it is resumed
+                self.ack_deadline = None;
+                let result = session.pause().await;
+                if result.is_ok() && self.status != AgentStatus::Paused {
+                    self.status_before_pause = self.status;
+                    self.set_status(AgentStatus::Paused).await;
+                }
+                let _ = reply.send(result);
+            }
+            AgentCommand::Resume(reply) => {
+                self.ack_deadline = None;
+                let result = session.resume().await;
+                if result.is_ok() && self.status == AgentStatus::Paused {
+                    self.set_status(self.status_before_pause).await;
+                }
+                let _ = reply.send(result);
+            }
+            AgentCommand::Terminate(grace, reply) => {
+                self.dismissed = true;
+                let _ = reply.send(session.terminate(grace).await);
+            }
+        }
+    }
+
+    async fn handle_event(&mut self, event: AgentEvent) {
+        // Output lines carrying the question marker are questions
+        let event = match event {
+            AgentEvent::Output { stream, line } => match parse_question(&line) {
+                Some(text) => AgentEvent::Question {
+                    text: text.to_string(),
+                },
+                None => AgentEvent::Output { stream, line },
+            },
+            event => event,
+        };
+
+        match &event {
+            AgentEvent::Output { stream, line } => self.log(stream.as_str(), line),
+            AgentEvent::Question { text } => self.log(EVENT_STREAM, &format!("question: {}", text)),
+            AgentEvent::Exited { code: Some(code) } => {
+                self.log(EVENT_STREAM, &format!("exited with code {}", code))
+            }
+            AgentEvent::Exited { code: None } => self.log(EVENT_STREAM, "terminated"),
+        }
+
+        match &event {
+            AgentEvent::Output { .. } => {
+                if self.last_touch.elapsed() >= ACTIVITY_THROTTLE {
+                    self.last_touch = Instant::now();
+                    let _ = self.db.touch_agent(&self.agent_id).await;
+                }
+            }
+            AgentEvent::Question { text } => {
+                if self.auto_checkpoint.policy.on_question {
+                    self.auto_checkpoint(&format!("Auto-checkpoint before question: {}", text))
+                        .await;
+                }
+                if let Err(e) = self
+                    .db
+                    .record_interaction(
+                        &self.agent_id,
+                        &self.session_id,
+                        InteractionType::Question,
+                        text,
+                        None,
+                    )
+                    .await
+                {
+                    eprintln!(
+                        "⚠️  Failed to record question of {}: {:#}",
+                        self.agent_name, e
+                    );
+                }
+                self.set_status(AgentStatus::Waiting).await
+            }
+            AgentEvent::Exited { code } => {
+                let _ = self.db.expire_questions(&self.agent_id).await;
+                let status = if self.dismissed {
+                    // Commands detached into the worktree would keep the ports
+                    if let Err(e) =
+                        stop_agent_commands(&self.db, &self.agent_id, DEFAULT_TERMINATE_GRACE).await
+                    {
+                        eprintln!(
+                            "⚠️  Failed to stop detached commands of {}: {:#}",
+                            self.agent_name, e
+                        );
+                    }
+                    if let Err(e) = self.db.release_ports(&self.agent_id).await {
+                        eprintln!(
+                            "⚠️  Failed to release ports of {}: {:#}",
+                            self.agent_name, e
+                        );
+                    }
+                    AgentStatus::Dismissed
+                } else if self.timed_out {
+                    AgentStatus::Failed
+                } else if *code == Some(0) {
+                    AgentStatus::Completed
+                } else {
+                    AgentStatus::Failed
+                };
+                self.set_status(status).await;
+            }
+        }
+
+        let _ = self.events.send(SupervisorEvent {
+            agent_id: self.agent_id.clone(),
+            agent_name: self.agent_name.clone(),
+            event,
+        });
+    }
+
+    /// Checkpoint if the agent's time or file-count policy says so
+    async fn poll_checkpoint(&mut self) {
+        if self.status == AgentStatus::Paused {
+            return;
+        }
+        let policy = self.auto_checkpoint.policy;
+        let changed_files = match (
+            policy.every_files,
+            &self.auto_checkpoint.agent.worktree_path,
+        ) {
+            (Some(_), Some(worktree)) => {
+                GitUtils::changed_files(std::path::Path::new(worktree)).unwrap_or(0)
+            }
+            _ => 0,
+        };
+        let since_last = self.auto_checkpoint.last.elapsed();
+        if let Some(reason) = checkpoint_reason(&policy, since_last, changed_files) {
+            self.auto_checkpoint(&format!("Auto-checkpoint after {}", reason))
+                .await;
+        }
+    }
+
+    /// Commit the agent's worktree; failures are logged, never fatal
+    async fn auto_checkpoint(&mut self, message: &str) {
+        self.auto_checkpoint.last = Instant::now();
+        match checkpoint(&self.db, &self.auto_checkpoint.agent, message).await {
+            Ok(Some(checkpoint)) => {
+                self.log(
+                    EVENT_STREAM,
+                    &format!("checkpoint {}: {}", short_id(checkpoint.commit), message),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => {
+                self.log(EVENT_STREAM, &format!("checkpoint failed: {:#}", e));
+                eprintln!("⚠️  Failed to checkpoint {}: {:#}", self.agent_name, e);
+            }
+        }
+    }
+
+    fn log(&mut self, stream: &str, text: &str) {
+        if let Some(log) = self.log.as_mut() {
+            if let Err(e) = log.write(stream, text) {
+                eprintln!("⚠️  Logging disabled for {}: {:#}", self.agent_name, e);
+                self.log = None;
+            }
+        }
+    }
+
+    async fn set_status(&mut self, status: AgentStatus) {
+        self.status = status;
+        if let Err(e) = self.db.update_agent_status(&self.agent_id, status).await {
+            eprintln!(
+                "⚠️  Failed to update status of {}: {:#}",
+                self.agent_name, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    async fn setup(options: &[(&str, &str)]) -> (TempDir, Database, AgentRecord) {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("build it", r#"{"mock":1}"#, "main", "abc123")
+            .await
+            .expect("Should create session");
+        let options: BTreeMap<_, _> = options
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let agent = db
+            .create_agent(&session.id, "mock", 1, &options)
+            .await
+            .expect("Should create agent");
+        db.set_agent_worktree(&agent.id, temp_dir.path(), "agentcrew/x/mock-1")
+            .await
+            .expect("Should set worktree");
+        let agent = db.get_agent(&agent.id).await.expect("Should reload agent");
+        (temp_dir, db, agent)
+    }
+
+    async fn wait_for_status(db: &Database, agent_id: &str, status: AgentStatus) {
+        for _ in 0..100 {
+            if db.get_agent(agent_id).await.unwrap().status == status {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Agent never reached {}", status);
+    }
+
+    #[tokio::test]
+    async fn test_status_state_machine() {
+        let (_dir, db, agent) = setup(&[("delay_ms", "0")]).await;
+        let supervisor = Supervisor::new(db.clone(), ProviderRegistry::builtin());
+
+        supervisor
+            .launch(&agent, "build it")
+            .await
+            .expect("Should launch");
+        wait_for_status(&db, &agent.id, AgentStatus::Waiting).await;
+
+        supervisor.pause(&agent.id).await.expect("Should pause");
+        wait_for_status(&db, &agent.id, AgentStatus::Paused).await;
+        supervisor.resume(&agent.id).await.expect("Should resume");
+        wait_for_status(&db, &agent.id, AgentStatus::Waiting).await;
+
+        supervisor
+            .send(&agent.id, "y")
+            .await
+            .expect("Should answer");
+        supervisor.wait_all().await;
+        wait_for_status(&db, &agent.id, AgentStatus::Completed).await;
+        assert_eq!(db.get_agent(&agent.id).await.unwrap().process_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_agent_output_is_logged() {
+        let (dir, db, agent) = setup(&[("delay_ms", "0")]).await;
+        let logs_dir = dir.path().join("logs");
+        let supervisor =
+            Supervisor::with_logs(db.clone(), ProviderRegistry::builtin(), logs_dir.clone());
+
+        supervisor
+            .launch(&agent, "build it")
+            .await
+            .expect("Should launch");
+        wait_for_status(&db, &agent.id, AgentStatus::Waiting).await;
+        supervisor
+            .send(&agent.id, "y")
+            .await
+            .expect("Should answer");
+        supervisor.wait_all().await;
+
+        let path = agent_log_path(&logs_dir, agent.session_slug(), "mock-1");
+        let entries = crate::process::logs::read_entries(&path).expect("Should read log");
+        let events: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.stream == EVENT_STREAM)
+            .map(|entry| entry.text.as_str())
+            .collect();
+        assert_eq!(events.first(), Some(&"started"));
+        assert!(events.iter().any(|text| text.starts_with("question: ")));
+        assert!(events.contains(&"received: y"));
+        assert_eq!(events.last(), Some(&"exited with code 0"));
+        assert!(entries.iter().any(|entry| entry.stream == "stdout"));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_supervisor_dismisses_agents() {
+        let (_dir, db, agent) = setup(&[]).await;
+        let mut registry = ProviderRegistry::builtin();
+        registry.register(Arc::new(crate::agents::CommandProvider {
+            name: "mock",
+            icon: "🧪",
+            description: "sleeping shell",
+            program: "sh",
+            base_args: &["-c", "echo started; sleep 30", "sh"],
+            model_flag: None,
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        let supervisor = Supervisor::new(db.clone(), registry);
+        supervisor
+            .launch(&agent, "ignored")
+            .await
+            .expect("Should launch");
+
+        drop(supervisor);
+        wait_for_status(&db, &agent.id, AgentStatus::Dismissed).await;
+    }
+
+    #[tokio::test]
+    async fn test_urgent_message_pauses_tools_until_acknowledged() {
+        let (_dir, db, agent) = setup(&[]).await;
+        let mut registry = ProviderRegistry::builtin();
+        registry.register(Arc::new(crate::agents::CommandProvider {
+            name: "mock",
+            icon: "🧪",
+            description: "shell that dies on SIGINT",
+            program: "sh",
+            base_args: &[
+                "-c",
+                "(sleep 0.5; echo tool done) & read line; sleep 0.6; echo got $line; wait",
+                "sh",
+            ],
+            model_flag: None,
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        let supervisor = Supervisor::new(db.clone(), registry);
+        let mut events = supervisor.subscribe();
+        supervisor
+            .launch(&agent, "ignored")
+            .await
+            .expect("Should launch");
+        // Let the tool start before the agent is paused
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        supervisor
+            .send_urgent(&agent.id, "stop")
+            .await
+            .expect("Should deliver");
+        assert_eq!(
+            db.get_agent(&agent.id).await.unwrap().status,
+            AgentStatus::Paused
+        );
+        supervisor.wait_all().await;
+        wait_for_status(&db, &agent.id, AgentStatus::Completed).await;
+
+        // The tool would have finished first had it not been held
+        let mut lines = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::Output { line, .. } = event.event {
+                lines.push(line);
+            }
+        }
+        assert_eq!(lines, vec!["got stop", "tool done"]);
+    }
+
+    #[tokio::test]
+    async fn test_dismiss_and_restart() {
+        let (_dir, db, agent) = setup(&[("delay_ms", "0")]).await;
+        let supervisor = Supervisor::new(db.clone(), ProviderRegistry::builtin());
+
+        supervisor
+            .launch(&agent, "build it")
+            .await
+            .expect("Should launch");
+        assert!(supervisor.launch(&agent, "build it").await.is_err());
+        wait_for_status(&db, &agent.id, AgentStatus::Waiting).await;
+        assert!(db.agent_ports(&agent.id).await.unwrap().is_some());
+
+        supervisor
+            .dismiss(&agent.id, Duration::from_millis(100))
+            .await
+            .expect("Should dismiss");
+        assert_eq!(
+            db.get_agent(&agent.id).await.unwrap().status,
+            AgentStatus::Dismissed
+        );
+        assert!(db.agent_ports(&agent.id).await.unwrap().is_none());
+
+        supervisor
+            .restart(&agent, "build it", Duration::from_millis(100))
+            .await
+            .expect("Should restart");
+        wait_for_status(&db, &agent.id, AgentStatus::Waiting).await;
+        supervisor.dismiss_all(Duration::from_millis(100)).await;
+        supervisor.wait_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_marker_question_is_recorded_and_answered() {
+        let (_dir, db, agent) = setup(&[]).await;
+        let mut registry = ProviderRegistry::builtin();
+        registry.register(Arc::new(crate::agents::CommandProvider {
+            name: "mock",
+            icon: "🧪",
+            description: "asking shell",
+            program: "sh",
+            base_args: &[
+                "-c",
+                r#"echo "$AGENTCREW_QUESTION_MARKER Use SQLite?"; read answer; test "$answer" = yes"#,
+                "sh",
+            ],
+            model_flag: None,
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        let supervisor = Supervisor::new(db.clone(), registry);
+        supervisor
+            .launch(&agent, "ignored")
+            .await
+            .expect("Should launch");
+        wait_for_status(&db, &agent.id, AgentStatus::Waiting).await;
+
+        let questions = db.pending_questions().await.unwrap();
+        assert_eq!(questions.len(), 1);
+        assert_eq!(questions[0].content, "Use SQLite?");
+
+        supervisor
+            .send(&agent.id, "yes")
+            .await
+            .expect("Should answer");
+        supervisor.wait_all().await;
+        wait_for_status(&db, &agent.id, AgentStatus::Completed).await;
+    }
+
+    #[test]
+    fn test_checkpoint_reason() {
+        let policy = CheckpointPolicy {
+            every_minutes: Some(10),
+            every_files: Some(3),
+            on_question: false,
+        };
+        assert_eq!(checkpoint_reason(&policy, Duration::from_secs(60), 2), None);
+        assert_eq!(
+            checkpoint_reason(&policy, Duration::from_secs(600), 0).as_deref(),
+            Some("10 minutes")
+        );
+        assert_eq!(
+            checkpoint_reason(&policy, Duration::from_secs(60), 4).as_deref(),
+            Some("4 changed files")
+        );
+        assert_eq!(
+            checkpoint_reason(&CheckpointPolicy::default(), Duration::from_secs(6000), 50),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_before_question() {
+        let (dir, db, agent) = setup(&[]).await;
+        let worktree = dir.path().join("worktree");
+        let repo = git2::Repository::init(&worktree).expect("Should init repo");
+        crate::git::diff::tests::commit_files(&repo, "main", None, &[("README.md", Some("hi\n"))]);
+        repo.set_head("refs/heads/main").unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))
+            .unwrap();
+        db.set_agent_worktree(&agent.id, &worktree, "main")
+            .await
+            .unwrap();
+        let agent = db.get_agent(&agent.id).await.unwrap();
+
+        let mut registry = ProviderRegistry::builtin();
+        registry.register(Arc::new(crate::agents::CommandProvider {
+            name: "mock",
+            icon: "🧪",
+            description: "writing shell",
+            program: "sh",
+            base_args: &[
+                "-c",
+                r#"echo draft > notes.txt; echo "$AGENTCREW_QUESTION_MARKER Keep it?"; read answer"#,
+                "sh",
+            ],
+            model_flag: None,
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        let supervisor = Supervisor::new(db.clone(), registry);
+        supervisor.set_checkpoint_policies(CheckpointPolicies {
+            default: CheckpointPolicy {
+                on_question: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        supervisor
+            .launch(&agent, "ignored")
+            .await
+            .expect("Should launch");
+        wait_for_status(&db, &agent.id, AgentStatus::Waiting).await;
+
+        let checkpoints = db.list_checkpoints(&agent.id).await.unwrap();
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(
+            checkpoints[0].content,
+            "Auto-checkpoint before question: Keep it?"
+        );
+        let changes = db.list_file_changes(&agent.id).await.unwrap();
+        assert_eq!(changes[0].file_path, "notes.txt");
+
+        supervisor
+            .send(&agent.id, "yes")
+            .await
+            .expect("Should answer");
+        supervisor.wait_all().await;
+    }
+
+    #[tokio::test]
+    async fn test_provider_settings() {
+        let (_dir, db, agent) = setup(&[]).await;
+        let mut registry = ProviderRegistry::builtin();
+        registry.register(Arc::new(crate::agents::CommandProvider {
+            name: "mock",
+            icon: "🧪",
+            description: "shell picked by config",
+            program: "false",
+            base_args: &[],
+            model_flag: Some("-c"),
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        let supervisor = Supervisor::new(db.clone(), registry);
+        let settings = AgentProviderConfig {
+            command: Some("sh".to_string()),
+            model: Some(r#"echo "model for $0""#.to_string()),
+            api_key_env: Some("AGENTCREW_TEST_UNSET_API_KEY".to_string()),
+            ..Default::default()
+        };
+        supervisor.set_provider_configs(BTreeMap::from([("mock".to_string(), settings.clone())]));
+
+        let error = supervisor.launch(&agent, "task").await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("AGENTCREW_TEST_UNSET_API_KEY is not set"));
+        assert_eq!(
+            db.get_agent(&agent.id).await.unwrap().status,
+            AgentStatus::Failed
+        );
+
+        supervisor.set_provider_configs(BTreeMap::from([(
+            "mock".to_string(),
+            AgentProviderConfig {
+                api_key_env: None,
+                ..settings
+            },
+        )]));
+        let mut events = supervisor.subscribe();
+        supervisor
+            .launch(&agent, "task")
+            .await
+            .expect("Should launch");
+        supervisor.wait_all().await;
+        let mut lines = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let AgentEvent::Output { line, .. } = event.event {
+                lines.push(line);
+            }
+        }
+        assert_eq!(lines, vec!["model for task"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_spawn_releases_ports() {
+        let (_dir, db, agent) = setup(&[]).await;
+        let mut registry = ProviderRegistry::builtin();
+        registry.register(Arc::new(crate::agents::CommandProvider {
+            name: "mock",
+            icon: "🧪",
+            description: "missing program",
+            program: "/nonexistent/agentcrew-test-agent",
+            base_args: &[],
+            model_flag: None,
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        let supervisor = Supervisor::new(db.clone(), registry);
+
+        assert!(supervisor.launch(&agent, "task").await.is_err());
+        assert_eq!(
+            db.get_agent(&agent.id).await.unwrap().status,
+            AgentStatus::Failed
+        );
+        assert!(db.agent_ports(&agent.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_process_agent_records_pid() {
+        let (dir, db, agent) = setup(&[]).await;
+        let mut registry = ProviderRegistry::builtin();
+        registry.register(Arc::new(crate::agents::CommandProvider {
+            name: "mock",
+            icon: "🧪",
+            description: "sleeping shell",
+            program: "sh",
+            base_args: &["-c", "sleep 30", "sh"],
+            model_flag: None,
+            prompt_flag: None,
+            interruptible: false,
+        }));
+        let supervisor = Supervisor::new(db.clone(), registry);
+        supervisor
+            .launch(&agent, "ignored")
+            .await
+            .expect("Should launch");
+
+        let pid = supervisor.pid(&agent.id).await.expect("Should have a pid");
+        assert_eq!(
+            db.get_agent(&agent.id).await.unwrap().process_id,
+            Some(pid as i64)
+        );
+
+        supervisor.pause(&agent.id).await.expect("Should SIGSTOP");
+        assert_eq!(
+            db.get_agent(&agent.id).await.unwrap().status,
+            AgentStatus::Paused
+        );
+        supervisor
+            .dismiss(&agent.id, Duration::from_millis(200))
+            .await
+            .expect("Should dismiss");
+        assert_eq!(
+            db.get_agent(&agent.id).await.unwrap().status,
+            AgentStatus::Dismissed
+        );
+        drop(dir);
+    }
+}
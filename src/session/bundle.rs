@@ -0,0 +1,569 @@
+use anyhow::{Context, Result};
+use git2::{BranchType, Oid};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::SessionSnapshot;
+use crate::database::{
+    AgentStatus, Database, FileChangeRecord, HarvestResultRecord, InteractionRecord, SessionRecord,
+};
+use crate::git::GitUtils;
+use crate::worktree::{AgentWorktree, WorktreeManager};
+
+/// Format version of the history file inside a bundle
+pub const BUNDLE_VERSION: u32 = 1;
+
+const SESSION_FILE: &str = "session.json";
+const HISTORY_FILE: &str = "history.json";
+const BRANCHES_FILE: &str = "branches.bundle";
+const LOGS_DIR: &str = "logs";
+
+/// Where a project keeps the things a bundle is made of
+#[derive(Debug, Clone)]
+pub struct ProjectLayout {
+    pub repo_root: PathBuf,
+    pub worktrees_dir: PathBuf,
+    pub logs_dir: PathBuf,
+    pub sessions_dir: PathBuf,
+}
+
+/// Database rows of a session besides the session and agents themselves
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    version: u32,
+    interactions: Vec<InteractionRecord>,
+    file_changes: Vec<FileChangeRecord>,
+    harvest_results: Vec<HarvestResultRecord>,
+}
+
+/// What went into an exported bundle
+#[derive(Debug)]
+pub struct Exported {
+    pub agents: usize,
+    pub branches: usize,
+    pub interactions: usize,
+    pub log_files: usize,
+}
+
+/// A session unpacked from a bundle
+#[derive(Debug)]
+pub struct Imported {
+    pub session: SessionRecord,
+    /// Whether the session and agents got new ids because the originals
+    /// were already taken in this project
+    pub remapped: bool,
+    /// Name of the snapshot `agentcrew load` can relaunch the agents from
+    pub snapshot_name: String,
+    pub worktrees: Vec<AgentWorktree>,
+    pub interactions: usize,
+}
+
+/// Pack a session into a tar file: its snapshot, history rows, logs and a
+/// `git bundle` of every agent branch
+pub async fn export(
+    db: &Database,
+    layout: &ProjectLayout,
+    session: &SessionRecord,
+    output: &Path,
+) -> Result<Exported> {
+    let repo = GitUtils::find_git_repository(&layout.repo_root)?;
+    let name = session
+        .name
+        .clone()
+        .unwrap_or_else(|| session.slug().to_string());
+    let snapshot = SessionSnapshot::capture(db, &repo, session, &name).await?;
+
+    let mut history = History {
+        version: BUNDLE_VERSION,
+        harvest_results: db.list_harvest_results(&session.id).await?,
+        ..Default::default()
+    };
+    for saved in &snapshot.agents {
+        history
+            .interactions
+            .extend(db.list_interactions(&saved.agent.id).await?);
+        history
+            .file_changes
+            .extend(db.list_file_changes(&saved.agent.id).await?);
+    }
+    history
+        .interactions
+        .sort_by_key(|interaction| interaction.id);
+
+    let branches: Vec<&str> = snapshot
+        .agents
+        .iter()
+        .filter(|saved| saved.branch_tip.is_some())
+        .filter_map(|saved| saved.agent.branch_name.as_deref())
+        .collect();
+
+    let file =
+        File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut archive = tar::Builder::new(file);
+    append_json(&mut archive, SESSION_FILE, &snapshot)?;
+    append_json(&mut archive, HISTORY_FILE, &history)?;
+
+    if !branches.is_empty() {
+        let bundle = output.with_extension("bundle.tmp");
+        let mut args = vec!["bundle", "create", "--quiet"];
+        let bundle_path = bundle.to_string_lossy().into_owned();
+        args.push(&bundle_path);
+        args.extend(&branches);
+        let created = git(&layout.repo_root, &args).and_then(|_| {
+            archive
+                .append_path_with_name(&bundle, BRANCHES_FILE)
+                .with_context(|| "Failed to add branches to bundle")
+        });
+        let _ = std::fs::remove_file(&bundle);
+        created?;
+    }
+
+    let mut log_files = 0;
+    let logs = layout.logs_dir.join(session.slug());
+    if logs.is_dir() {
+        for entry in std::fs::read_dir(&logs)
+            .with_context(|| format!("Failed to read {}", logs.display()))?
+        {
+            let path = entry?.path();
+            if let Some(file_name) = path.file_name().filter(|_| path.is_file()) {
+                archive.append_path_with_name(&path, Path::new(LOGS_DIR).join(file_name))?;
+                log_files += 1;
+            }
+        }
+    }
+    archive
+        .into_inner()
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    Ok(Exported {
+        agents: snapshot.agents.len(),
+        branches: branches.len(),
+        interactions: history.interactions.len(),
+        log_files,
+    })
+}
+
+/// Unpack a bundle written by [`export`] into this project: fetch the agent
+/// branches, check out their worktrees, insert the history rows and copy the
+/// logs. Sessions and agents whose ids are already taken get new ones.
+pub async fn import(db: &Database, layout: &ProjectLayout, input: &Path) -> Result<Imported> {
+    let staging = std::env::temp_dir().join(format!("agentcrew-import-{}", uuid::Uuid::new_v4()));
+    let file = File::open(input).with_context(|| format!("Failed to open {}", input.display()))?;
+    let unpacked = tar::Archive::new(file)
+        .unpack(&staging)
+        .with_context(|| format!("Failed to unpack {}", input.display()));
+
+    let result = match unpacked {
+        Ok(()) => import_unpacked(db, layout, &staging).await,
+        Err(e) => Err(e),
+    };
+    let _ = std::fs::remove_dir_all(&staging);
+    result
+}
+
+async fn import_unpacked(
+    db: &Database,
+    layout: &ProjectLayout,
+    staging: &Path,
+) -> Result<Imported> {
+    let mut snapshot = SessionSnapshot::read(&staging.join(SESSION_FILE))?;
+    let history_path = staging.join(HISTORY_FILE);
+    let history: History = serde_json::from_str(
+        &std::fs::read_to_string(&history_path)
+            .with_context(|| format!("Bundle has no {}", HISTORY_FILE))?,
+    )
+    .with_context(|| format!("Invalid {}", HISTORY_FILE))?;
+    if history.version > BUNDLE_VERSION {
+        anyhow::bail!(
+            "Bundle was written by a newer agentcrew (version {})",
+            history.version
+        );
+    }
+
+    let repo = GitUtils::find_git_repository(&layout.repo_root)?;
+    let old_slug = snapshot.session.slug().to_string();
+    let branch_taken = snapshot.agents.iter().any(|saved| {
+        saved
+            .agent
+            .branch_name
+            .as_deref()
+            .is_some_and(|branch| repo.find_branch(branch, BranchType::Local).is_ok())
+    });
+    let remapped = branch_taken
+        || db.get_session(&snapshot.session.id).await.is_ok()
+        || layout.worktrees_dir.join(&old_slug).exists();
+
+    let mut ids = HashMap::new();
+    if remapped {
+        ids.insert(
+            snapshot.session.id.clone(),
+            uuid::Uuid::new_v4().to_string(),
+        );
+    }
+    for saved in &snapshot.agents {
+        if remapped || db.get_agent(&saved.agent.id).await.is_ok() {
+            ids.insert(saved.agent.id.clone(), uuid::Uuid::new_v4().to_string());
+        }
+    }
+    let remap = |id: &str| ids.get(id).cloned().unwrap_or_else(|| id.to_string());
+
+    let mut session = snapshot.session.clone();
+    session.id = remap(&session.id);
+    let slug = session.slug().to_string();
+    let manager = WorktreeManager::new(&layout.repo_root, &layout.worktrees_dir);
+    let worktrees: Vec<(usize, AgentWorktree)> = snapshot
+        .agents
+        .iter()
+        .enumerate()
+        .filter(|(_, saved)| saved.branch_tip.is_some() && saved.agent.branch_name.is_some())
+        .map(|(index, saved)| {
+            let name = saved.agent.name();
+            let worktree = AgentWorktree {
+                branch: WorktreeManager::branch_name(&slug, &name),
+                path: manager.worktree_path(&slug, &name),
+                worktree_name: WorktreeManager::worktree_name(&slug, &name),
+                agent_name: name,
+            };
+            (index, worktree)
+        })
+        .collect();
+
+    let imported: Result<usize> = async {
+        if !worktrees.is_empty() {
+            let bundle = staging.join(BRANCHES_FILE);
+            let bundle_path = bundle.to_string_lossy().into_owned();
+            let refspecs: Vec<String> = worktrees
+                .iter()
+                .map(|(index, worktree)| {
+                    format!(
+                        "refs/heads/{}:refs/heads/{}",
+                        snapshot.agents[*index]
+                            .agent
+                            .branch_name
+                            .as_deref()
+                            .unwrap_or_default(),
+                        worktree.branch
+                    )
+                })
+                .collect();
+            let mut args = vec!["fetch", "--quiet", "--no-tags", bundle_path.as_str()];
+            args.extend(refspecs.iter().map(String::as_str));
+            git(&layout.repo_root, &args).with_context(|| "Failed to fetch agent branches")?;
+        }
+
+        for (index, worktree) in &worktrees {
+            let tip = snapshot.agents[*index]
+                .branch_tip
+                .as_deref()
+                .unwrap_or_default();
+            let commit = Oid::from_str(tip)?;
+            manager.restore(&slug, &worktree.agent_name, commit, false)?;
+        }
+
+        db.restore_session(&session).await?;
+        for (index, saved) in snapshot.agents.iter_mut().enumerate() {
+            let agent = &mut saved.agent;
+            agent.id = remap(&agent.id);
+            agent.session_id = session.id.clone();
+            match worktrees.iter().find(|(i, _)| *i == index) {
+                Some((_, worktree)) => {
+                    agent.worktree_path = Some(worktree.path.to_string_lossy().into_owned());
+                    agent.branch_name = Some(worktree.branch.clone());
+                }
+                None => {
+                    agent.worktree_path = None;
+                    agent.branch_name = None;
+                }
+            }
+            // Nothing runs here yet; `agentcrew load` relaunches from the snapshot
+            let status = if agent.status.is_terminal() {
+                agent.status
+            } else {
+                AgentStatus::Dismissed
+            };
+            db.restore_agent(agent, status).await?;
+        }
+
+        let mut interaction_ids = HashMap::new();
+        for interaction in &history.interactions {
+            let mut interaction = interaction.clone();
+            interaction.agent_id = remap(&interaction.agent_id);
+            interaction.session_id = session.id.clone();
+            interaction.metadata = interaction
+                .metadata
+                .as_deref()
+                .map(|metadata| remap_metadata(metadata, &interaction_ids));
+            let id = db.import_interaction(&interaction).await?;
+            interaction_ids.insert(interaction.id, id);
+        }
+        for saved in &mut snapshot.agents {
+            for question in &mut saved.pending_questions {
+                question.id = interaction_ids
+                    .get(&question.id)
+                    .copied()
+                    .unwrap_or(question.id);
+                question.agent_id = saved.agent.id.clone();
+                question.session_id = session.id.clone();
+            }
+        }
+
+        let file_changes: Vec<FileChangeRecord> = history
+            .file_changes
+            .iter()
+            .map(|change| FileChangeRecord {
+                agent_id: remap(&change.agent_id),
+                session_id: session.id.clone(),
+                ..change.clone()
+            })
+            .collect();
+        db.record_file_changes(&file_changes).await?;
+        for result in &history.harvest_results {
+            db.record_harvest_result(&HarvestResultRecord {
+                agent_id: remap(&result.agent_id),
+                session_id: session.id.clone(),
+                ..result.clone()
+            })
+            .await?;
+        }
+
+        let logs = staging.join(LOGS_DIR);
+        if logs.is_dir() {
+            let target = layout.logs_dir.join(&slug);
+            std::fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create directory: {}", target.display()))?;
+            for entry in std::fs::read_dir(&logs)? {
+                let entry = entry?;
+                std::fs::copy(entry.path(), target.join(entry.file_name()))
+                    .with_context(|| format!("Failed to copy {}", entry.path().display()))?;
+            }
+        }
+        Ok(history.interactions.len())
+    }
+    .await;
+
+    let interactions = match imported {
+        Ok(interactions) => interactions,
+        Err(e) => {
+            for (_, worktree) in &worktrees {
+                if let Err(cleanup) = manager.remove(&repo, worktree, true) {
+                    eprintln!("⚠️  Failed to remove {}: {:#}", worktree.branch, cleanup);
+                }
+            }
+            db.delete_session(&session.id).await?;
+            return Err(e.context("Import failed and was rolled back"));
+        }
+    };
+
+    let mut snapshot_name = snapshot.name.clone();
+    if SessionSnapshot::path(&layout.sessions_dir, &snapshot_name).exists() {
+        snapshot_name = format!("{}-{}", snapshot_name, slug);
+    }
+    session.name = Some(snapshot_name.clone());
+    db.set_session_name(&session.id, &snapshot_name).await?;
+    snapshot.name = snapshot_name.clone();
+    snapshot.session = session.clone();
+    snapshot.write(&SessionSnapshot::path(&layout.sessions_dir, &snapshot_name))?;
+
+    Ok(Imported {
+        session: db.get_session(&session.id).await?,
+        remapped,
+        snapshot_name,
+        worktrees: worktrees
+            .into_iter()
+            .map(|(_, worktree)| worktree)
+            .collect(),
+        interactions,
+    })
+}
+
+/// Point interaction ids referenced from metadata (answered questions,
+/// rolled back checkpoints) at their imported rows
+fn remap_metadata(metadata: &str, ids: &HashMap<i64, i64>) -> String {
+    let Ok(mut value) = serde_json::from_str::<serde_json::Value>(metadata) else {
+        return metadata.to_string();
+    };
+    let remap = |value: &mut serde_json::Value| {
+        if let Some(id) = value.as_i64().and_then(|id| ids.get(&id)) {
+            *value = (*id).into();
+        }
+    };
+    if let Some(questions) = value.get_mut("questions").and_then(|q| q.as_array_mut()) {
+        questions.iter_mut().for_each(remap);
+    }
+    if let Some(checkpoint) = value.get_mut("checkpoint") {
+        remap(checkpoint);
+    }
+    value.to_string()
+}
+
+fn append_json<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    value: &impl Serialize,
+) -> Result<()> {
+    let data = serde_json::to_vec_pretty(value)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive
+        .append_data(&mut header, name, data.as_slice())
+        .with_context(|| format!("Failed to add {} to bundle", name))
+}
+
+/// Run the git CLI in `dir`; libgit2 cannot read or write bundles
+fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| "Failed to run git; is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InteractionType;
+    use crate::git::diff::tests::commit_files;
+    use git2::Repository;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn layout(root: &Path) -> ProjectLayout {
+        ProjectLayout {
+            repo_root: root.to_path_buf(),
+            worktrees_dir: root.join(".agentcrew/worktrees"),
+            logs_dir: root.join(".agentcrew/logs"),
+            sessions_dir: root.join(".agentcrew/sessions"),
+        }
+    }
+
+    #[test]
+    fn test_remap_metadata() {
+        let ids = HashMap::from([(3, 30), (4, 40)]);
+        assert_eq!(
+            remap_metadata(r#"{"questions":[3,4,5]}"#, &ids),
+            r#"{"questions":[30,40,5]}"#
+        );
+        assert_eq!(
+            remap_metadata(r#"{"checkpoint":4,"commit":"abc"}"#, &ids),
+            r#"{"checkpoint":40,"commit":"abc"}"#
+        );
+        assert_eq!(remap_metadata("not json", &ids), "not json");
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import_into_clone() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let origin_dir = temp_dir.path().join("origin");
+        let origin = Repository::init(&origin_dir).expect("Should init repo");
+        let base = commit_files(&origin, "main", None, &[("README.md", Some("hi\n"))]);
+        origin.set_head("refs/heads/main").unwrap();
+        let clone_dir = temp_dir.path().join("clone");
+        Repository::clone(origin_dir.to_str().unwrap(), &clone_dir).expect("Should clone");
+
+        let origin_layout = layout(&origin_dir);
+        let db = Database::new(&temp_dir.path().join("origin.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("build it", r#"{"mock":1}"#, "main", &base.to_string())
+            .await
+            .unwrap();
+        let agent = db
+            .create_agent(&session.id, "mock", 1, &BTreeMap::new())
+            .await
+            .unwrap();
+        let branch = WorktreeManager::branch_name(session.slug(), "mock-1");
+        let tip = commit_files(&origin, &branch, Some(base), &[("work.rs", Some("1\n"))]);
+        db.set_agent_worktree(&agent.id, &origin_dir, &branch)
+            .await
+            .unwrap();
+        let question = db
+            .record_interaction(
+                &agent.id,
+                &session.id,
+                InteractionType::Question,
+                "Proceed?",
+                None,
+            )
+            .await
+            .unwrap();
+        db.answer_questions(&agent.id, &session.id, "yes")
+            .await
+            .unwrap();
+        let logs = origin_layout.logs_dir.join(session.slug());
+        std::fs::create_dir_all(&logs).unwrap();
+        std::fs::write(logs.join("mock-1.log"), "hello\n").unwrap();
+
+        let output = temp_dir.path().join("handoff.tar");
+        let exported = export(&db, &origin_layout, &session, &output)
+            .await
+            .expect("Should export");
+        assert_eq!(exported.branches, 1);
+        assert_eq!(exported.interactions, 2);
+        assert_eq!(exported.log_files, 1);
+
+        // A teammate's clone with its own database keeps the ids
+        let clone_layout = layout(&clone_dir);
+        let clone_db = Database::new(&temp_dir.path().join("clone.db"))
+            .await
+            .expect("Should create database");
+        let imported = import(&clone_db, &clone_layout, &output)
+            .await
+            .expect("Should import");
+        assert!(!imported.remapped);
+        assert_eq!(imported.session.id, session.id);
+        assert_eq!(imported.interactions, 2);
+        let clone = Repository::open(&clone_dir).unwrap();
+        assert_eq!(
+            crate::git::diff::branch_tip(&clone, &branch).unwrap().id(),
+            tip
+        );
+        assert!(imported.worktrees[0].path.join("work.rs").exists());
+        let agents = clone_db.list_agents(&session.id).await.unwrap();
+        assert_eq!(agents[0].status, AgentStatus::Dismissed);
+        assert!(clone_layout
+            .logs_dir
+            .join(session.slug())
+            .join("mock-1.log")
+            .exists());
+        assert!(
+            SessionSnapshot::path(&clone_layout.sessions_dir, &imported.snapshot_name).exists()
+        );
+
+        // Importing again collides and gets fresh ids
+        let again = import(&clone_db, &clone_layout, &output)
+            .await
+            .expect("Should import again");
+        assert!(again.remapped);
+        assert_ne!(again.session.id, session.id);
+        let agents = clone_db.list_agents(&again.session.id).await.unwrap();
+        assert_eq!(agents.len(), 1);
+        assert_ne!(agents[0].id, agent.id);
+        let interactions = clone_db.list_interactions(&agents[0].id).await.unwrap();
+        let response = &interactions[1];
+        let answered: serde_json::Value =
+            serde_json::from_str(response.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(answered["questions"][0], interactions[0].id);
+        assert_ne!(interactions[0].id, question);
+        assert_eq!(
+            crate::git::diff::branch_tip(&clone, &again.worktrees[0].branch)
+                .unwrap()
+                .id(),
+            tip
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Saved snapshots of multi-agent sessions
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use git2::{Oid, Repository};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::database::{AgentRecord, Database, InteractionRecord, SessionRecord};
+use crate::git::diff::{branch_tip, short_id};
+
+mod bundle;
+
+pub use bundle::{export, import, Exported, Imported, ProjectLayout};
+
+/// Format version written into every snapshot file
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything needed to bring a session back: its row, every agent, the
+/// commit each agent branch pointed to and the questions still unanswered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub version: u32,
+    pub name: String,
+    pub saved_at: DateTime<Utc>,
+    pub session: SessionRecord,
+    pub agents: Vec<AgentSnapshot>,
+}
+
+/// One agent of a saved session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentSnapshot {
+    pub agent: AgentRecord,
+    /// Commit the agent's branch pointed to when the session was saved
+    pub branch_tip: Option<String>,
+    pub pending_questions: Vec<InteractionRecord>,
+}
+
+impl SessionSnapshot {
+    /// Record the current state of `session`
+    pub async fn capture(
+        db: &Database,
+        repo: &Repository,
+        session: &SessionRecord,
+        name: &str,
+    ) -> Result<Self> {
+        let pending = db.pending_questions().await?;
+        let mut agents = Vec::new();
+        for agent in db.list_agents(&session.id).await? {
+            let branch_tip = match agent.branch_name.as_deref() {
+                Some(branch) => Some(branch_tip(repo, branch)?.id().to_string()),
+                None => None,
+            };
+            let pending_questions = pending
+                .iter()
+                .filter(|question| question.agent_id == agent.id)
+                .cloned()
+                .collect();
+            agents.push(AgentSnapshot {
+                agent,
+                branch_tip,
+                pending_questions,
+            });
+        }
+
+        let mut session = session.clone();
+        session.name = Some(name.to_string());
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            name: name.to_string(),
+            saved_at: Utc::now(),
+            session,
+            agents,
+        })
+    }
+
+    /// File a snapshot called `name` is stored in
+    pub fn path(sessions_dir: &Path, name: &str) -> PathBuf {
+        sessions_dir.join(format!("{}.json", name))
+    }
+
+    /// Write the snapshot as pretty-printed JSON
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Read a snapshot written by [`SessionSnapshot::write`]
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let snapshot: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid session snapshot {}", path.display()))?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            anyhow::bail!(
+                "{} was written by a newer agentcrew (snapshot version {})",
+                path.display(),
+                snapshot.version
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// Names of the snapshots saved in `sessions_dir`, sorted
+    pub fn list(sessions_dir: &Path) -> Result<Vec<String>> {
+        if !sessions_dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names = Vec::new();
+        for entry in std::fs::read_dir(sessions_dir)
+            .with_context(|| format!("Failed to read {}", sessions_dir.display()))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+impl AgentSnapshot {
+    /// Message telling a relaunched agent where it left off
+    pub fn resume_brief(&self, snapshot: &SessionSnapshot) -> String {
+        let mut brief = format!(
+            "You are resuming session {} (saved as '{}' on {}).",
+            snapshot.session.slug(),
+            snapshot.name,
+            snapshot
+                .saved_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+        );
+        if let (Some(branch), Some(tip)) = (&self.agent.branch_name, &self.branch_tip) {
+            brief.push_str(&format!(
+                " Your branch {} is at {} and holds your earlier work; review it before you continue.",
+                branch,
+                Oid::from_str(tip).map_or_else(|_| tip.clone(), short_id)
+            ));
+        }
+        if !self.pending_questions.is_empty() {
+            brief.push_str(" These questions you asked were never answered:");
+            for question in &self.pending_questions {
+                brief.push_str(&format!("\n- {}", question.content));
+            }
+        }
+        brief
+    }
+}
+
+/// Check that a snapshot name is usable as a file name
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid session name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::InteractionType;
+    use crate::git::diff::tests::commit_files;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_capture_and_read_snapshot() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = Repository::init(temp_dir.path().join("repo")).expect("Should init repo");
+        let base = commit_files(&repo, "main", None, &[("README.md", Some("hi\n"))]);
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("build it", r#"{"mock":2}"#, "main", &base.to_string())
+            .await
+            .unwrap();
+        let first = db
+            .create_agent(&session.id, "mock", 1, &BTreeMap::new())
+            .await
+            .unwrap();
+        db.create_agent(&session.id, "mock", 2, &BTreeMap::new())
+            .await
+            .unwrap();
+        let branch = format!("agentcrew/{}/mock-1", session.slug());
+        let tip = commit_files(&repo, &branch, Some(base), &[("a.rs", Some("1\n"))]);
+        db.set_agent_worktree(&first.id, temp_dir.path(), &branch)
+            .await
+            .unwrap();
+        db.record_interaction(
+            &first.id,
+            &session.id,
+            InteractionType::Question,
+            "Use SQLite?",
+            None,
+        )
+        .await
+        .unwrap();
+
+        let snapshot = SessionSnapshot::capture(&db, &repo, &session, "nightly")
+            .await
+            .expect("Should capture");
+        assert_eq!(snapshot.session.name.as_deref(), Some("nightly"));
+        assert_eq!(snapshot.agents.len(), 2);
+        assert_eq!(snapshot.agents[0].branch_tip, Some(tip.to_string()));
+        assert_eq!(snapshot.agents[0].pending_questions.len(), 1);
+        assert_eq!(snapshot.agents[1].branch_tip, None);
+
+        let sessions_dir = temp_dir.path().join("sessions");
+        let path = SessionSnapshot::path(&sessions_dir, "nightly");
+        snapshot.write(&path).expect("Should write");
+        let read = SessionSnapshot::read(&path).expect("Should read");
+        assert_eq!(read.session.prompt, "build it");
+        assert_eq!(read.agents[0].agent.id, first.id);
+        assert_eq!(
+            SessionSnapshot::list(&sessions_dir).unwrap(),
+            vec!["nightly"]
+        );
+
+        let brief = read.agents[0].resume_brief(&read);
+        assert!(brief.contains(&branch));
+        assert!(brief.contains(&short_id(tip)));
+        assert!(brief.ends_with("\n- Use SQLite?"));
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("auth-refactor_v2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../escape").is_err());
+        assert!(validate_name(".hidden").is_err());
+        assert!(validate_name("a b").is_err());
+    }
+}
@@ -0,0 +1,622 @@
+//! State of the terminal UI and how keys change it
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::VecDeque;
+
+use super::compare::{Comparison, Side};
+use crate::database::{AgentRecord, AgentStatus, InteractionRecord, SessionRecord};
+use crate::git::diff::FileDiff;
+use crate::process::logs::LogEntry;
+
+/// Lines kept in the log pane
+const MAX_LOG_LINES: usize = 1000;
+
+/// Lines moved by PageUp/PageDown
+const PAGE_LINES: usize = 10;
+
+/// A log line of one agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub agent: String,
+    pub entry: LogEntry,
+}
+
+/// Something the UI asks the caller to do on its behalf
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Answer the agent's pending questions, like `agentcrew respond`
+    Respond {
+        agent_id: String,
+        name: String,
+        message: String,
+    },
+    /// Send instructions to every running agent
+    Brief {
+        message: String,
+    },
+    Pause {
+        agent_id: String,
+        name: String,
+    },
+    Resume {
+        agent_id: String,
+        name: String,
+    },
+    Dismiss {
+        agent_id: String,
+        name: String,
+    },
+    /// Diff two agents' branches and pass it to [`App::show_compare`]
+    Compare {
+        left: Side,
+        right: Side,
+    },
+    /// Remember an agent as the session's winner for `agentcrew adopt`
+    MarkWinner {
+        session_id: String,
+        agent_id: String,
+        name: String,
+    },
+    /// Load the full log of an agent and pass it to [`App::show_logs`]
+    ShowLogs {
+        session_slug: String,
+        name: String,
+    },
+}
+
+/// What a line of text typed at the prompt is for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prompt {
+    Answer {
+        agent_id: String,
+        name: String,
+        question: String,
+    },
+    Brief,
+}
+
+/// Which screen keys go to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Input {
+        prompt: Prompt,
+        text: String,
+    },
+    ConfirmDismiss {
+        agent_id: String,
+        name: String,
+    },
+    /// Full log of one agent; `scroll` counts lines up from the end
+    Logs {
+        agent: String,
+        entries: Vec<LogEntry>,
+        scroll: usize,
+    },
+    Help,
+    /// Picking the agent to compare agent `left` with
+    ChooseRival {
+        left: usize,
+        cursor: usize,
+    },
+    Compare(Box<Comparison>),
+}
+
+/// Everything the TUI shows
+#[derive(Debug)]
+pub struct App {
+    pub(super) session: Option<SessionRecord>,
+    pub(super) agents: Vec<AgentRecord>,
+    /// Pending questions of the session's agents, oldest first
+    pub(super) questions: Vec<InteractionRecord>,
+    pub(super) logs: VecDeque<LogLine>,
+    pub(super) selected: usize,
+    pub(super) question: usize,
+    /// Lines the log pane is scrolled up from the newest line
+    pub(super) log_scroll: usize,
+    pub(super) mode: Mode,
+    /// Outcome of the last action, shown in the footer
+    pub(super) message: Option<String>,
+    quit: bool,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            session: None,
+            agents: Vec::new(),
+            questions: Vec::new(),
+            logs: VecDeque::new(),
+            selected: 0,
+            question: 0,
+            log_scroll: 0,
+            mode: Mode::Normal,
+            message: None,
+            quit: false,
+        }
+    }
+
+    /// Whether the user asked to leave
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Id of the session on screen
+    pub fn session_id(&self) -> Option<&str> {
+        self.session.as_ref().map(|session| session.id.as_str())
+    }
+
+    /// Replace the session state with a fresh read. Logs are dropped when
+    /// the session changes.
+    pub fn update(
+        &mut self,
+        session: Option<SessionRecord>,
+        agents: Vec<AgentRecord>,
+        questions: Vec<InteractionRecord>,
+    ) {
+        let changed = self.session_id() != session.as_ref().map(|session| session.id.as_str());
+        let selected_id = self.agents.get(self.selected).map(|agent| agent.id.clone());
+        let question_id = self
+            .questions
+            .get(self.question)
+            .map(|question| question.id);
+        if changed {
+            self.logs.clear();
+            self.log_scroll = 0;
+        }
+
+        self.session = session;
+        self.agents = agents;
+        self.questions = questions;
+        self.selected = selected_id
+            .and_then(|id| self.agents.iter().position(|agent| agent.id == id))
+            .unwrap_or(0)
+            .min(self.agents.len().saturating_sub(1));
+        self.question = question_id
+            .and_then(|id| self.questions.iter().position(|question| question.id == id))
+            .unwrap_or(self.question)
+            .min(self.questions.len().saturating_sub(1));
+    }
+
+    /// Append log lines, oldest first, keeping the view in place when the
+    /// pane is scrolled up
+    pub fn push_logs(&mut self, mut lines: Vec<LogLine>) {
+        lines.sort_by_key(|line| line.entry.timestamp);
+        if self.log_scroll > 0 {
+            self.log_scroll += lines.len();
+        }
+        self.logs.extend(lines);
+        while self.logs.len() > MAX_LOG_LINES {
+            self.logs.pop_front();
+        }
+        self.log_scroll = self.log_scroll.min(self.logs.len().saturating_sub(1));
+    }
+
+    /// Open the full log of an agent
+    pub fn show_logs(&mut self, agent: String, entries: Vec<LogEntry>) {
+        self.mode = Mode::Logs {
+            agent,
+            entries,
+            scroll: 0,
+        };
+    }
+
+    /// Open the side-by-side diff of two agents
+    pub fn show_compare(&mut self, left: Side, right: Side, files: Vec<FileDiff>) {
+        self.mode = Mode::Compare(Box::new(Comparison::new(left, right, files)));
+    }
+
+    /// Agent marked as the session's winner
+    pub(super) fn winner(&self) -> Option<&str> {
+        self.session.as_ref()?.winner_agent_id.as_deref()
+    }
+
+    /// Show the outcome of an action in the footer
+    pub fn set_message(&mut self, message: impl Into<String>) {
+        self.message = Some(message.into());
+    }
+
+    /// Selected agent, if the session has any
+    pub fn selected_agent(&self) -> Option<&AgentRecord> {
+        self.agents.get(self.selected)
+    }
+
+    /// Name of the agent that asked a question
+    pub(super) fn asker(&self, question: &InteractionRecord) -> String {
+        self.agents
+            .iter()
+            .find(|agent| agent.id == question.agent_id)
+            .map_or_else(|| question.agent_id.clone(), |agent| agent.name())
+    }
+
+    /// Apply a key press, returning the action it triggers
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return None;
+        }
+        self.message = None;
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.normal_key(key),
+            Mode::Input { prompt, text } => self.input_key(key, prompt, text),
+            Mode::ConfirmDismiss { agent_id, name } => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Some(Action::Dismiss { agent_id, name }),
+                _ => None,
+            },
+            Mode::Logs {
+                agent,
+                entries,
+                scroll,
+            } => {
+                let last = entries.len().saturating_sub(1);
+                let scroll = match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('l') => return None,
+                    KeyCode::Up | KeyCode::Char('k') => scroll + 1,
+                    KeyCode::Down | KeyCode::Char('j') => scroll.saturating_sub(1),
+                    KeyCode::PageUp => scroll + PAGE_LINES,
+                    KeyCode::PageDown => scroll.saturating_sub(PAGE_LINES),
+                    KeyCode::Home | KeyCode::Char('g') => last,
+                    KeyCode::End | KeyCode::Char('G') => 0,
+                    _ => scroll,
+                };
+                self.mode = Mode::Logs {
+                    agent,
+                    entries,
+                    scroll: scroll.min(last),
+                };
+                None
+            }
+            Mode::Help => None,
+            Mode::ChooseRival { left, cursor } => {
+                let rivals = self.agents.len().saturating_sub(1);
+                let cursor = match key.code {
+                    KeyCode::Esc => return None,
+                    KeyCode::Up | KeyCode::Char('k') => cursor.saturating_sub(1),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        (cursor + 1).min(rivals.saturating_sub(1))
+                    }
+                    KeyCode::Enter => {
+                        let side = |agent: &AgentRecord| Side {
+                            agent_id: agent.id.clone(),
+                            name: agent.name(),
+                        };
+                        let left = self.agents.get(left)?;
+                        let right = self.rivals(left).nth(cursor)?;
+                        return Some(Action::Compare {
+                            left: side(left),
+                            right: side(right),
+                        });
+                    }
+                    _ => cursor,
+                };
+                self.mode = Mode::ChooseRival { left, cursor };
+                None
+            }
+            Mode::Compare(mut comparison) => {
+                let winner = match key.code {
+                    KeyCode::Esc | KeyCode::Char('q') => return None,
+                    KeyCode::Tab | KeyCode::Char(']') => {
+                        comparison.select_file(comparison.file + 1);
+                        None
+                    }
+                    KeyCode::BackTab | KeyCode::Char('[') => {
+                        comparison.select_file(comparison.file + comparison.files.len().max(1) - 1);
+                        None
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        comparison.scroll_by(1);
+                        None
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        comparison.scroll_by(-1);
+                        None
+                    }
+                    KeyCode::PageDown => {
+                        comparison.scroll_by(PAGE_LINES as isize);
+                        None
+                    }
+                    KeyCode::PageUp => {
+                        comparison.scroll_by(-(PAGE_LINES as isize));
+                        None
+                    }
+                    KeyCode::Char('n') => {
+                        comparison.next_hunk();
+                        None
+                    }
+                    KeyCode::Char('N') | KeyCode::Char('p') => {
+                        comparison.previous_hunk();
+                        None
+                    }
+                    KeyCode::Char('1') => Some(comparison.left.clone()),
+                    KeyCode::Char('2') => Some(comparison.right.clone()),
+                    _ => None,
+                };
+                self.mode = Mode::Compare(comparison);
+                let session_id = self.session_id()?.to_string();
+                winner.map(|side| Action::MarkWinner {
+                    session_id,
+                    agent_id: side.agent_id,
+                    name: side.name,
+                })
+            }
+        }
+    }
+
+    /// Agents that `agent` can be compared with, in list order
+    pub(super) fn rivals<'a>(
+        &'a self,
+        agent: &'a AgentRecord,
+    ) -> impl Iterator<Item = &'a AgentRecord> + 'a {
+        self.agents.iter().filter(move |other| other.id != agent.id)
+    }
+
+    fn normal_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Tab => self.select_agent(self.selected + 1),
+            KeyCode::BackTab => self.select_agent(self.selected + self.agents.len().max(1) - 1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.question = (self.question + 1).min(self.questions.len().saturating_sub(1))
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.question = self.question.saturating_sub(1),
+            KeyCode::PageUp => {
+                self.log_scroll =
+                    (self.log_scroll + PAGE_LINES).min(self.logs.len().saturating_sub(1))
+            }
+            KeyCode::PageDown => self.log_scroll = self.log_scroll.saturating_sub(PAGE_LINES),
+            KeyCode::End => self.log_scroll = 0,
+            KeyCode::Enter => match self.questions.get(self.question) {
+                Some(question) => {
+                    self.mode = Mode::Input {
+                        prompt: Prompt::Answer {
+                            agent_id: question.agent_id.clone(),
+                            name: self.asker(question),
+                            question: question.content.clone(),
+                        },
+                        text: String::new(),
+                    }
+                }
+                None => self.set_message("No pending questions"),
+            },
+            KeyCode::Char('b') => {
+                self.mode = Mode::Input {
+                    prompt: Prompt::Brief,
+                    text: String::new(),
+                }
+            }
+            KeyCode::Char('p') => {
+                let agent = self.selected_agent()?;
+                let (agent_id, name) = (agent.id.clone(), agent.name());
+                return match agent.status {
+                    AgentStatus::Paused => Some(Action::Resume { agent_id, name }),
+                    status if status.is_terminal() => {
+                        self.set_message(format!("{} already {}", name, status));
+                        None
+                    }
+                    _ => Some(Action::Pause { agent_id, name }),
+                };
+            }
+            KeyCode::Char('d') => {
+                let agent = self.selected_agent()?;
+                if agent.status.is_terminal() {
+                    let message = format!("{} already {}", agent.name(), agent.status);
+                    self.set_message(message);
+                } else {
+                    self.mode = Mode::ConfirmDismiss {
+                        agent_id: agent.id.clone(),
+                        name: agent.name(),
+                    };
+                }
+            }
+            KeyCode::Char('l') => {
+                return self.selected_agent().map(|agent| Action::ShowLogs {
+                    session_slug: agent.session_slug().to_string(),
+                    name: agent.name(),
+                })
+            }
+            KeyCode::Char('c') if self.agents.len() < 2 => {
+                self.set_message("Comparing needs at least two agents")
+            }
+            KeyCode::Char('c') => {
+                self.mode = Mode::ChooseRival {
+                    left: self.selected,
+                    cursor: 0,
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Char('?') => self.mode = Mode::Help,
+            _ => {}
+        }
+        None
+    }
+
+    fn input_key(&mut self, key: KeyEvent, prompt: Prompt, mut text: String) -> Option<Action> {
+        match key.code {
+            KeyCode::Esc => return None,
+            KeyCode::Enter if !text.trim().is_empty() => {
+                let message = text.trim().to_string();
+                return Some(match prompt {
+                    Prompt::Answer { agent_id, name, .. } => Action::Respond {
+                        agent_id,
+                        name,
+                        message,
+                    },
+                    Prompt::Brief => Action::Brief { message },
+                });
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        self.mode = Mode::Input { prompt, text };
+        None
+    }
+
+    /// Select agent `index` (wrapping) and the first question it asked
+    fn select_agent(&mut self, index: usize) {
+        if self.agents.is_empty() {
+            return;
+        }
+        self.selected = index % self.agents.len();
+        let id = &self.agents[self.selected].id;
+        if let Some(position) = self
+            .questions
+            .iter()
+            .position(|question| question.agent_id == *id)
+        {
+            self.question = position;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Database, InteractionType};
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Action> {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn type_text(app: &mut App, text: &str) {
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_app_keys() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("build it", r#"{"mock":2}"#, "main", "abc123")
+            .await
+            .unwrap();
+        let first = db
+            .create_agent(&session.id, "mock", 1, &BTreeMap::new())
+            .await
+            .unwrap();
+        let second = db
+            .create_agent(&session.id, "mock", 2, &BTreeMap::new())
+            .await
+            .unwrap();
+        db.update_agent_status(&first.id, AgentStatus::Paused)
+            .await
+            .unwrap();
+        db.record_interaction(
+            &second.id,
+            &session.id,
+            InteractionType::Question,
+            "Use OAuth2?",
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut app = App::new();
+        app.update(
+            Some(session.clone()),
+            db.list_agents(&session.id).await.unwrap(),
+            db.pending_questions().await.unwrap(),
+        );
+
+        // The first agent is paused, so p resumes it
+        assert_eq!(
+            press(&mut app, KeyCode::Char('p')),
+            Some(Action::Resume {
+                agent_id: first.id.clone(),
+                name: "mock-1".to_string()
+            })
+        );
+
+        // Enter answers the highlighted question
+        assert_eq!(press(&mut app, KeyCode::Enter), None);
+        type_text(&mut app, "yes!");
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(
+            press(&mut app, KeyCode::Enter),
+            Some(Action::Respond {
+                agent_id: second.id.clone(),
+                name: "mock-2".to_string(),
+                message: "yes".to_string()
+            })
+        );
+        assert_eq!(app.mode, Mode::Normal);
+
+        // Dismiss asks first; anything but y cancels
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.selected_agent().unwrap().id, second.id);
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(press(&mut app, KeyCode::Char('n')), None);
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(
+            press(&mut app, KeyCode::Char('y')),
+            Some(Action::Dismiss {
+                agent_id: second.id.clone(),
+                name: "mock-2".to_string()
+            })
+        );
+
+        // Escape abandons a brief
+        press(&mut app, KeyCode::Char('b'));
+        type_text(&mut app, "stop");
+        assert_eq!(press(&mut app, KeyCode::Esc), None);
+        assert_eq!(app.mode, Mode::Normal);
+
+        // c picks the agent to compare the selected one with
+        press(&mut app, KeyCode::Char('c'));
+        let action = press(&mut app, KeyCode::Enter);
+        let Some(Action::Compare { left, right }) = action else {
+            panic!("Expected a comparison, got {:?}", action);
+        };
+        assert_eq!(
+            (left.name.as_str(), right.name.as_str()),
+            ("mock-2", "mock-1")
+        );
+        app.show_compare(left, right, Vec::new());
+        assert_eq!(
+            press(&mut app, KeyCode::Char('2')),
+            Some(Action::MarkWinner {
+                session_id: session.id.clone(),
+                agent_id: first.id.clone(),
+                name: "mock-1".to_string()
+            })
+        );
+        assert!(matches!(app.mode, Mode::Compare(_)));
+        press(&mut app, KeyCode::Esc);
+
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.should_quit());
+    }
+
+    #[test]
+    fn test_push_logs_keeps_scrolled_view() {
+        let line = |text: &str| LogLine {
+            agent: "mock-1".to_string(),
+            entry: LogEntry {
+                timestamp: chrono::Utc::now(),
+                stream: "stdout".to_string(),
+                text: text.to_string(),
+            },
+        };
+        let mut app = App::new();
+        app.push_logs((0..20).map(|n| line(&n.to_string())).collect());
+        press(&mut app, KeyCode::PageUp);
+        assert_eq!(app.log_scroll, PAGE_LINES);
+        app.push_logs(vec![line("new")]);
+        assert_eq!(app.log_scroll, PAGE_LINES + 1);
+        press(&mut app, KeyCode::End);
+        assert_eq!(app.log_scroll, 0);
+    }
+}
@@ -0,0 +1,150 @@
+//! Side-by-side comparison of two agents' branches
+
+use crate::git::diff::{FileDiff, RowKind};
+
+/// One of the two compared agents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Side {
+    pub agent_id: String,
+    pub name: String,
+}
+
+/// Diff of two agents' branch tips, file by file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub left: Side,
+    pub right: Side,
+    pub files: Vec<FileDiff>,
+    /// Index of the file on screen
+    pub file: usize,
+    /// First row on screen
+    pub scroll: usize,
+}
+
+impl Comparison {
+    pub fn new(left: Side, right: Side, files: Vec<FileDiff>) -> Self {
+        Self {
+            left,
+            right,
+            files,
+            file: 0,
+            scroll: 0,
+        }
+    }
+
+    /// File on screen, if the branches differ at all
+    pub fn current(&self) -> Option<&FileDiff> {
+        self.files.get(self.file)
+    }
+
+    /// Rows of the file on screen that start a hunk
+    pub fn hunks(&self) -> Vec<usize> {
+        self.current().map_or_else(Vec::new, |file| {
+            file.rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| matches!(row.kind, RowKind::Hunk(_)))
+                .map(|(index, _)| index)
+                .collect()
+        })
+    }
+
+    /// Show file `index`, wrapping around, from its top
+    pub fn select_file(&mut self, index: usize) {
+        if !self.files.is_empty() {
+            self.file = index % self.files.len();
+            self.scroll = 0;
+        }
+    }
+
+    /// Move `lines` rows down, or up when negative
+    pub fn scroll_by(&mut self, lines: isize) {
+        let last = self
+            .current()
+            .map_or(0, |file| file.rows.len().saturating_sub(1));
+        self.scroll = self.scroll.saturating_add_signed(lines).min(last);
+    }
+
+    /// Jump to the next hunk, continuing with the next file after the last
+    pub fn next_hunk(&mut self) {
+        match self.hunks().into_iter().find(|&row| row > self.scroll) {
+            Some(row) => self.scroll = row,
+            None if self.file + 1 < self.files.len() => self.select_file(self.file + 1),
+            None => {}
+        }
+    }
+
+    /// Jump to the previous hunk, continuing with the last hunk of the
+    /// previous file before the first
+    pub fn previous_hunk(&mut self) {
+        match self.hunks().into_iter().rfind(|&row| row < self.scroll) {
+            Some(row) => self.scroll = row,
+            None if self.file > 0 => {
+                self.select_file(self.file - 1);
+                self.scroll = self.hunks().last().copied().unwrap_or(0);
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::diff::{ChangeKind, FileChange, SideBySideRow};
+
+    fn file(path: &str, kinds: Vec<RowKind>) -> FileDiff {
+        FileDiff {
+            change: FileChange {
+                path: path.to_string(),
+                kind: ChangeKind::Modified,
+                additions: 0,
+                deletions: 0,
+            },
+            rows: kinds
+                .into_iter()
+                .map(|kind| SideBySideRow {
+                    kind,
+                    left: None,
+                    right: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_hunk_navigation() {
+        let hunk = || RowKind::Hunk("@@".to_string());
+        let side = |name: &str| Side {
+            agent_id: name.to_string(),
+            name: name.to_string(),
+        };
+        let mut comparison = Comparison::new(
+            side("mock-1"),
+            side("mock-2"),
+            vec![
+                file(
+                    "a.rs",
+                    vec![hunk(), RowKind::Changed, hunk(), RowKind::Added],
+                ),
+                file("b.rs", vec![hunk(), RowKind::Removed]),
+            ],
+        );
+
+        comparison.next_hunk();
+        assert_eq!((comparison.file, comparison.scroll), (0, 2));
+        comparison.next_hunk();
+        assert_eq!((comparison.file, comparison.scroll), (1, 0));
+        comparison.next_hunk();
+        assert_eq!((comparison.file, comparison.scroll), (1, 0));
+        comparison.previous_hunk();
+        assert_eq!((comparison.file, comparison.scroll), (0, 2));
+
+        comparison.scroll_by(10);
+        assert_eq!(comparison.scroll, 3);
+        comparison.scroll_by(-10);
+        assert_eq!(comparison.scroll, 0);
+        comparison.select_file(3);
+        assert_eq!(comparison.current().unwrap().change.path, "b.rs");
+    }
+}
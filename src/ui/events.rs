@@ -0,0 +1,53 @@
+//! Terminal input forwarded to the async UI loop
+
+use crossterm::event::{self, KeyEvent, KeyEventKind};
+use std::thread;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Something the UI loop reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    Key(KeyEvent),
+    /// The terminal was resized; the next draw picks up the new size
+    Resize,
+    /// No input for a tick: time to refresh state
+    Tick,
+}
+
+/// Stream of terminal events read on a background thread, so blocking
+/// reads never stall the async runtime
+pub struct Events {
+    receiver: mpsc::UnboundedReceiver<Event>,
+}
+
+impl Events {
+    /// Start reading input, emitting a [`Event::Tick`] whenever `tick_rate`
+    /// passes without any
+    pub fn new(tick_rate: Duration) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        thread::spawn(move || loop {
+            let event = match event::poll(tick_rate) {
+                Ok(true) => match event::read() {
+                    Ok(event::Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                        Event::Key(key)
+                    }
+                    Ok(event::Event::Resize(..)) => Event::Resize,
+                    Ok(_) => continue,
+                    Err(_) => break,
+                },
+                Ok(false) => Event::Tick,
+                Err(_) => break,
+            };
+            if sender.send(event).is_err() {
+                break;
+            }
+        });
+        Self { receiver }
+    }
+
+    /// The next event, or `None` once the terminal can no longer be read
+    pub async fn next(&mut self) -> Option<Event> {
+        self.receiver.recv().await
+    }
+}
@@ -0,0 +1,270 @@
+//! Lightweight syntax coloring for diff lines, picked by file extension
+
+use std::path::Path;
+
+/// Class of a piece of source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Plain,
+    Keyword,
+    String,
+    Number,
+    Comment,
+}
+
+/// Lexical rules of a language, as far as coloring needs them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Syntax {
+    line_comments: &'static [&'static str],
+    quotes: &'static [char],
+    keywords: &'static [&'static str],
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "else", "enum", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "type", "unsafe",
+    "use", "where", "while",
+];
+const PYTHON_KEYWORDS: &[&str] = &[
+    "and", "as", "assert", "async", "await", "break", "class", "continue", "def", "del", "elif",
+    "else", "except", "False", "finally", "for", "from", "global", "if", "import", "in", "is",
+    "lambda", "None", "not", "or", "pass", "raise", "return", "self", "True", "try", "while",
+    "with", "yield",
+];
+const JS_KEYWORDS: &[&str] = &[
+    "async",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "from",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "return",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "type",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "yield",
+];
+const GO_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "chan",
+    "const",
+    "continue",
+    "default",
+    "defer",
+    "else",
+    "fallthrough",
+    "false",
+    "for",
+    "func",
+    "go",
+    "goto",
+    "if",
+    "import",
+    "interface",
+    "map",
+    "nil",
+    "package",
+    "range",
+    "return",
+    "select",
+    "struct",
+    "switch",
+    "true",
+    "type",
+    "var",
+];
+const C_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "final",
+    "float",
+    "for",
+    "if",
+    "import",
+    "int",
+    "long",
+    "new",
+    "null",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "struct",
+    "switch",
+    "this",
+    "true",
+    "typedef",
+    "unsigned",
+    "void",
+    "while",
+];
+const SHELL_KEYWORDS: &[&str] = &[
+    "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if", "in",
+    "local", "return", "then", "while",
+];
+
+impl Syntax {
+    /// Rules for a file, by extension; `None` for text without syntax
+    pub fn for_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?;
+        let (line_comments, quotes, keywords): (&[&str], &[char], &[&str]) = match extension {
+            "rs" => (&["//"], &['"'], RUST_KEYWORDS),
+            "py" => (&["#"], &['"', '\''], PYTHON_KEYWORDS),
+            "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" => {
+                (&["//"], &['"', '\'', '`'], JS_KEYWORDS)
+            }
+            "go" => (&["//"], &['"', '`'], GO_KEYWORDS),
+            "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "kt" | "cs" | "swift" => {
+                (&["//"], &['"', '\''], C_KEYWORDS)
+            }
+            "sh" | "bash" | "zsh" => (&["#"], &['"', '\''], SHELL_KEYWORDS),
+            "toml" | "yaml" | "yml" => (&["#"], &['"', '\''], &["false", "true"]),
+            "sql" => (&["--"], &['\''], &[]),
+            _ => return None,
+        };
+        Some(Self {
+            line_comments,
+            quotes,
+            keywords,
+        })
+    }
+
+    /// Split a line into colored pieces
+    pub fn highlight<'a>(&self, line: &'a str) -> Vec<(Token, &'a str)> {
+        // (token, start, end) byte ranges; runs of plain text are merged
+        let mut ranges: Vec<(Token, usize, usize)> = Vec::new();
+        let mut start = 0;
+        while let Some(c) = line[start..].chars().next() {
+            let rest = &line[start..];
+            let (token, length) = if self
+                .line_comments
+                .iter()
+                .any(|comment| rest.starts_with(comment))
+            {
+                (Token::Comment, rest.len())
+            } else if self.quotes.contains(&c) {
+                let mut escaped = false;
+                let end = rest[1..]
+                    .char_indices()
+                    .find(|&(_, next)| {
+                        let closes = next == c && !escaped;
+                        escaped = next == '\\' && !escaped;
+                        closes
+                    })
+                    .map_or(rest.len(), |(index, _)| index + 2);
+                (Token::String, end)
+            } else if c.is_ascii_digit() {
+                let end = rest
+                    .find(|next: char| {
+                        !(next.is_ascii_alphanumeric() || next == '_' || next == '.')
+                    })
+                    .unwrap_or(rest.len());
+                (Token::Number, end)
+            } else if c.is_alphabetic() || c == '_' {
+                let end = rest
+                    .find(|next: char| !(next.is_alphanumeric() || next == '_'))
+                    .unwrap_or(rest.len());
+                match self.keywords.contains(&&rest[..end]) {
+                    true => (Token::Keyword, end),
+                    false => (Token::Plain, end),
+                }
+            } else {
+                (Token::Plain, c.len_utf8())
+            };
+
+            match ranges.last_mut() {
+                Some((Token::Plain, _, end)) if token == Token::Plain => *end += length,
+                _ => ranges.push((token, start, start + length)),
+            }
+            start += length;
+        }
+        ranges
+            .into_iter()
+            .map(|(token, start, end)| (token, &line[start..end]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let rust = Syntax::for_path("src/lib.rs").expect("Should know Rust");
+        assert_eq!(
+            rust.highlight(r#"let name = "a \"b\""; // 42"#),
+            vec![
+                (Token::Keyword, "let"),
+                (Token::Plain, " name = "),
+                (Token::String, r#""a \"b\"""#),
+                (Token::Plain, "; "),
+                (Token::Comment, "// 42"),
+            ]
+        );
+        assert_eq!(
+            rust.highlight("x1 + 2.5"),
+            vec![(Token::Plain, "x1 + "), (Token::Number, "2.5")]
+        );
+
+        let python = Syntax::for_path("app.py").unwrap();
+        assert_eq!(
+            python.highlight("def f(): # 'not a string'"),
+            vec![
+                (Token::Keyword, "def"),
+                (Token::Plain, " f(): "),
+                (Token::Comment, "# 'not a string'"),
+            ]
+        );
+        // An unterminated string runs to the end of the line
+        assert_eq!(
+            python.highlight("s = 'ab"),
+            vec![(Token::Plain, "s = "), (Token::String, "'ab")]
+        );
+        assert_eq!(Syntax::for_path("README"), None);
+    }
+}
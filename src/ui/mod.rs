@@ -0,0 +1,51 @@
+//! ratatui terminal interface for watching and steering a session
+
+mod app;
+mod compare;
+mod events;
+mod highlight;
+mod widgets;
+
+pub use app::{Action, App, LogLine, Mode, Prompt};
+pub use compare::{Comparison, Side};
+pub use events::{Event, Events};
+pub use widgets::progress_bar;
+
+use anyhow::{Context, Result};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::Terminal;
+use std::io::{stdout, Stdout};
+
+/// The terminal in raw mode on the alternate screen; restored on drop,
+/// including when a panic unwinds
+pub struct Tui {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Tui {
+    /// Take over the terminal
+    pub fn enter() -> Result<Self> {
+        enable_raw_mode().with_context(|| "Failed to enable raw terminal mode")?;
+        execute!(stdout(), EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+        Ok(Self { terminal })
+    }
+
+    /// Redraw the screen
+    pub fn draw(&mut self, app: &App) -> Result<()> {
+        self.terminal.draw(|frame| widgets::draw(frame, app))?;
+        Ok(())
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
@@ -0,0 +1,472 @@
+//! Drawing the TUI screens
+
+use chrono::Local;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+use super::app::{App, LogLine, Mode, Prompt};
+use super::compare::Comparison;
+use super::highlight::{Syntax, Token};
+use crate::database::{AgentRecord, AgentStatus, InteractionRecord};
+use crate::git::diff::{RowKind, SideLine};
+use crate::process::logs::{LogEntry, EVENT_STREAM};
+
+/// Cells of an agent's progress bar
+const BAR_WIDTH: usize = 10;
+
+/// Key bindings listed in the footer
+const CONTROLS: &str =
+    " Tab:Navigate Enter:Respond b:Brief p:Pause/Resume d:Dismiss l:Logs c:Compare h:Help q:Quit ";
+
+/// Draw the whole screen
+pub fn draw(frame: &mut Frame, app: &App) {
+    if let Mode::Logs {
+        agent,
+        entries,
+        scroll,
+    } = &app.mode
+    {
+        draw_full_log(frame, frame.area(), agent, entries, *scroll);
+        return;
+    }
+    if let Mode::Compare(comparison) = &app.mode {
+        draw_compare(frame, frame.area(), comparison, app.winner());
+        return;
+    }
+
+    let agents_height = app.agents.len().max(1) as u16 + 2;
+    let queue_height = app.questions.len().clamp(1, 6) as u16 + 2;
+    let [agents_area, queue_area, logs_area, footer_area] = Layout::vertical([
+        Constraint::Max(agents_height),
+        Constraint::Max(queue_height),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    draw_agents(frame, agents_area, app);
+    draw_queue(frame, queue_area, app);
+    draw_logs(frame, logs_area, app);
+    let footer = match &app.message {
+        Some(message) => Line::from(format!(" {}", message)).style(Style::new().fg(Color::Yellow)),
+        None => Line::from(CONTROLS).style(Style::new().add_modifier(Modifier::DIM)),
+    };
+    frame.render_widget(footer, footer_area);
+
+    match &app.mode {
+        Mode::Input { prompt, text } => draw_prompt(frame, prompt, text),
+        Mode::ConfirmDismiss { name, .. } => draw_popup(
+            frame,
+            " Dismiss ",
+            vec![Line::from(format!("Dismiss {}? (y/n)", name))],
+        ),
+        Mode::Help => draw_popup(frame, " Help ", help_lines()),
+        Mode::ChooseRival { left, cursor } => draw_rivals(frame, app, *left, *cursor),
+        Mode::Normal | Mode::Logs { .. } | Mode::Compare(_) => {}
+    }
+}
+
+/// Text progress bar such as `████████░░`
+pub fn progress_bar(progress: i64, width: usize) -> String {
+    let filled = (progress.clamp(0, 100) as usize * width + 50) / 100;
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+fn draw_agents(frame: &mut Frame, area: Rect, app: &App) {
+    let title = match &app.session {
+        Some(session) => format!(" Active Agents: session {} ", session.slug()),
+        None => " Active Agents ".to_string(),
+    };
+    let items: Vec<ListItem> = if app.agents.is_empty() {
+        let hint = match app.session {
+            Some(_) => "No agents in this session",
+            None => "No sessions yet. Run 'agentcrew deploy' to start one.",
+        };
+        vec![ListItem::new(hint)]
+    } else {
+        app.agents
+            .iter()
+            .map(|agent| {
+                let question = app
+                    .questions
+                    .iter()
+                    .find(|question| question.agent_id == agent.id);
+                let winner = app.winner() == Some(agent.id.as_str());
+                ListItem::new(agent_line(agent, question, winner))
+            })
+            .collect()
+    };
+    let list = List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state =
+        ListState::default().with_selected((!app.agents.is_empty()).then_some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn agent_line(
+    agent: &AgentRecord,
+    question: Option<&InteractionRecord>,
+    winner: bool,
+) -> Line<'static> {
+    let (symbol, detail, color) = match (agent.status, question) {
+        (_, Some(question)) => ("?", format!("\"{}\"", question.content), Color::Yellow),
+        (AgentStatus::Initializing, _) => ("…", "Starting".to_string(), Color::Gray),
+        (AgentStatus::Running, _) => ("↻", "Working...".to_string(), Color::Cyan),
+        (AgentStatus::Waiting, _) => ("…", "Waiting".to_string(), Color::Gray),
+        (AgentStatus::Paused, _) => ("⏸", "Paused".to_string(), Color::Magenta),
+        (AgentStatus::Completed, _) => ("✓", "Complete".to_string(), Color::Green),
+        (AgentStatus::Failed, _) => ("✗", "Failed".to_string(), Color::Red),
+        (AgentStatus::Dismissed, _) => ("–", "Dismissed".to_string(), Color::DarkGray),
+    };
+    Line::from(vec![
+        Span::raw(format!("{:<12} ", agent.name())),
+        Span::styled(
+            progress_bar(agent.progress, BAR_WIDTH),
+            Style::new().fg(color),
+        ),
+        Span::raw(format!(" {:>3}% ", agent.progress)),
+        Span::styled(format!("{} {}", symbol, detail), Style::new().fg(color)),
+        Span::raw(if winner { "  🏆 winner" } else { "" }),
+    ])
+}
+
+fn draw_queue(frame: &mut Frame, area: Rect, app: &App) {
+    let items: Vec<ListItem> = if app.questions.is_empty() {
+        vec![ListItem::new("No pending questions")]
+    } else {
+        app.questions
+            .iter()
+            .map(|question| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(
+                        format!("[{}]", app.asker(question)),
+                        Style::new().fg(Color::Yellow),
+                    ),
+                    Span::raw(format!(": {}", question.content)),
+                ]))
+            })
+            .collect()
+    };
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" Interaction Queue ({}) ", app.questions.len())))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state =
+        ListState::default().with_selected((!app.questions.is_empty()).then_some(app.question));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_logs(frame: &mut Frame, area: Rect, app: &App) {
+    let height = area.height.saturating_sub(2) as usize;
+    let end = app.logs.len().saturating_sub(app.log_scroll);
+    let start = end.saturating_sub(height);
+    let lines: Vec<Line> = app
+        .logs
+        .range(start..end)
+        .map(|line| log_line(Some(line), &line.entry))
+        .collect();
+    let title = match app.log_scroll {
+        0 => " Logs ".to_string(),
+        n => format!(" Logs (scrolled up {} lines, End to follow) ", n),
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(title)),
+        area,
+    );
+}
+
+fn log_line(owner: Option<&LogLine>, entry: &LogEntry) -> Line<'static> {
+    let style = match entry.stream.as_str() {
+        "stderr" => Style::new().fg(Color::Red),
+        EVENT_STREAM => Style::new().fg(Color::DarkGray),
+        _ => Style::new(),
+    };
+    let mut spans = vec![Span::styled(
+        entry
+            .timestamp
+            .with_timezone(&Local)
+            .format("%H:%M ")
+            .to_string(),
+        Style::new().add_modifier(Modifier::DIM),
+    )];
+    if let Some(owner) = owner {
+        spans.push(Span::styled(
+            format!("{}: ", owner.agent),
+            Style::new().fg(Color::Cyan),
+        ));
+    }
+    spans.push(Span::styled(entry.text.clone(), style));
+    Line::from(spans)
+}
+
+fn draw_full_log(frame: &mut Frame, area: Rect, agent: &str, entries: &[LogEntry], scroll: usize) {
+    let [log_area, footer_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(area);
+    let height = log_area.height.saturating_sub(2) as usize;
+    let end = entries.len().saturating_sub(scroll);
+    let start = end.saturating_sub(height);
+    let lines: Vec<Line> = entries[start..end]
+        .iter()
+        .map(|entry| log_line(None, entry))
+        .collect();
+    let title = format!(
+        " Logs: {} (lines {}-{} of {}) ",
+        agent,
+        start + 1,
+        end,
+        entries.len()
+    );
+    let body = if entries.is_empty() {
+        Paragraph::new("No logs yet")
+    } else {
+        Paragraph::new(lines)
+    };
+    frame.render_widget(body.block(Block::bordered().title(title)), log_area);
+    frame.render_widget(
+        Line::from(" ↑/↓ PgUp/PgDn Home/End: Scroll  Esc: Back ")
+            .style(Style::new().add_modifier(Modifier::DIM)),
+        footer_area,
+    );
+}
+
+fn draw_prompt(frame: &mut Frame, prompt: &Prompt, text: &str) {
+    let (title, mut lines) = match prompt {
+        Prompt::Answer { name, question, .. } => (
+            format!(" Answer {} ", name),
+            vec![Line::from(question.clone()), Line::from("")],
+        ),
+        Prompt::Brief => (" Brief all agents ".to_string(), Vec::new()),
+    };
+    lines.push(Line::from(format!("> {}█", text)));
+    lines.push(Line::from(""));
+    lines.push(
+        Line::from("Enter: Send  Esc: Cancel").style(Style::new().add_modifier(Modifier::DIM)),
+    );
+    draw_popup(frame, &title, lines);
+}
+
+fn help_lines() -> Vec<Line<'static>> {
+    [
+        "Tab / Shift+Tab  Select agent",
+        "↑ / ↓            Select question",
+        "Enter            Answer selected question",
+        "b                Brief all agents",
+        "p                Pause / resume selected agent",
+        "d                Dismiss selected agent",
+        "l                Full logs of selected agent",
+        "c                Compare selected agent with another",
+        "PgUp / PgDn      Scroll logs (End follows)",
+        "q                Quit",
+    ]
+    .into_iter()
+    .map(Line::from)
+    .collect()
+}
+
+fn draw_rivals(frame: &mut Frame, app: &App, left: usize, cursor: usize) {
+    let Some(agent) = app.agents.get(left) else {
+        return;
+    };
+    let lines = app
+        .rivals(agent)
+        .enumerate()
+        .map(|(index, rival)| {
+            let line = Line::from(format!("  {}", rival.name()));
+            match index == cursor {
+                true => line.style(Style::new().add_modifier(Modifier::REVERSED)),
+                false => line,
+            }
+        })
+        .collect();
+    draw_popup(frame, &format!(" Compare {} with ", agent.name()), lines);
+}
+
+fn draw_compare(frame: &mut Frame, area: Rect, comparison: &Comparison, winner: Option<&str>) {
+    let [main_area, footer_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(area);
+    let [files_area, left_area, right_area] = Layout::horizontal([
+        Constraint::Percentage(20),
+        Constraint::Percentage(40),
+        Constraint::Percentage(40),
+    ])
+    .areas(main_area);
+
+    let items: Vec<ListItem> = comparison
+        .files
+        .iter()
+        .map(|file| {
+            ListItem::new(format!(
+                "{} {} +{} -{}",
+                file.change.kind.letter(),
+                file.change.path,
+                file.change.additions,
+                file.change.deletions
+            ))
+        })
+        .collect();
+    let files = List::new(items)
+        .block(Block::bordered().title(format!(" Files ({}) ", comparison.files.len())))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default()
+        .with_selected((!comparison.files.is_empty()).then_some(comparison.file));
+    frame.render_stateful_widget(files, files_area, &mut state);
+
+    let height = left_area.height.saturating_sub(2) as usize;
+    let (left_lines, right_lines) = match comparison.current() {
+        Some(file) if file.rows.is_empty() => (
+            vec![Line::from("Binary file or no textual changes")],
+            Vec::new(),
+        ),
+        Some(file) => {
+            let syntax = Syntax::for_path(&file.change.path);
+            file.rows
+                .iter()
+                .skip(comparison.scroll)
+                .take(height)
+                .map(|row| {
+                    (
+                        side_line(&row.kind, row.left.as_ref(), syntax, true),
+                        side_line(&row.kind, row.right.as_ref(), syntax, false),
+                    )
+                })
+                .unzip()
+        }
+        None => (
+            vec![Line::from("The branches have no differences")],
+            Vec::new(),
+        ),
+    };
+    let hunks = comparison.hunks();
+    let position = match hunks.iter().rposition(|&row| row <= comparison.scroll) {
+        Some(index) => format!(" hunk {}/{} ", index + 1, hunks.len()),
+        None => String::new(),
+    };
+    for (side, lines, side_area) in [
+        (&comparison.left, left_lines, left_area),
+        (&comparison.right, right_lines, right_area),
+    ] {
+        let mut title = format!(" {} ", side.name);
+        if winner == Some(side.agent_id.as_str()) {
+            title.push_str("🏆 ");
+        }
+        let block = Block::bordered()
+            .title(title)
+            .title_bottom(Line::from(position.clone()).right_aligned());
+        frame.render_widget(Paragraph::new(lines).block(block), side_area);
+    }
+
+    frame.render_widget(
+        Line::from(
+            " Tab/[ ]:File  ↑/↓ PgUp/PgDn:Scroll  n/N:Next/prev hunk  1/2:Mark winner  Esc:Back ",
+        )
+        .style(Style::new().add_modifier(Modifier::DIM)),
+        footer_area,
+    );
+}
+
+/// One side of a side-by-side row: line number and colored text, on a red
+/// or green background where the line was removed or added
+fn side_line(
+    kind: &RowKind,
+    line: Option<&SideLine>,
+    syntax: Option<Syntax>,
+    left: bool,
+) -> Line<'static> {
+    if let RowKind::Hunk(header) = kind {
+        return Line::from(header.clone()).style(Style::new().fg(Color::Cyan));
+    }
+    let Some(line) = line else {
+        return Line::from("");
+    };
+    let background = match (kind, left) {
+        (RowKind::Removed | RowKind::Changed, true) => Some(Color::Indexed(52)),
+        (RowKind::Added | RowKind::Changed, false) => Some(Color::Indexed(22)),
+        _ => None,
+    };
+    let base = background.map_or(Style::new(), |color| Style::new().bg(color));
+
+    let mut spans = vec![Span::styled(
+        format!("{:>4} ", line.number),
+        Style::new().add_modifier(Modifier::DIM),
+    )];
+    match syntax {
+        Some(syntax) => spans.extend(syntax.highlight(&line.text).into_iter().map(
+            |(token, text)| {
+                let style = match token {
+                    Token::Plain => base,
+                    Token::Keyword => base.fg(Color::Magenta),
+                    Token::String => base.fg(Color::Green),
+                    Token::Number => base.fg(Color::Yellow),
+                    Token::Comment => base.fg(Color::DarkGray),
+                };
+                Span::styled(text.to_string(), style)
+            },
+        )),
+        None => spans.push(Span::styled(line.text.clone(), base)),
+    }
+    Line::from(spans)
+}
+
+/// Centered box drawn over the screen
+fn draw_popup(frame: &mut Frame, title: &str, lines: Vec<Line<'static>>) {
+    let area = frame.area();
+    let width = area.width.saturating_sub(4).min(72);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(title.to_string())),
+        popup,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(80, 10), "████████░░");
+        assert_eq!(progress_bar(100, 10), "██████████");
+        assert_eq!(progress_bar(-5, 4), "░░░░");
+        assert_eq!(progress_bar(250, 4), "████");
+    }
+
+    #[test]
+    fn test_draw_logs() {
+        let mut app = App::new();
+        app.push_logs(vec![LogLine {
+            agent: "mock-1".to_string(),
+            entry: LogEntry {
+                timestamp: Utc::now(),
+                stream: "stdout".to_string(),
+                text: "Created auth middleware".to_string(),
+            },
+        }]);
+        let mut terminal = Terminal::new(TestBackend::new(60, 14)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        assert!(screen.contains("No sessions yet"));
+        assert!(screen.contains("Interaction Queue (0)"));
+        assert!(screen.contains("mock-1: Created auth middleware"));
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use git2::build::CheckoutBuilder;
+use git2::{IndexAddOption, Oid, Repository, ResetType, Signature};
+use std::path::Path;
+
+use crate::database::{AgentRecord, ChangeType, Database, FileChangeRecord, InteractionType};
+use crate::git::diff::{self, ChangeKind, FileChange};
+
+/// Email used for commits made on behalf of agents
+const CHECKPOINT_EMAIL: &str = "agentcrew@local";
+
+/// A commit made by checkpointing an agent's worktree
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub commit: Oid,
+    pub changes: Vec<FileChange>,
+}
+
+/// Stage everything in the agent's worktree and commit it as the agent, then
+/// record the touched files and a `checkpoint` interaction. Returns `None`
+/// when there is nothing to commit.
+pub async fn checkpoint(
+    db: &Database,
+    agent: &AgentRecord,
+    message: &str,
+) -> Result<Option<Checkpoint>> {
+    let worktree = agent
+        .worktree_path
+        .as_deref()
+        .map(Path::new)
+        .filter(|path| path.exists())
+        .ok_or_else(|| anyhow::anyhow!("{} has no worktree", agent.name()))?;
+
+    let Some(checkpoint) = commit_worktree(worktree, &agent.name(), message)? else {
+        return Ok(None);
+    };
+
+    let commit_hash = checkpoint.commit.to_string();
+    let timestamp = Utc::now();
+    let records: Vec<FileChangeRecord> = checkpoint
+        .changes
+        .iter()
+        .map(|change| FileChangeRecord {
+            id: 0,
+            agent_id: agent.id.clone(),
+            session_id: agent.session_id.clone(),
+            file_path: change.path.clone(),
+            change_type: change_type(change.kind),
+            lines_added: change.additions as i64,
+            lines_removed: change.deletions as i64,
+            commit_hash: Some(commit_hash.clone()),
+            timestamp,
+        })
+        .collect();
+    db.record_file_changes(&records).await?;
+
+    let metadata = serde_json::json!({
+        "commit": commit_hash,
+        "files": checkpoint.changes.len(),
+        "lines_added": checkpoint.changes.iter().map(|change| change.additions).sum::<usize>(),
+        "lines_removed": checkpoint.changes.iter().map(|change| change.deletions).sum::<usize>(),
+    });
+    db.record_interaction(
+        &agent.id,
+        &agent.session_id,
+        InteractionType::Checkpoint,
+        message,
+        Some(&metadata.to_string()),
+    )
+    .await?;
+
+    Ok(Some(checkpoint))
+}
+
+/// Commit all changes of the worktree at `path` on its checked out branch
+/// with `<agent_name> <agentcrew@local>` as author and committer
+fn commit_worktree(path: &Path, agent_name: &str, message: &str) -> Result<Option<Checkpoint>> {
+    let repo = Repository::open(path)
+        .with_context(|| format!("Failed to open worktree {}", path.display()))?;
+    let mut index = repo.index()?;
+    index
+        .add_all(["*"], IndexAddOption::DEFAULT, None)
+        .with_context(|| "Failed to stage changes")?;
+    index.update_all(["*"], None)?;
+    index.write()?;
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let parent = repo
+        .head()
+        .with_context(|| "Failed to get HEAD reference")?
+        .peel_to_commit()?;
+    if parent.tree_id() == tree.id() {
+        return Ok(None);
+    }
+
+    let signature = Signature::now(agent_name, CHECKPOINT_EMAIL)?;
+    let commit = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &[&parent],
+    )?;
+    let changes = diff::file_changes(&diff::diff_commits(
+        &repo,
+        &parent,
+        &repo.find_commit(commit)?,
+    )?)?;
+
+    Ok(Some(Checkpoint { commit, changes }))
+}
+
+/// Hard-reset the worktree at `path` to `commit`, discarding uncommitted and
+/// untracked (but not ignored) files
+pub fn rollback(path: &Path, commit: Oid) -> Result<()> {
+    let repo = Repository::open(path)
+        .with_context(|| format!("Failed to open worktree {}", path.display()))?;
+    let target = repo
+        .find_commit(commit)
+        .with_context(|| format!("Checkpoint {} not found", commit))?;
+    repo.reset(target.as_object(), ResetType::Hard, None)
+        .with_context(|| format!("Failed to reset {} to {}", path.display(), commit))?;
+    // A hard reset keeps untracked files; remove them like `git clean -fd`
+    repo.checkout_head(Some(CheckoutBuilder::new().force().remove_untracked(true)))
+        .with_context(|| format!("Failed to clean {}", path.display()))?;
+    Ok(())
+}
+
+/// `file_changes.change_type` of a diff entry
+fn change_type(kind: ChangeKind) -> ChangeType {
+    match kind {
+        ChangeKind::Added => ChangeType::Created,
+        ChangeKind::Modified => ChangeType::Modified,
+        ChangeKind::Deleted => ChangeType::Deleted,
+        ChangeKind::Renamed => ChangeType::Renamed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worktree::manager::tests::init_repo;
+    use crate::worktree::WorktreeManager;
+    use std::collections::BTreeMap;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_checkpoint_records_changes() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let repo = init_repo(&repo_dir);
+        let manager = WorktreeManager::new(&repo_dir, temp_dir.path().join("worktrees"));
+        let base = repo.head().unwrap().target().unwrap();
+        let mut batch = manager.begin("abcd1234", base).expect("Should begin");
+        let worktree = batch.create("mock-1").expect("Should create worktree");
+        batch.commit();
+
+        let db = Database::new(&temp_dir.path().join("test.db"))
+            .await
+            .expect("Should create database");
+        let session = db
+            .create_session("build it", r#"{"mock":1}"#, "main", &base.to_string())
+            .await
+            .unwrap();
+        let agent = db
+            .create_agent(&session.id, "mock", 1, &BTreeMap::new())
+            .await
+            .unwrap();
+        db.set_agent_worktree(&agent.id, &worktree.path, &worktree.branch)
+            .await
+            .unwrap();
+        let agent = db.get_agent(&agent.id).await.unwrap();
+
+        std::fs::write(worktree.path.join("README.md"), "hello\nworld\n").unwrap();
+        std::fs::create_dir(worktree.path.join("src")).unwrap();
+        std::fs::write(worktree.path.join("src/lib.rs"), "// new\n").unwrap();
+
+        let first = checkpoint(&db, &agent, "First steps")
+            .await
+            .expect("Should checkpoint")
+            .expect("Should commit changes");
+        let commit = repo.find_commit(first.commit).unwrap();
+        assert_eq!(commit.author().name(), Some("mock-1"));
+        assert_eq!(commit.author().email(), Some(CHECKPOINT_EMAIL));
+        assert_eq!(
+            repo.find_branch(&worktree.branch, git2::BranchType::Local)
+                .unwrap()
+                .get()
+                .target(),
+            Some(first.commit)
+        );
+
+        let changes = db.list_file_changes(&agent.id).await.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].file_path, "README.md");
+        assert_eq!(changes[0].change_type, ChangeType::Modified);
+        assert_eq!(changes[0].lines_added, 1);
+        assert_eq!(changes[1].file_path, "src/lib.rs");
+        assert_eq!(changes[1].change_type, ChangeType::Created);
+        assert_eq!(
+            changes[1].commit_hash.as_deref(),
+            Some(first.commit.to_string().as_str())
+        );
+
+        let interactions = db.list_interactions(&agent.id).await.unwrap();
+        assert_eq!(interactions.len(), 1);
+        assert_eq!(
+            interactions[0].interaction_type,
+            InteractionType::Checkpoint
+        );
+        assert_eq!(interactions[0].content, "First steps");
+
+        assert!(checkpoint(&db, &agent, "Nothing new")
+            .await
+            .expect("Should check for changes")
+            .is_none());
+
+        std::fs::write(worktree.path.join("README.md"), "rewritten\n").unwrap();
+        std::fs::write(worktree.path.join("scratch.txt"), "junk\n").unwrap();
+        checkpoint(&db, &agent, "Second")
+            .await
+            .expect("Should checkpoint")
+            .expect("Should commit changes");
+        std::fs::write(worktree.path.join("later.txt"), "uncommitted\n").unwrap();
+
+        rollback(&worktree.path, first.commit).expect("Should roll back");
+        assert_eq!(
+            std::fs::read_to_string(worktree.path.join("README.md")).unwrap(),
+            "hello\nworld\n"
+        );
+        assert!(!worktree.path.join("scratch.txt").exists());
+        assert!(!worktree.path.join("later.txt").exists());
+        assert_eq!(
+            Repository::open(&worktree.path)
+                .unwrap()
+                .head()
+                .unwrap()
+                .target(),
+            Some(first.commit)
+        );
+        assert_eq!(db.list_checkpoints(&agent.id).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,592 @@
+use anyhow::{Context, Result};
+use git2::{BranchType, Oid, Repository, ResetType, WorktreeAddOptions, WorktreePruneOptions};
+use std::path::{Path, PathBuf};
+
+use crate::git::diff::short_id;
+use crate::git::GitUtils;
+
+/// Prefix shared by every branch agentcrew creates
+pub const BRANCH_PREFIX: &str = "agentcrew";
+
+/// A worktree checked out for a single agent instance
+#[derive(Debug, Clone)]
+pub struct AgentWorktree {
+    /// Agent instance name such as `claude-1`
+    pub agent_name: String,
+    /// Local branch checked out in the worktree
+    pub branch: String,
+    /// Working directory of the worktree
+    pub path: PathBuf,
+    /// Name git uses to track the worktree under `.git/worktrees/`
+    pub worktree_name: String,
+}
+
+/// A worktree git has registered for the repository
+#[derive(Debug, Clone)]
+pub struct RegisteredWorktree {
+    /// Name under `.git/worktrees/`
+    pub name: String,
+    /// Working directory git recorded for the worktree
+    pub path: PathBuf,
+    /// Whether that directory still exists
+    pub exists: bool,
+}
+
+/// Creates and removes per-agent branches and worktrees
+pub struct WorktreeManager {
+    repo_root: PathBuf,
+    worktrees_dir: PathBuf,
+}
+
+impl WorktreeManager {
+    /// Create a manager for the repository at `repo_root` placing worktrees in `worktrees_dir`
+    pub fn new(repo_root: impl Into<PathBuf>, worktrees_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            repo_root: repo_root.into(),
+            worktrees_dir: worktrees_dir.into(),
+        }
+    }
+
+    /// Open the main repository
+    pub fn open(&self) -> Result<Repository> {
+        GitUtils::find_git_repository(&self.repo_root)
+    }
+
+    /// Branch name used for an agent, e.g. `agentcrew/1a2b3c4d/claude-1`
+    pub fn branch_name(session_slug: &str, agent_name: &str) -> String {
+        format!("{}/{}/{}", BRANCH_PREFIX, session_slug, agent_name)
+    }
+
+    /// Name git uses for an agent worktree (may not contain slashes)
+    pub fn worktree_name(session_slug: &str, agent_name: &str) -> String {
+        format!("{}-{}-{}", BRANCH_PREFIX, session_slug, agent_name)
+    }
+
+    /// Directory an agent worktree is checked out into
+    pub fn worktree_path(&self, session_slug: &str, agent_name: &str) -> PathBuf {
+        self.worktrees_dir.join(session_slug).join(agent_name)
+    }
+
+    /// Commit currently checked out in the main repository
+    pub fn head_commit(&self) -> Result<Oid> {
+        let repo = self.open()?;
+        let commit = repo
+            .head()
+            .and_then(|head| head.peel_to_commit())
+            .with_context(|| "Repository has no commits yet. Create an initial commit first.")?;
+        Ok(commit.id())
+    }
+
+    /// Start provisioning worktrees for a session, forking from `base`
+    pub fn begin(&self, session_slug: &str, base: Oid) -> Result<WorktreeBatch<'_>> {
+        let repo = self.open()?;
+        repo.find_commit(base)
+            .with_context(|| format!("Base commit {} not found", base))?;
+
+        Ok(WorktreeBatch {
+            manager: self,
+            repo,
+            base,
+            session_slug: session_slug.to_string(),
+            created: Vec::new(),
+            committed: false,
+        })
+    }
+
+    /// Check out an agent's branch at `commit`, recreating the branch and
+    /// worktree if they are gone. An existing worktree is reset to `commit`
+    /// unless it has uncommitted changes. Commits on the branch that
+    /// `commit` does not contain are only discarded with `force`.
+    pub fn restore(
+        &self,
+        session_slug: &str,
+        agent_name: &str,
+        commit: Oid,
+        force: bool,
+    ) -> Result<AgentWorktree> {
+        let worktree = AgentWorktree {
+            agent_name: agent_name.to_string(),
+            branch: WorktreeManager::branch_name(session_slug, agent_name),
+            path: self.worktree_path(session_slug, agent_name),
+            worktree_name: WorktreeManager::worktree_name(session_slug, agent_name),
+        };
+        let repo = self.open()?;
+        let target = repo
+            .find_commit(commit)
+            .with_context(|| format!("Commit {} not found in this repository", commit))?;
+
+        if !force {
+            let dropped = Self::commits_not_in(&repo, &worktree.branch, commit)?;
+            if !dropped.is_empty() {
+                anyhow::bail!(
+                    "{} has {} commit(s) that {} does not contain; restoring would discard:\n{}\nUse --force to discard them.",
+                    worktree.branch,
+                    dropped.len(),
+                    short_id(commit),
+                    dropped.join("\n")
+                );
+            }
+        }
+
+        if worktree.path.exists() {
+            let checkout = Repository::open(&worktree.path)
+                .with_context(|| format!("Failed to open worktree {}", worktree.path.display()))?;
+            if checkout.head().ok().and_then(|head| head.target()) != Some(commit) {
+                if GitUtils::is_dirty(&worktree.path)? {
+                    anyhow::bail!(
+                        "{} has uncommitted changes; checkpoint or discard them first",
+                        worktree.path.display()
+                    );
+                }
+                checkout
+                    .reset(
+                        checkout.find_commit(commit)?.as_object(),
+                        ResetType::Hard,
+                        None,
+                    )
+                    .with_context(|| {
+                        format!("Failed to reset {} to {}", worktree.branch, commit)
+                    })?;
+            }
+            return Ok(worktree);
+        }
+
+        // Forget a worktree whose directory was deleted
+        self.remove(&repo, &worktree, false)?;
+        let branch = repo
+            .branch(&worktree.branch, &target, true)
+            .with_context(|| format!("Failed to create branch {}", worktree.branch))?;
+        if let Some(parent) = worktree.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        let mut opts = WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        repo.worktree(&worktree.worktree_name, &worktree.path, Some(&opts))
+            .with_context(|| format!("Failed to create worktree at {}", worktree.path.display()))?;
+
+        Ok(worktree)
+    }
+
+    /// One line per commit on `branch` that `commit` does not contain,
+    /// newest first; empty when the branch does not exist
+    fn commits_not_in(repo: &Repository, branch: &str, commit: Oid) -> Result<Vec<String>> {
+        let Some(tip) = repo
+            .find_branch(branch, BranchType::Local)
+            .ok()
+            .and_then(|branch| branch.get().target())
+        else {
+            return Ok(Vec::new());
+        };
+        let mut walk = repo.revwalk()?;
+        walk.push(tip)?;
+        walk.hide(commit)?;
+        walk.map(|oid| {
+            let found = repo.find_commit(oid?)?;
+            Ok(format!(
+                "  {} {}",
+                short_id(found.id()),
+                found.summary().unwrap_or_default()
+            ))
+        })
+        .collect()
+    }
+
+    /// Worktrees git knows about that agentcrew created: those named like
+    /// ours or checked out below the worktrees directory
+    pub fn registered(&self, repo: &Repository) -> Result<Vec<RegisteredWorktree>> {
+        let prefix = format!("{}-", BRANCH_PREFIX);
+        let mut registered = Vec::new();
+        for name in repo.worktrees()?.iter().flatten() {
+            let worktree = repo
+                .find_worktree(name)
+                .with_context(|| format!("Failed to open worktree {}", name))?;
+            let path = worktree.path().to_path_buf();
+            if name.starts_with(&prefix) || path.starts_with(&self.worktrees_dir) {
+                registered.push(RegisteredWorktree {
+                    name: name.to_string(),
+                    exists: path.exists(),
+                    path,
+                });
+            }
+        }
+        Ok(registered)
+    }
+
+    /// Register an existing worktree directory with git again after its
+    /// administrative files under `.git/worktrees/` were lost. Uncommitted
+    /// changes in the directory are kept; the index is rebuilt from the branch.
+    pub fn reattach(&self, repo: &Repository, worktree: &AgentWorktree) -> Result<()> {
+        if repo.find_worktree(&worktree.worktree_name).is_ok() {
+            anyhow::bail!("Worktree {} is already registered", worktree.worktree_name);
+        }
+        repo.find_branch(&worktree.branch, BranchType::Local)
+            .with_context(|| format!("Branch {} not found", worktree.branch))?;
+        let path = worktree
+            .path
+            .canonicalize()
+            .with_context(|| format!("Worktree {} not found", worktree.path.display()))?;
+
+        let admin = repo.path().join("worktrees").join(&worktree.worktree_name);
+        std::fs::create_dir_all(&admin)
+            .with_context(|| format!("Failed to create directory: {}", admin.display()))?;
+        let files = [
+            (
+                admin.join("gitdir"),
+                format!("{}\n", path.join(".git").display()),
+            ),
+            (admin.join("commondir"), "../..\n".to_string()),
+            (
+                admin.join("HEAD"),
+                format!("ref: refs/heads/{}\n", worktree.branch),
+            ),
+            (path.join(".git"), format!("gitdir: {}\n", admin.display())),
+        ];
+        for (file, content) in &files {
+            std::fs::write(file, content)
+                .with_context(|| format!("Failed to write {}", file.display()))?;
+        }
+
+        let checkout = Repository::open(&path)
+            .with_context(|| format!("Failed to open worktree {}", path.display()))?;
+        let tree = checkout.head()?.peel_to_tree()?;
+        let mut index = checkout.index()?;
+        index.read_tree(&tree)?;
+        index
+            .write()
+            .with_context(|| format!("Failed to write index of {}", worktree.worktree_name))
+    }
+
+    /// Unregister the worktree `name` and delete its directory `path`
+    pub fn prune(&self, repo: &Repository, name: &str, path: &Path) -> Result<()> {
+        if let Ok(wt) = repo.find_worktree(name) {
+            let mut opts = WorktreePruneOptions::new();
+            opts.valid(true).locked(true).working_tree(true);
+            wt.prune(Some(&mut opts))
+                .with_context(|| format!("Failed to prune worktree {}", name))?;
+        }
+
+        if path.exists() {
+            std::fs::remove_dir_all(path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+        remove_empty_parent(path, &self.worktrees_dir);
+        Ok(())
+    }
+
+    /// Remove a worktree and, if given, its branch
+    pub fn remove(
+        &self,
+        repo: &Repository,
+        worktree: &AgentWorktree,
+        delete_branch: bool,
+    ) -> Result<()> {
+        self.prune(repo, &worktree.worktree_name, &worktree.path)?;
+
+        if delete_branch {
+            if let Ok(mut branch) = repo.find_branch(&worktree.branch, BranchType::Local) {
+                branch
+                    .delete()
+                    .with_context(|| format!("Failed to delete branch {}", worktree.branch))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Worktrees provisioned for one session.
+///
+/// Everything created through the batch is removed again (worktrees and
+/// branches) unless [`WorktreeBatch::commit`] is called, so a deploy that
+/// fails partway leaves the repository as it found it.
+pub struct WorktreeBatch<'a> {
+    manager: &'a WorktreeManager,
+    repo: Repository,
+    base: Oid,
+    session_slug: String,
+    created: Vec<AgentWorktree>,
+    committed: bool,
+}
+
+impl WorktreeBatch<'_> {
+    /// Create the branch and worktree for one agent instance
+    pub fn create(&mut self, agent_name: &str) -> Result<AgentWorktree> {
+        let worktree = AgentWorktree {
+            agent_name: agent_name.to_string(),
+            branch: WorktreeManager::branch_name(&self.session_slug, agent_name),
+            path: self.manager.worktree_path(&self.session_slug, agent_name),
+            worktree_name: WorktreeManager::worktree_name(&self.session_slug, agent_name),
+        };
+
+        if worktree.path.exists() {
+            anyhow::bail!("Worktree path already exists: {}", worktree.path.display());
+        }
+
+        let base = self.repo.find_commit(self.base)?;
+        let branch = self
+            .repo
+            .branch(&worktree.branch, &base, false)
+            .with_context(|| format!("Failed to create branch {}", worktree.branch))?;
+
+        // Track the branch right away so a failing worktree add still removes it
+        self.created.push(worktree.clone());
+
+        if let Some(parent) = worktree.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let mut opts = WorktreeAddOptions::new();
+        opts.reference(Some(branch.get()));
+        self.repo
+            .worktree(&worktree.worktree_name, &worktree.path, Some(&opts))
+            .with_context(|| format!("Failed to create worktree at {}", worktree.path.display()))?;
+
+        Ok(worktree)
+    }
+
+    /// Keep everything created so far and return it
+    pub fn commit(mut self) -> Vec<AgentWorktree> {
+        self.committed = true;
+        std::mem::take(&mut self.created)
+    }
+
+    /// Remove every worktree and branch created by this batch
+    pub fn rollback(mut self) -> Result<()> {
+        self.committed = true;
+        self.remove_created()
+    }
+
+    fn remove_created(&mut self) -> Result<()> {
+        let mut first_error = None;
+        while let Some(worktree) = self.created.pop() {
+            if let Err(e) = self.manager.remove(&self.repo, &worktree, true) {
+                first_error.get_or_insert(e);
+            }
+        }
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for WorktreeBatch<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Err(e) = self.remove_created() {
+                eprintln!("⚠️  Failed to roll back worktrees: {:#}", e);
+            }
+        }
+    }
+}
+
+/// Remove `path`'s parent if it is an empty directory below `root`
+fn remove_empty_parent(path: &Path, root: &Path) {
+    if let Some(parent) = path.parent() {
+        if parent != root && parent.starts_with(root) {
+            // Only succeeds when the directory is empty
+            let _ = std::fs::remove_dir(parent);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use git2::Signature;
+    use tempfile::TempDir;
+
+    /// Create a repository with a single commit on `main`
+    pub(crate) fn init_repo(dir: &Path) -> Repository {
+        let repo = Repository::init(dir).expect("Should init repo");
+        std::fs::write(dir.join("README.md"), "hello\n").expect("Should write file");
+        {
+            let mut index = repo.index().expect("Should open index");
+            index
+                .add_path(Path::new("README.md"))
+                .expect("Should stage file");
+            index.write().expect("Should write index");
+            let tree_id = index.write_tree().expect("Should write tree");
+            let tree = repo.find_tree(tree_id).expect("Should find tree");
+            let sig = Signature::now("test", "test@example.com").expect("Should create signature");
+            repo.commit(Some("refs/heads/main"), &sig, &sig, "initial", &tree, &[])
+                .expect("Should commit");
+            repo.set_head("refs/heads/main").expect("Should set HEAD");
+        }
+        repo
+    }
+
+    #[test]
+    fn test_create_worktrees() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = init_repo(temp_dir.path());
+        let manager = WorktreeManager::new(
+            temp_dir.path(),
+            temp_dir.path().join(".agentcrew/worktrees"),
+        );
+
+        let mut batch = manager
+            .begin(
+                "abcd1234",
+                manager.head_commit().expect("Should resolve HEAD"),
+            )
+            .expect("Should begin batch");
+        batch.create("claude-1").expect("Should create worktree");
+        batch.create("gpt-1").expect("Should create worktree");
+        let created = batch.commit();
+
+        assert_eq!(created.len(), 2);
+        assert_eq!(created[0].branch, "agentcrew/abcd1234/claude-1");
+        assert!(created[0].path.join("README.md").exists());
+        assert!(repo
+            .find_branch("agentcrew/abcd1234/gpt-1", BranchType::Local)
+            .is_ok());
+        assert!(repo.find_worktree("agentcrew-abcd1234-gpt-1").is_ok());
+    }
+
+    #[test]
+    fn test_failed_batch_rolls_back() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = init_repo(temp_dir.path());
+        let manager = WorktreeManager::new(
+            temp_dir.path(),
+            temp_dir.path().join(".agentcrew/worktrees"),
+        );
+
+        {
+            let mut batch = manager
+                .begin(
+                    "abcd1234",
+                    manager.head_commit().expect("Should resolve HEAD"),
+                )
+                .expect("Should begin batch");
+            batch.create("claude-1").expect("Should create worktree");
+            // Creating the same agent twice fails partway
+            assert!(batch.create("claude-1").is_err());
+        }
+
+        assert!(repo
+            .find_branch("agentcrew/abcd1234/claude-1", BranchType::Local)
+            .is_err());
+        assert!(repo.find_worktree("agentcrew-abcd1234-claude-1").is_err());
+        assert!(!manager.worktree_path("abcd1234", "claude-1").exists());
+    }
+
+    #[test]
+    fn test_restore_worktree_at_commit() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = init_repo(temp_dir.path());
+        let manager = WorktreeManager::new(
+            temp_dir.path(),
+            temp_dir.path().join(".agentcrew/worktrees"),
+        );
+        let base = manager.head_commit().expect("Should resolve HEAD");
+        let mut batch = manager.begin("abcd1234", base).expect("Should begin batch");
+        let worktree = batch.create("claude-1").expect("Should create worktree");
+        batch.commit();
+        let tip = crate::git::diff::tests::commit_files(
+            &repo,
+            &worktree.branch,
+            Some(base),
+            &[("work.rs", Some("done\n"))],
+        );
+
+        // Worktree and branch are both gone
+        manager
+            .remove(&repo, &worktree, true)
+            .expect("Should remove worktree");
+        let restored = manager
+            .restore("abcd1234", "claude-1", tip, false)
+            .expect("Should recreate worktree");
+        assert_eq!(restored.path, worktree.path);
+        assert!(restored.path.join("work.rs").exists());
+
+        // Moving back would drop the commit made on top of base
+        let err = manager
+            .restore("abcd1234", "claude-1", base, false)
+            .expect_err("Should refuse to discard commits");
+        assert!(format!("{:#}", err).contains(&short_id(tip)));
+        assert!(worktree.path.join("work.rs").exists());
+
+        // Existing worktree is moved back when forced
+        let restored = manager
+            .restore("abcd1234", "claude-1", base, true)
+            .expect("Should reset worktree");
+        assert!(!restored.path.join("work.rs").exists());
+        assert_eq!(
+            repo.find_branch(&worktree.branch, BranchType::Local)
+                .unwrap()
+                .get()
+                .target(),
+            Some(base)
+        );
+
+        std::fs::write(restored.path.join("README.md"), "edited\n").unwrap();
+        assert!(manager.restore("abcd1234", "claude-1", tip, false).is_err());
+
+        // A deleted worktree is not re-created behind its branch
+        std::fs::write(restored.path.join("README.md"), "hello\n").unwrap();
+        manager
+            .restore("abcd1234", "claude-1", tip, false)
+            .expect("Should fast-forward worktree");
+        manager
+            .remove(&repo, &worktree, false)
+            .expect("Should remove worktree");
+        assert!(manager
+            .restore("abcd1234", "claude-1", base, false)
+            .is_err());
+        assert_eq!(
+            repo.find_branch(&worktree.branch, BranchType::Local)
+                .unwrap()
+                .get()
+                .target(),
+            Some(tip)
+        );
+    }
+
+    #[test]
+    fn test_registered_and_reattach() {
+        let temp_dir = TempDir::new().expect("Should create temp dir");
+        let repo = init_repo(temp_dir.path());
+        let manager = WorktreeManager::new(
+            temp_dir.path(),
+            temp_dir.path().join(".agentcrew/worktrees"),
+        );
+        let mut batch = manager
+            .begin(
+                "abcd1234",
+                manager.head_commit().expect("Should resolve HEAD"),
+            )
+            .expect("Should begin batch");
+        let worktree = batch.create("claude-1").expect("Should create worktree");
+        batch.commit();
+
+        let registered = manager.registered(&repo).expect("Should list worktrees");
+        assert_eq!(registered.len(), 1);
+        assert_eq!(registered[0].name, worktree.worktree_name);
+        assert!(registered[0].exists);
+        assert!(manager.reattach(&repo, &worktree).is_err());
+
+        // Git forgets the worktree while its directory and changes survive
+        std::fs::write(worktree.path.join("README.md"), "edited\n").unwrap();
+        std::fs::remove_dir_all(repo.path().join("worktrees")).unwrap();
+        assert!(manager.registered(&repo).unwrap().is_empty());
+
+        manager
+            .reattach(&repo, &worktree)
+            .expect("Should register worktree again");
+        assert_eq!(manager.registered(&repo).unwrap().len(), 1);
+        let checkout = Repository::open(&worktree.path).expect("Should open worktree");
+        assert_eq!(
+            checkout.head().unwrap().shorthand(),
+            Some("agentcrew/abcd1234/claude-1")
+        );
+        assert_eq!(GitUtils::changed_files(&worktree.path).unwrap(), 1);
+
+        std::fs::remove_dir_all(&worktree.path).unwrap();
+        let registered = manager.registered(&repo).unwrap();
+        assert!(!registered[0].exists);
+    }
+}
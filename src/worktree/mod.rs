@@ -0,0 +1,7 @@
+//! Git worktree management for agent instances
+
+mod checkpoint;
+mod manager;
+
+pub use checkpoint::{checkpoint, rollback, Checkpoint};
+pub use manager::{AgentWorktree, RegisteredWorktree, WorktreeBatch, WorktreeManager};